@@ -0,0 +1,92 @@
+//! Arrow record batches and Parquet files of the declared structs
+//!
+//! The Arrow schema is inferred from the declaration, the rows are the structs and the columns are the JSON keys,
+//! nested structs are struct columns and arrays are list columns:
+//!
+//! ```rust
+//! use native_json::arrow;
+//!
+//! let batch = arrow::to_record_batch(&students)?;
+//! let students: Vec<Student> = arrow::from_record_batch(&batch)?;
+//!
+//! // requires the `parquet` feature
+//! arrow::write_parquet(File::create("students.parquet")?, &students)?;
+//! let students: Vec<Student> = arrow::read_parquet(File::open("students.parquet")?)?;
+//! ```
+use crate::{Error, Result};
+use ::arrow::datatypes::{FieldRef, Schema};
+use ::arrow::record_batch::RecordBatch;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_arrow::schema::{SchemaLike, TracingOptions};
+
+impl From<serde_arrow::Error> for Error {
+    fn from(e: serde_arrow::Error) -> Self {
+        Error::Custom(e.to_string())
+    }
+}
+
+impl From<::arrow::error::ArrowError> for Error {
+    fn from(e: ::arrow::error::ArrowError) -> Self {
+        Error::Custom(e.to_string())
+    }
+}
+
+#[cfg(feature = "parquet")]
+impl From<::parquet::errors::ParquetError> for Error {
+    fn from(e: ::parquet::errors::ParquetError) -> Self {
+        Error::Custom(e.to_string())
+    }
+}
+
+// columns of the declaration, maps are Arrow maps rather than structs
+fn fields<T: DeserializeOwned>() -> Result<Vec<FieldRef>> {
+    let options = TracingOptions::default()
+        .allow_null_fields(true)
+        .map_as_struct(false);
+    Ok(Vec::<FieldRef>::from_type::<T>(options)?)
+}
+
+/// Arrow schema of the declared struct
+pub fn schema<T: DeserializeOwned>() -> Result<Schema> {
+    Ok(Schema::new(fields::<T>()?))
+}
+
+/// Record batch of the rows, the schema is inferred from the declaration
+pub fn to_record_batch<T: Serialize + DeserializeOwned>(rows: &[T]) -> Result<RecordBatch> {
+    Ok(serde_arrow::to_record_batch(&fields::<T>()?, &rows)?)
+}
+
+/// Rows of the record batch
+pub fn from_record_batch<T: DeserializeOwned>(batch: &RecordBatch) -> Result<Vec<T>> {
+    Ok(serde_arrow::from_record_batch(batch)?)
+}
+
+/// Write the rows as a Parquet file of one row group
+#[cfg(feature = "parquet")]
+pub fn write_parquet<T, W>(writer: W, rows: &[T]) -> Result<()>
+where
+    T: Serialize + DeserializeOwned,
+    W: std::io::Write + Send,
+{
+    let batch = to_record_batch(rows)?;
+    let mut writer = ::parquet::arrow::ArrowWriter::try_new(writer, batch.schema(), None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Read the rows of a Parquet file, e.g. `File` or `bytes::Bytes`
+#[cfg(feature = "parquet")]
+pub fn read_parquet<T, R>(reader: R) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+    R: ::parquet::file::reader::ChunkReader + 'static,
+{
+    let builder = ::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(reader)?;
+    let mut rows = Vec::new();
+    for batch in builder.build()? {
+        rows.extend(from_record_batch::<T>(&batch?)?);
+    }
+    Ok(rows)
+}
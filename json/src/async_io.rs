@@ -0,0 +1,163 @@
+//! Asynchronous file I/O with tokio
+//!
+//! The files are read and written by `tokio::fs`, the documents are (de)serialized in memory.
+//! The `_blocking` variants run the whole work on the blocking thread pool, for very large documents
+//! which would stall the runtime while they are (de)serialized:
+//!
+//! ```rust
+//! let config: Config = native_json::read_async("config.json").await?;
+//! native_json::write_async("config.json", &config).await?;
+//! let state: State = native_json::read_async_blocking("state.json").await?;
+//! ```
+use crate::{error, Error, Result, Writer};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+/// Deserialize from file asynchronously
+pub async fn read_async<T, P: AsRef<Path>>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let bytes = tokio::fs::read(path).await?;
+    error::from_slice(&crate::compress::decompress_bytes(bytes)?)
+}
+
+/// Serialize into file asynchronously
+pub async fn write_async<T, P: AsRef<Path>>(path: P, value: &T) -> Result<()>
+where
+    T: Serialize,
+{
+    let mut bytes = Vec::new();
+    crate::writer(&path).encode(&mut bytes, value)?;
+    Ok(tokio::fs::write(path, bytes).await?)
+}
+
+fn join_error(e: tokio::task::JoinError) -> Error {
+    Error::Io(io::Error::other(e))
+}
+
+/// Deserialize from file on the blocking thread pool
+pub async fn read_async_blocking<T, P: AsRef<Path>>(path: P) -> Result<T>
+where
+    T: DeserializeOwned + Send + 'static,
+{
+    let path = path.as_ref().to_path_buf();
+    let task = tokio::task::spawn_blocking(move || crate::read(path));
+    task.await.map_err(join_error)?
+}
+
+/// Serialize into file on the blocking thread pool
+pub async fn write_async_blocking<T, P: AsRef<Path>>(path: P, value: Arc<T>) -> Result<()>
+where
+    T: Serialize + Send + Sync + 'static,
+{
+    let path: PathBuf = path.as_ref().to_path_buf();
+    let task = tokio::task::spawn_blocking(move || crate::write(path, value.as_ref()));
+    task.await.map_err(join_error)?
+}
+
+impl<'a> Writer<'a> {
+    /// Write the value into file asynchronously
+    pub async fn write_async<T>(&self, value: &T) -> Result<()>
+    where
+        T: Serialize,
+    {
+        let bytes = match self.append {
+            true => self.line(value)?,
+            false => {
+                let mut bytes = Vec::new();
+                self.encode(&mut bytes, value)?;
+                bytes
+            }
+        };
+        if self.create_dirs {
+            if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+        }
+        let _lock = match self.exclusive {
+            true => {
+                let path = self.path.to_path_buf();
+                let task = tokio::task::spawn_blocking(move || crate::lock(&path, true));
+                Some(task.await.map_err(join_error)??)
+            }
+            false => None,
+        };
+        if self.backup > 0 {
+            let (path, backup) = (self.path.to_path_buf(), self.backup);
+            let task = tokio::task::spawn_blocking(move || crate::rotate(&path, backup));
+            task.await.map_err(join_error)??;
+        }
+        if self.append {
+            let mut file = tokio::fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(self.path)
+                .await?;
+            if let Some(permissions) = crate::permissions(self.mode) {
+                file.set_permissions(permissions).await?;
+            }
+            // a single write, so the lines of concurrent appenders do not interleave
+            file.write_all(&bytes).await?;
+            if let Some(f) = &self.progress {
+                f(bytes.len() as u64, Some(bytes.len() as u64));
+            }
+            return Ok(file.flush().await?);
+        }
+        if !self.atomic {
+            let mut file = tokio::fs::File::create(self.path).await?;
+            if let Some(permissions) = crate::permissions(self.mode) {
+                file.set_permissions(permissions).await?;
+            }
+            self.write_observed(&mut file, &bytes).await?;
+            return Ok(file.flush().await?);
+        }
+        let temp = crate::temp_path(self.path);
+        let result = async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&temp)
+                .await?;
+            if let Some(permissions) = crate::permissions(self.mode) {
+                file.set_permissions(permissions).await?;
+            } else if let Ok(meta) = tokio::fs::metadata(self.path).await {
+                file.set_permissions(meta.permissions()).await?;
+            }
+            self.write_observed(&mut file, &bytes).await?;
+            file.sync_all().await?;
+            tokio::fs::rename(&temp, self.path).await
+        }
+        .await;
+        if result.is_err() {
+            let _ = tokio::fs::remove_file(&temp).await;
+        }
+        Ok(result?)
+    }
+
+    // write the bytes through the progress observer if any, it is called as by `write`
+    async fn write_observed<W: AsyncWrite + Unpin>(
+        &self,
+        file: &mut W,
+        bytes: &[u8],
+    ) -> io::Result<()> {
+        let observer = match &self.progress {
+            Some(f) => f,
+            None => return file.write_all(bytes).await,
+        };
+        let mut done = 0;
+        for chunk in bytes.chunks(crate::progress::STEP as usize) {
+            file.write_all(chunk).await?;
+            done += chunk.len() as u64;
+            observer(done, None);
+        }
+        if bytes.is_empty() {
+            observer(0, None);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,206 @@
+//! axum extractor and response of the declared structs
+//!
+//! [Json] is the drop-in replacement of `axum::Json`, a rejected body is answered with the field path and the
+//! expected type:
+//!
+//! ```rust
+//! use native_json::axum::Json;
+//!
+//! async fn create(Json(user): Json<User>) -> Json<User> {
+//!     Json(user)
+//! }
+//! ```
+//!
+//! ```text
+//! HTTP/1.1 422 Unprocessable Entity
+//!
+//! {"error":"students[3].age: invalid type: string \"x\", expected u16 at line 1 column 42","path":"students[3].age","expected":"u16","line":1,"column":42}
+//! ```
+//!
+//! The server errors such as `Error::Io` are answered with the status reason only, e.g.
+//! `{"error":"Internal Server Error"}`.
+use crate::{error, Error};
+use ::axum::body::Bytes;
+use ::axum::extract::{FromRequest, Request};
+use ::axum::http::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use ::axum::http::StatusCode;
+use ::axum::response::{IntoResponse, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt;
+
+/// JSON body of the request or the response
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+/// Rejection of [Json], the response is the status with the error body
+#[derive(Debug)]
+pub struct Rejection {
+    pub status: StatusCode,
+    pub error: Error,
+}
+
+// error body of the rejection, the unknown members are omitted
+#[derive(Serialize)]
+struct Body<'a> {
+    error: String,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    path: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expected: Option<String>,
+    #[serde(skip_serializing_if = "crate::is_default")]
+    line: usize,
+    #[serde(skip_serializing_if = "crate::is_default")]
+    column: usize,
+}
+
+// `application/json` or `application/*+json`
+fn is_json(headers: &HeaderMap) -> bool {
+    let content_type = match headers.get(CONTENT_TYPE).and_then(|x| x.to_str().ok()) {
+        Some(content_type) => content_type,
+        None => return false,
+    };
+    let mime = content_type.split(';').next().unwrap_or_default().trim();
+    let mime = mime.to_ascii_lowercase();
+    match mime.strip_prefix("application/") {
+        Some(subtype) => subtype == "json" || subtype.ends_with("+json"),
+        None => false,
+    }
+}
+
+impl Rejection {
+    pub fn new(status: StatusCode, error: Error) -> Self {
+        Self { status, error }
+    }
+
+    // the expected type of the type error, e.g. `u16` of `invalid type: string "x", expected u16`
+    fn expected(message: &str) -> Option<&str> {
+        let (_, expected) = message.split_once(", expected ")?;
+        Some(expected.split(" at line ").next().unwrap_or(expected))
+    }
+}
+
+impl From<Error> for Rejection {
+    fn from(error: Error) -> Self {
+        let status = match &error {
+            Error::Syntax { .. } => StatusCode::BAD_REQUEST,
+            Error::Limit { limit, .. } if *limit == "max_bytes" => StatusCode::PAYLOAD_TOO_LARGE,
+            Error::Limit { .. } => StatusCode::BAD_REQUEST,
+            Error::Type { .. } | Error::Validation(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::Status { status, .. } => {
+                StatusCode::from_u16(*status).unwrap_or(StatusCode::BAD_GATEWAY)
+            }
+            Error::Io(_) | Error::Custom(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self { status, error }
+    }
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)
+    }
+}
+
+impl std::error::Error for Rejection {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+impl IntoResponse for Rejection {
+    fn into_response(self) -> Response {
+        // the server errors are not disclosed to the client, e.g. the file paths of the I/O errors
+        let body = match self.status.is_server_error() {
+            true => {
+                let reason = self.status.canonical_reason().unwrap_or("Server Error");
+                Body {
+                    error: reason.to_owned(),
+                    path: "",
+                    expected: None,
+                    line: 0,
+                    column: 0,
+                }
+            }
+            false => {
+                let error = self.error.to_string();
+                let expected = match self.error {
+                    Error::Type { .. } => Self::expected(&error).map(str::to_owned),
+                    _ => None,
+                };
+                Body {
+                    error,
+                    path: self.error.path(),
+                    expected,
+                    line: self.error.line(),
+                    column: self.error.column(),
+                }
+            }
+        };
+        let json = HeaderValue::from_static("application/json");
+        match serde_json::to_vec(&body) {
+            Ok(bytes) => (self.status, [(CONTENT_TYPE, json)], bytes).into_response(),
+            Err(_) => (self.status, body.error).into_response(),
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        Rejection::from(self).into_response()
+    }
+}
+
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Rejection;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        if !is_json(request.headers()) {
+            let error = Error::custom("expected `Content-Type: application/json`");
+            return Err(Rejection::new(StatusCode::UNSUPPORTED_MEDIA_TYPE, error));
+        }
+        let bytes = match Bytes::from_request(request, state).await {
+            Ok(bytes) => bytes,
+            Err(e) => return Err(Rejection::new(e.status(), Error::custom(e.body_text()))),
+        };
+        match error::from_slice(&bytes) {
+            Ok(value) => Ok(Json(value)),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        match serde_json::to_vec(&self.0) {
+            Ok(body) => {
+                let json = HeaderValue::from_static("application/json");
+                ([(CONTENT_TYPE, json)], body).into_response()
+            }
+            Err(e) => Rejection::new(StatusCode::INTERNAL_SERVER_ERROR, e.into()).into_response(),
+        }
+    }
+}
+
+impl<T> std::ops::Deref for Json<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Json<T> {
+    fn from(value: T) -> Self {
+        Json(value)
+    }
+}
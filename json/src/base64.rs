@@ -0,0 +1,71 @@
+//! Base64 strings for byte fields
+//!
+//! The `bytes` type of declarations is `Vec<u8>` serialized as base64 string, requires the `base64` feature.
+//! Select the alphabet with the `@base64(...)` annotation, the annotation also applies to `Vec<u8>`:
+//!
+//! ```rust
+//! json!{ Blob {
+//!     data: bytes,                      // standard alphabet with padding
+//!     key: bytes @base64(url_safe_no_pad),
+//!     raw: Vec<u8> @base64
+//! }}
+//! ```
+use ::base64::engine::{general_purpose, GeneralPurpose};
+use ::base64::Engine;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+
+fn serialize<T: AsRef<[u8]>, S: Serializer>(
+    engine: &GeneralPurpose,
+    bytes: &T,
+    s: S,
+) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&engine.encode(bytes.as_ref()))
+}
+
+fn deserialize<'de, T: From<Vec<u8>>, D: Deserializer<'de>>(
+    engine: &GeneralPurpose,
+    d: D,
+) -> Result<T, D::Error> {
+    let s = String::deserialize(d)?;
+    let bytes = engine.decode(s.as_bytes()).map_err(D::Error::custom)?;
+    Ok(T::from(bytes))
+}
+
+macro_rules! alphabet {
+    ($(#[$doc:meta])* $name:ident, $engine:ident) => {
+        $(#[$doc])*
+        pub mod $name {
+            use super::*;
+
+            pub fn serialize<T: AsRef<[u8]>, S: Serializer>(bytes: &T, s: S) -> Result<S::Ok, S::Error> {
+                super::serialize(&general_purpose::$engine, bytes, s)
+            }
+
+            pub fn deserialize<'de, T: From<Vec<u8>>, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+                super::deserialize(&general_purpose::$engine, d)
+            }
+        }
+    };
+}
+
+alphabet!(
+    /// `@base64(standard)`: standard alphabet with padding, the default
+    standard,
+    STANDARD
+);
+alphabet!(
+    /// `@base64(standard_no_pad)`: standard alphabet without padding
+    standard_no_pad,
+    STANDARD_NO_PAD
+);
+alphabet!(
+    /// `@base64(url_safe)`: URL-safe alphabet with padding
+    url_safe,
+    URL_SAFE
+);
+alphabet!(
+    /// `@base64(url_safe_no_pad)`: URL-safe alphabet without padding
+    url_safe_no_pad,
+    URL_SAFE_NO_PAD
+);
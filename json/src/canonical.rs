@@ -0,0 +1,78 @@
+//! Canonical JSON (RFC 8785, JSON Canonicalization Scheme)
+//!
+//! Keys are sorted by UTF-16 code units, numbers are formatted as ECMAScript doubles and there is no whitespace,
+//! the output is stable for signatures and content hashes:
+//!
+//! ```rust
+//! let text = json!{ b: 1.0, a: "x" }.canonicalize()?; // {"a":"x","b":1}
+//! ```
+use serde_json::Value;
+
+// ECMAScript Number::toString of a finite double
+fn format_number(f: f64) -> String {
+    if f == 0.0 {
+        return "0".to_owned();
+    }
+    if f < 0.0 {
+        return format!("-{}", format_number(-f));
+    }
+    // shortest round-trip digits, e.g. 1.2345e-7
+    let scientific = format!("{:e}", f);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let digits = mantissa.replace('.', "");
+    let k = digits.len() as i32;
+    let n = exponent.parse::<i32>().unwrap() + 1;
+    if k <= n && n <= 21 {
+        digits + &"0".repeat((n - k) as usize)
+    } else if 0 < n && n <= 21 {
+        format!("{}.{}", &digits[..n as usize], &digits[n as usize..])
+    } else if -6 < n && n <= 0 {
+        format!("0.{}{}", "0".repeat(-n as usize), digits)
+    } else {
+        let sign = if n > 0 { "+" } else { "-" };
+        let e = (n - 1).abs();
+        match k {
+            1 => format!("{}e{}{}", digits, sign, e),
+            _ => format!("{}.{}e{}{}", &digits[..1], &digits[1..], sign, e),
+        }
+    }
+}
+
+fn write_value(value: &Value, out: &mut String) {
+    match value {
+        Value::Null | Value::Bool(_) | Value::String(_) => out.push_str(&value.to_string()),
+        // numbers are IEEE 754 doubles, integers beyond 2^53 are rounded
+        Value::Number(n) => out.push_str(&format_number(n.as_f64().unwrap_or(0.0))),
+        Value::Array(array) => {
+            out.push('[');
+            for (i, item) in array.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_value(item, out);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut pairs: Vec<_> = map.iter().collect();
+            pairs.sort_by(|a, b| a.0.encode_utf16().cmp(b.0.encode_utf16()));
+            out.push('{');
+            for (i, (key, item)) in pairs.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push(':');
+                write_value(item, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+/// Canonical form of the JSON value
+pub fn canonicalize(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(value, &mut out);
+    out
+}
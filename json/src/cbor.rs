@@ -0,0 +1,64 @@
+//! CBOR interop
+//!
+//! The declared structs are encoded as CBOR maps with the JSON keys, e.g. for IoT devices and COSE workflows:
+//!
+//! ```rust
+//! let bytes = reading.to_cbor()?;
+//! let reading = Reading::from_cbor(&bytes)?;
+//! ```
+use crate::{Error, Result};
+use serde::de::{DeserializeOwned, Error as _};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::io;
+
+impl From<ciborium::ser::Error<io::Error>> for Error {
+    fn from(e: ciborium::ser::Error<io::Error>) -> Self {
+        match e {
+            ciborium::ser::Error::Io(e) => Error::Io(e),
+            e => Error::Custom(e.to_string()),
+        }
+    }
+}
+
+impl From<ciborium::de::Error<io::Error>> for Error {
+    fn from(e: ciborium::de::Error<io::Error>) -> Self {
+        match e {
+            ciborium::de::Error::Io(e) => Error::Io(e),
+            ciborium::de::Error::Semantic(_, msg) => Error::Custom(msg),
+            e => Error::Custom(e.to_string()),
+        }
+    }
+}
+
+// the deserializer of ciborium is private, the field path is tracked inside of the value
+struct Tracked<T>(T);
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Tracked<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut track = serde_path_to_error::Track::new();
+        let de = serde_path_to_error::Deserializer::new(deserializer, &mut track);
+        match T::deserialize(de) {
+            Ok(value) => Ok(Tracked(value)),
+            Err(e) => {
+                let path = track.path();
+                match path.iter().next() {
+                    Some(_) => Err(D::Error::custom(format!("{}: {}", path, e))),
+                    None => Err(e),
+                }
+            }
+        }
+    }
+}
+
+/// Encode the value, structs are maps with the JSON keys
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decode the value, the errors report the field path
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    let value: Tracked<T> = ciborium::from_reader(bytes)?;
+    Ok(value.0)
+}
@@ -0,0 +1,106 @@
+//! Compressed files
+//!
+//! [Writer](crate::Writer) compresses by `compress`, or by the extension `.gz` or `.zst` of the file. The reading
+//! functions detect the compression by the magic bytes:
+//!
+//! ```rust
+//! native_json::writer(&"snapshot.json").compress(Compression::Zstd(3)).write(&snapshot)?;
+//! native_json::write("events.json.gz", &events)?;
+//! let snapshot: Snapshot = native_json::read("snapshot.json")?;
+//! ```
+use crate::Result;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+const GZIP: &[u8] = &[0x1f, 0x8b];
+const ZSTD: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression of files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Compression {
+    #[default]
+    None,
+    /// Gzip with the level 0..=9, requires the `gzip` feature
+    #[cfg(feature = "gzip")]
+    Gzip(u32),
+    /// Zstandard with the level 1..=22, requires the `zstd` feature
+    #[cfg(feature = "zstd")]
+    Zstd(i32),
+}
+
+impl Compression {
+    /// Compression of the extension `.gz` or `.zst` with the default level, None for the others
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            #[cfg(feature = "gzip")]
+            Some("gz") => Compression::Gzip(6),
+            #[cfg(feature = "zstd")]
+            Some("zst") => Compression::Zstd(3),
+            _ => Compression::None,
+        }
+    }
+}
+
+/// Write the output of the closure compressed into the writer
+pub(crate) fn compress<W, F>(writer: W, compression: Compression, f: F) -> Result<()>
+where
+    W: Write,
+    F: FnOnce(&mut dyn Write) -> Result<()>,
+{
+    match compression {
+        Compression::None => {
+            let mut writer = writer;
+            f(&mut writer)
+        }
+        #[cfg(feature = "gzip")]
+        Compression::Gzip(level) => {
+            let level = flate2::Compression::new(level);
+            let mut encoder = flate2::write::GzEncoder::new(writer, level);
+            f(&mut encoder)?;
+            encoder.finish()?;
+            Ok(())
+        }
+        #[cfg(feature = "zstd")]
+        Compression::Zstd(level) => {
+            let mut encoder = zstd::Encoder::new(writer, level)?;
+            f(&mut encoder)?;
+            encoder.finish()?;
+            Ok(())
+        }
+    }
+}
+
+/// Decompress the reader by the magic bytes, it is read as is if it is not compressed
+pub fn decompress<'a, R: Read + 'a>(reader: R) -> Result<Box<dyn Read + 'a>> {
+    let mut reader = BufReader::new(reader);
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(GZIP) {
+        #[cfg(feature = "gzip")]
+        return Ok(Box::new(flate2::bufread::MultiGzDecoder::new(reader)));
+        #[cfg(not(feature = "gzip"))]
+        return Err(crate::Error::custom(
+            "gzip compressed, it requires the `gzip` feature",
+        ));
+    }
+    if magic.starts_with(ZSTD) {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(zstd::Decoder::with_buffer(reader)?));
+        #[cfg(not(feature = "zstd"))]
+        return Err(crate::Error::custom(
+            "zstd compressed, it requires the `zstd` feature",
+        ));
+    }
+    Ok(Box::new(reader))
+}
+
+/// Decompress the bytes by the magic bytes
+#[cfg(any(feature = "tokio", feature = "redis"))]
+pub(crate) fn decompress_bytes(bytes: Vec<u8>) -> Result<Vec<u8>> {
+    if !bytes.starts_with(GZIP) && !bytes.starts_with(ZSTD) {
+        return Ok(bytes);
+    }
+    let mut out = Vec::new();
+    decompress(std::io::Cursor::new(bytes))?.read_to_end(&mut out)?;
+    Ok(out)
+}
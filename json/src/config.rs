@@ -0,0 +1,405 @@
+//! Layered configuration
+//!
+//! [Loader] overlays the sources in priority order on the default value of the struct: files, environment variables
+//! and overrides, the later layers win. Objects are merged recursively, the other values are replaced:
+//!
+//! ```rust
+//! let config = Loader::new()
+//!     .file("config.json")
+//!     .optional_file("config.local.json")
+//!     .env("APP") // APP__STYLE__SIZE=14 sets style.size
+//!     .args(std::env::args().skip(1)) // --style.size 14 or --style.size=14
+//!     .set("port", 8080)
+//!     .load::<Config>()?;
+//!
+//! let size = config.style.size;
+//! println!("{}", config.source("style.size").unwrap()); // arg --style.size
+//! ```
+//!
+//! With the `clap` feature, [command] builds a `clap::Command` of the values for `--help` and the validation of the
+//! options, [augment] adds them to an existing command and [Loader::matches] loads the matched values.
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Deref;
+use std::path::PathBuf;
+
+/// The layer which a value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    /// Default value of the struct
+    Default,
+    File(PathBuf),
+    /// Name of the environment variable
+    Env(String),
+    /// Command line option, e.g. `--style.size`
+    Arg(String),
+    Override,
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "file {}", path.display()),
+            Source::Env(name) => write!(f, "env {}", name),
+            Source::Arg(flag) => write!(f, "arg {}", flag),
+            Source::Override => write!(f, "override"),
+        }
+    }
+}
+
+enum Layer {
+    File(PathBuf, bool),
+    Env(String),
+    Args(Vec<String>),
+    Override(String, Value),
+}
+
+/// Loader of the layered configuration
+#[derive(Default)]
+pub struct Loader {
+    layers: Vec<Layer>,
+}
+
+/// The loaded value with the source of every value, keyed by the dotted path, e.g. `style.size`
+#[derive(Debug, Clone)]
+pub struct Loaded<T> {
+    value: T,
+    sources: BTreeMap<String, Source>,
+}
+
+impl<T> Loaded<T> {
+    /// Source of the value at the dotted path, arrays are single values
+    pub fn source(&self, path: &str) -> Option<&Source> {
+        self.sources.get(path)
+    }
+
+    /// Sources of all values, ordered by the dotted path
+    pub fn sources(&self) -> &BTreeMap<String, Source> {
+        &self.sources
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Loaded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl Loader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// JSON file, it is an error if the file does not exist
+    pub fn file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.layers.push(Layer::File(path.into(), true));
+        self
+    }
+
+    /// JSON file which is skipped if it does not exist, e.g. local overrides
+    pub fn optional_file<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.layers.push(Layer::File(path.into(), false));
+        self
+    }
+
+    /// Environment variables `{PREFIX}__{KEY}__{KEY}`, e.g. `APP__STYLE__SIZE=14` sets `style.size`,
+    /// the keys are case-insensitive, the values are parsed as JSON unless the default is a string
+    pub fn env(mut self, prefix: &str) -> Self {
+        self.layers.push(Layer::Env(prefix.to_owned()));
+        self
+    }
+
+    /// Command line options `--style.size 14` or `--style.size=14` without the program name, `--bold` alone is true,
+    /// the values are parsed like the environment variables, an unknown path is an error
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let args = args.into_iter().map(Into::into).collect();
+        self.layers.push(Layer::Args(args));
+        self
+    }
+
+    /// Command line options of the matches of [command] or [augment], the values which are not given are skipped
+    #[cfg(feature = "clap")]
+    pub fn matches(self, matches: &clap::ArgMatches) -> Self {
+        let mut args = Vec::new();
+        for id in matches.ids() {
+            let path = match id.as_str().strip_prefix(ARG) {
+                Some(path) => path,
+                None => continue,
+            };
+            if matches.value_source(id.as_str()) != Some(clap::parser::ValueSource::CommandLine) {
+                continue;
+            }
+            if let Ok(Some(text)) = matches.try_get_one::<String>(id.as_str()) {
+                args.push(format!("--{}={}", path, text));
+            }
+        }
+        self.args(args)
+    }
+
+    /// Override the value at the dotted path, e.g. `set("style.size", 14)`
+    pub fn set<V: Into<Value>>(mut self, path: &str, value: V) -> Self {
+        self.layers
+            .push(Layer::Override(path.to_owned(), value.into()));
+        self
+    }
+
+    /// Override the values of the map, keyed by the dotted paths
+    pub fn overrides<I, K, V>(mut self, map: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<Value>,
+    {
+        for (path, value) in map {
+            self.layers.push(Layer::Override(path.into(), value.into()));
+        }
+        self
+    }
+
+    /// Overlay the layers on the default value and deserialize the result
+    pub fn load<T>(&self) -> Result<Loaded<T>>
+    where
+        T: Serialize + DeserializeOwned + Default,
+    {
+        let mut root = serde_json::to_value(T::default())?;
+        let mut sources = BTreeMap::new();
+        record(&root, "", &Source::Default, &mut sources);
+        for layer in &self.layers {
+            match layer {
+                Layer::File(path, required) => {
+                    if !required && !path.exists() {
+                        continue;
+                    }
+                    let value: Value = crate::read(path)?;
+                    let source = Source::File(path.clone());
+                    overlay(&mut root, "", &value, &source, &mut sources);
+                }
+                Layer::Env(prefix) => {
+                    let head = format!("{}__", prefix);
+                    let mut vars: Vec<_> = std::env::vars()
+                        .filter(|(name, _)| name.starts_with(&head))
+                        .collect();
+                    vars.sort();
+                    for (name, text) in vars {
+                        let keys: Vec<_> = name[head.len()..].split("__").collect();
+                        let path = resolve(&root, &keys);
+                        let value = text_value(&root, &path, text);
+                        let source = Source::Env(name);
+                        set(&mut root, &path, value, &source, &mut sources);
+                    }
+                }
+                Layer::Args(args) => apply_args(&mut root, args, &mut sources)?,
+                Layer::Override(path, value) => {
+                    let path: Vec<_> = path.split('.').map(str::to_owned).collect();
+                    set(
+                        &mut root,
+                        &path,
+                        value.clone(),
+                        &Source::Override,
+                        &mut sources,
+                    );
+                }
+            }
+        }
+        let value = crate::error::from_value(root)?;
+        Ok(Loaded { value, sources })
+    }
+}
+
+// id prefix of the options of the values, the other arguments of the command are ignored
+#[cfg(feature = "clap")]
+const ARG: &str = "config:";
+
+/// Command with an option of every value of the default config, e.g. `--style.size <NUMBER>`, see [Loader::matches]
+#[cfg(feature = "clap")]
+pub fn command<T: Serialize + Default>(name: &str) -> Result<clap::Command> {
+    augment::<T>(clap::Command::new(name.to_owned()))
+}
+
+/// Add an option of every value of the default config to the command, the defaults are shown in the help
+#[cfg(feature = "clap")]
+pub fn augment<T: Serialize + Default>(command: clap::Command) -> Result<clap::Command> {
+    let root = serde_json::to_value(T::default())?;
+    let mut values = BTreeMap::new();
+    leaves(&root, "", &mut values);
+    let mut command = command;
+    for (path, value) in values {
+        let name = match value {
+            Value::Bool(_) => "BOOL",
+            Value::Number(_) => "NUMBER",
+            Value::String(_) => "STRING",
+            _ => "JSON",
+        };
+        let mut arg = clap::Arg::new(format!("{}{}", ARG, path))
+            .long(path)
+            .value_name(name)
+            .help(format!("default: {}", value));
+        // `--bold` alone is true
+        if value.is_boolean() {
+            arg = arg.num_args(0..=1).default_missing_value("true");
+        }
+        command = command.arg(arg);
+    }
+    Ok(command)
+}
+
+// the leaf values by the dotted path
+#[cfg(feature = "clap")]
+fn leaves<'a>(value: &'a Value, path: &str, values: &mut BTreeMap<String, &'a Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                leaves(value, &join(path, key), values);
+            }
+        }
+        _ if path.is_empty() => {}
+        _ => {
+            values.insert(path.to_owned(), value);
+        }
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    match path.is_empty() {
+        true => key.to_owned(),
+        false => format!("{}.{}", path, key),
+    }
+}
+
+// the source of every leaf value
+fn record(value: &Value, path: &str, source: &Source, sources: &mut BTreeMap<String, Source>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                record(value, &join(path, key), source, sources);
+            }
+        }
+        _ => {
+            sources.insert(path.to_owned(), source.clone());
+        }
+    }
+}
+
+// overlay like merge::overlay, the replaced values are recorded
+fn overlay(
+    target: &mut Value,
+    path: &str,
+    value: &Value,
+    source: &Source,
+    sources: &mut BTreeMap<String, Source>,
+) {
+    match (target, value) {
+        (Value::Object(map), Value::Object(other)) => {
+            for (key, value) in other {
+                let target = map.entry(key.as_str()).or_insert(Value::Null);
+                overlay(target, &join(path, key), value, source, sources);
+            }
+        }
+        (target, value) => {
+            let prefix = format!("{}.", path);
+            sources.retain(|k, _| k != path && !k.starts_with(&prefix));
+            *target = value.clone();
+            record(value, path, source, sources);
+        }
+    }
+}
+
+// replace the value at the path, the missing objects are created
+fn set(
+    root: &mut Value,
+    path: &[String],
+    value: Value,
+    source: &Source,
+    sources: &mut BTreeMap<String, Source>,
+) {
+    let mut patch = value;
+    for key in path.iter().rev() {
+        patch = Value::Object(Map::from_iter([(key.clone(), patch)]));
+    }
+    overlay(root, "", &patch, source, sources);
+}
+
+// keys of the existing objects which match case-insensitively, the others are lowercase
+fn resolve(root: &Value, keys: &[&str]) -> Vec<String> {
+    let mut node = Some(root);
+    let mut path = Vec::new();
+    for key in keys {
+        let found = node
+            .and_then(Value::as_object)
+            .and_then(|map| map.keys().find(|k| k.eq_ignore_ascii_case(key)));
+        let key = found.cloned().unwrap_or_else(|| key.to_ascii_lowercase());
+        node = node.and_then(|n| n.get(&key));
+        path.push(key);
+    }
+    path
+}
+
+// strings stay strings, e.g. a password "1234", the others are parsed as JSON if possible
+fn text_value(root: &Value, path: &[String], text: String) -> Value {
+    let current = path.iter().try_fold(root, |node, key| node.get(key));
+    match current {
+        Some(Value::String(_)) => Value::String(text),
+        _ => serde_json::from_str(&text).unwrap_or(Value::String(text)),
+    }
+}
+
+// `--key.key value`, `--key.key=value`, or `--key.key` without value
+fn parse_args(args: &[String]) -> Result<Vec<(String, Option<String>)>> {
+    let mut options = Vec::new();
+    let mut args = args.iter().peekable();
+    while let Some(arg) = args.next() {
+        let option = match arg.strip_prefix("--") {
+            Some(option) if !option.is_empty() => option,
+            _ => return Err(Error::Custom(format!("unexpected argument `{}`", arg))),
+        };
+        let (path, text) = match option.split_once('=') {
+            Some((path, text)) => (path, Some(text.to_owned())),
+            None => match args.peek() {
+                Some(next) if !next.starts_with("--") => (option, args.next().cloned()),
+                _ => (option, None),
+            },
+        };
+        options.push((path.to_owned(), text));
+    }
+    Ok(options)
+}
+
+// override the values by the command line options, the paths must exist
+pub(crate) fn apply_args(
+    root: &mut Value,
+    args: &[String],
+    sources: &mut BTreeMap<String, Source>,
+) -> Result<()> {
+    for (option, text) in parse_args(args)? {
+        let path: Vec<_> = option.split('.').map(str::to_owned).collect();
+        if path
+            .iter()
+            .try_fold(&*root, |node, key| node.get(key))
+            .is_none()
+        {
+            return Err(Error::Custom(format!("unknown option `--{}`", option)));
+        }
+        let value = match text {
+            Some(text) => text_value(root, &path, text),
+            None => Value::Bool(true),
+        };
+        let source = Source::Arg(format!("--{}", option));
+        set(root, &path, value, &source, sources);
+    }
+    Ok(())
+}
@@ -0,0 +1,437 @@
+//! CSV of the flat declared structs
+//!
+//! The rows are the structs and the columns are the JSON keys of the scalar fields, nested structs are flattened
+//! into dotted columns by the option, e.g. `address.city`:
+//!
+//! ```rust
+//! use native_json::csv::{self, CsvOptions};
+//!
+//! csv::to_csv(File::create("students.csv")?, &students)?;
+//! let students: Vec<Student> = csv::from_csv(File::open("students.csv")?)?;
+//!
+//! let options = CsvOptions::new().flatten(true).delimiter(b';');
+//! csv::to_csv_with(&mut buffer, &students, &options)?;
+//! ```
+//!
+//! The empty cells are None or the default values, e.g. `0` and `false`, so the optional fields read back as written.
+use crate::{Error, Result};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Unexpected, Visitor,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::io;
+
+impl From<::csv::Error> for Error {
+    fn from(e: ::csv::Error) -> Self {
+        match e.is_io_error() {
+            true => match e.into_kind() {
+                ::csv::ErrorKind::Io(e) => Error::Io(e),
+                _ => unreachable!(),
+            },
+            false => Error::Custom(e.to_string()),
+        }
+    }
+}
+
+/// Options of [to_csv_with] and [from_csv_with]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvOptions {
+    flatten: bool,
+    delimiter: u8,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CsvOptions {
+    /// Comma separated, nested structs are not allowed
+    pub fn new() -> Self {
+        Self {
+            flatten: false,
+            delimiter: b',',
+        }
+    }
+
+    /// Flatten one level of nested structs into dotted columns, e.g. `address.city`
+    pub fn flatten(mut self, flatten: bool) -> Self {
+        self.flatten = flatten;
+        self
+    }
+
+    /// Delimiter of the cells, e.g. `b';'` or `b'\t'`
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+}
+
+// serialized value in the order of the declaration
+enum Node {
+    Null,
+    Scalar(String),
+    Array,
+    Object(Vec<(String, Node)>),
+}
+
+impl<'de> Deserialize<'de> for Node {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct NodeVisitor;
+
+        impl<'de> Visitor<'de> for NodeVisitor {
+            type Value = Node;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("any JSON value")
+            }
+
+            fn visit_unit<E>(self) -> Result<Node, E> {
+                Ok(Node::Null)
+            }
+
+            fn visit_bool<E>(self, v: bool) -> Result<Node, E> {
+                Ok(Node::Scalar(v.to_string()))
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Node, E> {
+                Ok(Node::Scalar(v.to_string()))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Node, E> {
+                Ok(Node::Scalar(v.to_string()))
+            }
+
+            // the same text as JSON, e.g. `1.0`
+            fn visit_f64<E>(self, v: f64) -> Result<Node, E> {
+                let number = serde_json::Number::from_f64(v);
+                Ok(number.map_or(Node::Null, |x| Node::Scalar(x.to_string())))
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Node, E> {
+                Ok(Node::Scalar(v.to_owned()))
+            }
+
+            fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Node, A::Error> {
+                while seq.next_element::<de::IgnoredAny>()?.is_some() {}
+                Ok(Node::Array)
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Node, A::Error> {
+                let mut pairs = Vec::new();
+                while let Some(pair) = map.next_entry()? {
+                    pairs.push(pair);
+                }
+                Ok(Node::Object(pairs))
+            }
+        }
+
+        deserializer.deserialize_any(NodeVisitor)
+    }
+}
+
+fn not_scalar(row: usize, key: &str) -> Error {
+    Error::Custom(format!("[{}].{}: expected a scalar value", row, key))
+}
+
+/// Write the rows with a header, see [to_csv_with]
+pub fn to_csv<W: io::Write, T: Serialize>(writer: W, rows: &[T]) -> Result<()> {
+    to_csv_with(writer, rows, &CsvOptions::new())
+}
+
+/// Write the rows with a header of the JSON keys, the skipped optional fields are empty cells
+pub fn to_csv_with<W: io::Write, T: Serialize>(
+    writer: W,
+    rows: &[T],
+    options: &CsvOptions,
+) -> Result<()> {
+    let mut columns: Vec<String> = Vec::new();
+    let mut records = Vec::new();
+    for (i, row) in rows.iter().enumerate() {
+        let pairs = match serde_json::from_str(&serde_json::to_string(row)?)? {
+            Node::Object(pairs) => pairs,
+            _ => return Err(Error::Custom(format!("[{}]: expected an object", i))),
+        };
+        let mut cells = Vec::new();
+        for (key, node) in pairs {
+            match node {
+                Node::Null => cells.push((key, String::new())),
+                Node::Scalar(s) => cells.push((key, s)),
+                Node::Object(nested) if options.flatten => {
+                    for (child, node) in nested {
+                        let column = format!("{}.{}", key, child);
+                        match node {
+                            Node::Null => cells.push((column, String::new())),
+                            Node::Scalar(s) => cells.push((column, s)),
+                            _ => return Err(not_scalar(i, &column)),
+                        }
+                    }
+                }
+                _ => return Err(not_scalar(i, &key)),
+            }
+        }
+        // the columns in the order of their first appearance
+        for (column, _) in &cells {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+        records.push(cells);
+    }
+
+    let mut writer = ::csv::WriterBuilder::new()
+        .delimiter(options.delimiter)
+        .from_writer(writer);
+    if !columns.is_empty() {
+        writer.write_record(&columns)?;
+    }
+    for cells in records {
+        let record = columns.iter().map(|column| {
+            let cell = cells.iter().find(|x| &x.0 == column);
+            cell.map_or("", |x| x.1.as_str())
+        });
+        writer.write_record(record)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read the rows of the header, see [from_csv_with]
+pub fn from_csv<R: io::Read, T: DeserializeOwned>(reader: R) -> Result<Vec<T>> {
+    from_csv_with(reader, &CsvOptions::new())
+}
+
+/// Read the rows by the header of the JSON keys, the errors report the field path and the line
+pub fn from_csv_with<R: io::Read, T: DeserializeOwned>(
+    reader: R,
+    options: &CsvOptions,
+) -> Result<Vec<T>> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .delimiter(options.delimiter)
+        .from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record?;
+        let mut row = Row::default();
+        for (column, cell) in headers.iter().zip(record.iter()) {
+            match column.split_once('.') {
+                Some((key, child)) if options.flatten => {
+                    row.nested(key).push(child, Field::Cell(cell))
+                }
+                _ => row.push(column, Field::Cell(cell)),
+            }
+        }
+        let line = record.position().map_or(0, |x| x.line() as usize);
+        match serde_path_to_error::deserialize(row) {
+            Ok(value) => rows.push(value),
+            Err(e) => {
+                let mut e = Error::from(e);
+                if let Error::Type { line: l, .. } = &mut e {
+                    *l = line;
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(rows)
+}
+
+enum Field<'a> {
+    Cell(&'a str),
+    Row(Row<'a>),
+}
+
+// cells of a record by the keys
+#[derive(Default)]
+struct Row<'a> {
+    fields: Vec<(&'a str, Field<'a>)>,
+}
+
+impl<'a> Row<'a> {
+    fn push(&mut self, key: &'a str, field: Field<'a>) {
+        self.fields.push((key, field));
+    }
+
+    // the nested row of the dotted columns, an empty cell of the same key is dropped
+    fn nested(&mut self, key: &'a str) -> &mut Row<'a> {
+        self.fields
+            .retain(|x| x.0 != key || matches!(x.1, Field::Row(_) | Field::Cell("")));
+        let i = match self
+            .fields
+            .iter()
+            .position(|x| x.0 == key && matches!(x.1, Field::Row(_)))
+        {
+            Some(i) => i,
+            None => {
+                self.fields.push((key, Field::Row(Row::default())));
+                self.fields.len() - 1
+            }
+        };
+        match &mut self.fields[i].1 {
+            Field::Row(row) => row,
+            Field::Cell(_) => unreachable!(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.fields.iter().all(|x| match &x.1 {
+            Field::Cell(cell) => cell.is_empty(),
+            Field::Row(row) => row.is_empty(),
+        })
+    }
+}
+
+impl<'de> Deserializer<'de> for Row<'de> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(Fields {
+            fields: self.fields.into_iter(),
+            value: None,
+        })
+    }
+
+    // None if all the cells are empty
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.is_empty() {
+            true => visitor.visit_none(),
+            false => visitor.visit_some(self),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct Fields<'de> {
+    fields: std::vec::IntoIter<(&'de str, Field<'de>)>,
+    value: Option<Field<'de>>,
+}
+
+impl<'de> MapAccess<'de> for Fields<'de> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.fields.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(key.into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value.take() {
+            Some(Field::Cell(cell)) => seed.deserialize(Cell(cell)),
+            Some(Field::Row(row)) => seed.deserialize(row),
+            None => Err(de::Error::custom("value is missing")),
+        }
+    }
+}
+
+// text of a cell, which is converted to the type of the field
+struct Cell<'de>(&'de str);
+
+impl Cell<'_> {
+    fn parse<T: std::str::FromStr + Default>(
+        &self,
+        expected: &dyn de::Expected,
+    ) -> Result<T, serde_json::Error> {
+        if self.0.is_empty() {
+            return Ok(T::default());
+        }
+        self.0
+            .trim()
+            .parse()
+            .map_err(|_| de::Error::invalid_value(Unexpected::Str(self.0), expected))
+    }
+}
+
+macro_rules! deserialize_number {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                let value = self.parse(&visitor)?;
+                visitor.$visit(value)
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Cell<'de> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.is_empty() {
+            true => visitor.visit_unit(),
+            false => visitor.visit_borrowed_str(self.0),
+        }
+    }
+
+    deserialize_number! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.0)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        match self.0.is_empty() {
+            true => visitor.visit_none(),
+            false => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // unit variants by the names
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+
+    serde::forward_to_deserialize_any! {
+        char bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
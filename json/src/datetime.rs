@@ -0,0 +1,133 @@
+//! Date & time formats for declared fields
+//!
+//! Select a format per field with the `@format(...)` annotation, requires the `chrono` or `time` feature:
+//!
+//! ```rust
+//! json!{ Event {
+//!     created_at: DateTime<Utc>,                  // RFC3339 string, chrono default
+//!     updated_at: DateTime<Utc> @format(unix),    // unix seconds
+//!     expires_at: OffsetDateTime @format(unix_ms) // unix milliseconds
+//! }}
+//! ```
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Date & time types which can be (de)serialized by the formats of this module
+pub trait Timestamp: Sized {
+    /// RFC3339 string
+    fn to_rfc3339(&self) -> String;
+
+    /// Parse from RFC3339 string
+    fn from_rfc3339(s: &str) -> Result<Self, String>;
+
+    /// Milliseconds since the unix epoch
+    fn to_unix_ms(&self) -> i64;
+
+    /// Build from milliseconds since the unix epoch
+    fn from_unix_ms(ms: i64) -> Option<Self>;
+}
+
+#[cfg(feature = "chrono")]
+impl Timestamp for chrono::DateTime<chrono::Utc> {
+    fn to_rfc3339(&self) -> String {
+        self.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+    }
+
+    fn from_rfc3339(s: &str) -> Result<Self, String> {
+        let t = chrono::DateTime::parse_from_rfc3339(s).map_err(|e| e.to_string())?;
+        Ok(t.with_timezone(&chrono::Utc))
+    }
+
+    fn to_unix_ms(&self) -> i64 {
+        self.timestamp_millis()
+    }
+
+    fn from_unix_ms(ms: i64) -> Option<Self> {
+        chrono::DateTime::from_timestamp_millis(ms)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl Timestamp for chrono::DateTime<chrono::FixedOffset> {
+    fn to_rfc3339(&self) -> String {
+        self.to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true)
+    }
+
+    fn from_rfc3339(s: &str) -> Result<Self, String> {
+        chrono::DateTime::parse_from_rfc3339(s).map_err(|e| e.to_string())
+    }
+
+    fn to_unix_ms(&self) -> i64 {
+        self.timestamp_millis()
+    }
+
+    fn from_unix_ms(ms: i64) -> Option<Self> {
+        let t = chrono::DateTime::from_timestamp_millis(ms)?;
+        Some(t.fixed_offset())
+    }
+}
+
+#[cfg(feature = "time")]
+impl Timestamp for time::OffsetDateTime {
+    fn to_rfc3339(&self) -> String {
+        let format = &time::format_description::well_known::Rfc3339;
+        self.format(format).unwrap_or_default()
+    }
+
+    fn from_rfc3339(s: &str) -> Result<Self, String> {
+        let format = &time::format_description::well_known::Rfc3339;
+        time::OffsetDateTime::parse(s, format).map_err(|e| e.to_string())
+    }
+
+    fn to_unix_ms(&self) -> i64 {
+        (self.unix_timestamp_nanos() / 1_000_000) as i64
+    }
+
+    fn from_unix_ms(ms: i64) -> Option<Self> {
+        time::OffsetDateTime::from_unix_timestamp_nanos(ms as i128 * 1_000_000).ok()
+    }
+}
+
+/// `@format(rfc3339)`: "2024-01-02T03:04:05Z"
+pub mod rfc3339 {
+    use super::*;
+
+    pub fn serialize<T: Timestamp, S: Serializer>(t: &T, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&t.to_rfc3339())
+    }
+
+    pub fn deserialize<'de, T: Timestamp, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+        let s = String::deserialize(d)?;
+        T::from_rfc3339(&s).map_err(D::Error::custom)
+    }
+}
+
+/// `@format(unix)`: seconds since the unix epoch
+pub mod unix {
+    use super::*;
+
+    pub fn serialize<T: Timestamp, S: Serializer>(t: &T, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_i64(t.to_unix_ms().div_euclid(1000))
+    }
+
+    pub fn deserialize<'de, T: Timestamp, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+        let secs = i64::deserialize(d)?;
+        let ms = secs.checked_mul(1000);
+        ms.and_then(T::from_unix_ms)
+            .ok_or_else(|| D::Error::custom(format!("timestamp out of range: {secs}")))
+    }
+}
+
+/// `@format(unix_ms)`: milliseconds since the unix epoch
+pub mod unix_ms {
+    use super::*;
+
+    pub fn serialize<T: Timestamp, S: Serializer>(t: &T, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_i64(t.to_unix_ms())
+    }
+
+    pub fn deserialize<'de, T: Timestamp, D: Deserializer<'de>>(d: D) -> Result<T, D::Error> {
+        let ms = i64::deserialize(d)?;
+        T::from_unix_ms(ms).ok_or_else(|| D::Error::custom(format!("timestamp out of range: {ms}")))
+    }
+}
@@ -0,0 +1,36 @@
+//! Content hashing of the canonical JSON
+//!
+//! ```rust
+//! let key = config.digest(Digest::Sha256)?; // requires the `sha2` feature
+//! let key = config.digest(Digest::Blake3)?; // requires the `blake3` feature
+//! ```
+use std::fmt::Write;
+
+/// Hash algorithm
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Digest {
+    #[cfg(feature = "sha2")]
+    Sha256,
+    #[cfg(feature = "blake3")]
+    Blake3,
+}
+
+impl Digest {
+    /// Lowercase hex of the hash
+    pub fn hex(&self, data: &[u8]) -> String {
+        let hash: Vec<u8> = match self {
+            #[cfg(feature = "sha2")]
+            Digest::Sha256 => {
+                use sha2::Digest as _;
+                sha2::Sha256::digest(data).to_vec()
+            }
+            #[cfg(feature = "blake3")]
+            Digest::Blake3 => blake3::hash(data).as_bytes().to_vec(),
+        };
+        hash.iter().fold(String::new(), |mut s, x| {
+            let _ = write!(s, "{:02x}", x);
+            s
+        })
+    }
+}
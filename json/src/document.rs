@@ -0,0 +1,439 @@
+//! Comment-preserving documents
+//!
+//! [Document] keeps the text of a JSON file with comments, the changed values are edited in place, so the comments,
+//! the key order and the blank lines of the user are preserved:
+//!
+//! ```rust
+//! let mut doc = Document::read("settings.json")?;
+//! let mut settings: Settings = doc.get()?;
+//! settings.size = 14;
+//! doc.set(&settings)?;
+//! doc.write("settings.json")?;
+//! ```
+//!
+//! The new keys are appended to the objects, arrays which change the length and values which change the type are
+//! rewritten, on a single line if they were, otherwise with the indentation of the document.
+use crate::parse::{self, ReadOptions};
+use crate::stringify::{self, StringifyOptions};
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::Path;
+
+// byte spans of the values
+struct Node {
+    start: usize,
+    end: usize,
+    kind: Kind,
+}
+
+enum Kind {
+    Object(Vec<Member>),
+    Array(Vec<Node>),
+    Scalar,
+}
+
+struct Member {
+    key: String,
+    start: usize,
+    value: Node,
+    // position of the comma after the value
+    comma: Option<usize>,
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    text: &'a str,
+    i: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip(&mut self) {
+        loop {
+            match (self.bytes.get(self.i), self.bytes.get(self.i + 1)) {
+                (Some(b), _) if b.is_ascii_whitespace() => self.i += 1,
+                (Some(b'/'), Some(b'/')) => {
+                    while self.i < self.bytes.len() && self.bytes[self.i] != b'\n' {
+                        self.i += 1;
+                    }
+                }
+                (Some(b'/'), Some(b'*')) => {
+                    self.i = self.text[self.i + 2..]
+                        .find("*/")
+                        .map_or(self.bytes.len(), |p| self.i + 2 + p + 2);
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip();
+        self.bytes.get(self.i).copied()
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let start = self.i;
+        self.i += 1;
+        while self.i < self.bytes.len() {
+            match self.bytes[self.i] {
+                b'\\' => self.i += 2,
+                b'"' => break,
+                _ => self.i += 1,
+            }
+        }
+        self.i += 1;
+        Ok(serde_json::from_str(&self.text[start..self.i])?)
+    }
+
+    // the comma after an element, None before the end of the container
+    fn comma(&mut self) -> Option<usize> {
+        match self.peek() {
+            Some(b',') => {
+                self.i += 1;
+                Some(self.i - 1)
+            }
+            _ => None,
+        }
+    }
+
+    fn node(&mut self) -> Result<Node> {
+        self.skip();
+        let start = self.i;
+        let kind = match self.peek() {
+            Some(b'{') => {
+                self.i += 1;
+                let mut members = Vec::new();
+                while self.peek() == Some(b'"') {
+                    let start = self.i;
+                    let key = self.string()?;
+                    self.peek();
+                    self.i += 1; // the colon
+                    self.peek();
+                    let value = self.node()?;
+                    let comma = self.comma();
+                    members.push(Member {
+                        key,
+                        start,
+                        value,
+                        comma,
+                    });
+                }
+                Kind::Object(members)
+            }
+            Some(b'[') => {
+                self.i += 1;
+                let mut items = Vec::new();
+                while !matches!(self.peek(), Some(b']') | None) {
+                    items.push(self.node()?);
+                    self.comma();
+                }
+                Kind::Array(items)
+            }
+            Some(b'"') => {
+                self.string()?;
+                return Ok(Node {
+                    start,
+                    end: self.i,
+                    kind: Kind::Scalar,
+                });
+            }
+            Some(_) => {
+                let len = self.bytes[self.i..]
+                    .iter()
+                    .position(|b| b",]}/".contains(b) || b.is_ascii_whitespace())
+                    .unwrap_or(self.bytes.len() - self.i);
+                self.i += len;
+                return Ok(Node {
+                    start,
+                    end: self.i,
+                    kind: Kind::Scalar,
+                });
+            }
+            None => return Err(Error::custom("unexpected end of document")),
+        };
+        // the closing bracket
+        self.peek();
+        self.i += 1;
+        Ok(Node {
+            start,
+            end: self.i,
+            kind,
+        })
+    }
+}
+
+/// A JSON document with comments, which is edited in place
+pub struct Document {
+    text: String,
+    root: Node,
+    value: Value,
+}
+
+struct Edit {
+    start: usize,
+    end: usize,
+    text: String,
+}
+
+impl Document {
+    /// Parse the text, comments and trailing commas are accepted
+    pub fn parse(text: &str) -> Result<Self> {
+        let options = ReadOptions::new()
+            .allow_comments(true)
+            .allow_trailing_commas(true);
+        let value = parse::from_str(text, &options)?;
+        let mut parser = Parser {
+            bytes: text.as_bytes(),
+            text,
+            i: 0,
+        };
+        let root = parser.node()?;
+        Ok(Self {
+            text: text.to_owned(),
+            root,
+            value,
+        })
+    }
+
+    /// Parse the file
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::parse(&crate::read_string(path.as_ref())?)
+    }
+
+    /// Deserialize the document, e.g. into a declared struct
+    pub fn get<T: DeserializeOwned>(&self) -> Result<T> {
+        crate::error::from_value(self.value.clone())
+    }
+
+    /// Update the document to the serialized value, only the changed values are edited
+    pub fn set<T: Serialize>(&mut self, value: &T) -> Result<()> {
+        let value = serde_json::to_value(value)?;
+        let mut edits = Vec::new();
+        self.edit(&self.root, &self.value, &value, &mut edits)?;
+        edits.sort_by_key(|e| (e.start, e.end));
+        let mut text = self.text.clone();
+        for e in edits.iter().rev() {
+            text.replace_range(e.start..e.end, &e.text);
+        }
+        *self = Self::parse(&text)?;
+        Ok(())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Write the document into file atomically
+    pub fn write<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        crate::atomic(path.as_ref(), None, |writer| {
+            Ok(std::io::Write::write_all(writer, self.text.as_bytes())?)
+        })
+    }
+
+    // indentation of the line which contains the position
+    fn indent_at(&self, pos: usize) -> &str {
+        let start = self.text[..pos].rfind('\n').map_or(0, |i| i + 1);
+        let line = &self.text[start..];
+        &line[..line.len() - line.trim_start_matches([' ', '\t']).len()]
+    }
+
+    // true if the position is the first non-whitespace of its line
+    fn starts_line(&self, pos: usize) -> bool {
+        let start = self.text[..pos].rfind('\n').map_or(0, |i| i + 1);
+        self.text[start..pos].trim().is_empty()
+    }
+
+    // one level of indentation, the first indented line of the document
+    fn unit(&self) -> &str {
+        self.text
+            .lines()
+            .map(|line| &line[..line.len() - line.trim_start_matches([' ', '\t']).len()])
+            .find(|indent| !indent.is_empty())
+            .unwrap_or("  ")
+    }
+
+    // the value with the indentation of the document, the lines after the first are indented by the base
+    fn render(&self, value: &Value, base: &str) -> Result<String> {
+        let unit = self.unit();
+        let c = unit.chars().next().unwrap_or(' ');
+        let options = StringifyOptions::new()
+            .indent(unit.chars().count())
+            .indent_char(c);
+        let text = stringify::to_string(value, &options)?;
+        Ok(text.replace('\n', &format!("\n{}", base)))
+    }
+
+    // end of the same-line comment after the position, or the position
+    fn line_end(&self, pos: usize) -> usize {
+        let rest = &self.text[pos..];
+        let trimmed = rest.trim_start_matches([' ', '\t']);
+        match trimmed.starts_with("//") {
+            true => pos + rest.find('\n').unwrap_or(rest.len()),
+            false => pos,
+        }
+    }
+
+    fn edit(&self, node: &Node, old: &Value, new: &Value, edits: &mut Vec<Edit>) -> Result<()> {
+        if old == new {
+            return Ok(());
+        }
+        match (&node.kind, old, new) {
+            (Kind::Object(members), Value::Object(x), Value::Object(y)) if !members.is_empty() => {
+                self.edit_object(node, members, x, y, edits)
+            }
+            (Kind::Array(items), Value::Array(x), Value::Array(y)) if x.len() == y.len() => {
+                for ((item, a), b) in items.iter().zip(x).zip(y) {
+                    self.edit(item, a, b, edits)?;
+                }
+                Ok(())
+            }
+            _ => {
+                // single-line values stay on a single line
+                let text = match self.text[node.start..node.end].contains('\n') {
+                    true => self.render(new, self.indent_at(node.start))?,
+                    false => serde_json::to_string(new)?,
+                };
+                edits.push(Edit {
+                    start: node.start,
+                    end: node.end,
+                    text,
+                });
+                Ok(())
+            }
+        }
+    }
+
+    fn edit_object(
+        &self,
+        node: &Node,
+        members: &[Member],
+        old: &Map<String, Value>,
+        new: &Map<String, Value>,
+        edits: &mut Vec<Edit>,
+    ) -> Result<()> {
+        let mut kept = None;
+        for m in members {
+            let Some(value) = new.get(&m.key) else {
+                edits.push(self.remove(m));
+                continue;
+            };
+            if let Some(a) = old.get(&m.key) {
+                self.edit(&m.value, a, value, edits)?;
+            }
+            kept = Some(m);
+        }
+        // null of the missing keys is not added, e.g. None of Option<T>
+        let added: Vec<_> = new
+            .iter()
+            .filter(|(k, v)| !old.contains_key(*k) && !v.is_null())
+            .collect();
+        let last = members.last().filter(|m| m.comma.is_none());
+        match (kept, last) {
+            // the removed last member leaves the comma of the kept member
+            (Some(m), Some(last)) if added.is_empty() && !std::ptr::eq(m, last) => {
+                if let Some(comma) = m.comma {
+                    edits.push(Edit {
+                        start: comma,
+                        end: comma + 1,
+                        text: String::new(),
+                    });
+                }
+            }
+            (Some(m), _) if !added.is_empty() && m.comma.is_none() => edits.push(Edit {
+                start: m.value.end,
+                end: m.value.end,
+                text: ",".to_owned(),
+            }),
+            _ => {}
+        }
+        if added.is_empty() {
+            return Ok(());
+        }
+
+        let multiline = self.text[node.start..node.end].contains('\n');
+        let indent = match members.first() {
+            Some(m) if multiline => self.indent_at(m.start).to_owned(),
+            _ => format!("{}{}", self.indent_at(node.start), self.unit()),
+        };
+        let mut items = Vec::new();
+        for (key, value) in added {
+            let key = serde_json::to_string(key)?;
+            let value = match multiline {
+                true => self.render(value, &indent)?,
+                false => serde_json::to_string(value)?,
+            };
+            items.push(format!("{}: {}", key, value));
+        }
+        let (at, text) = match (kept, multiline) {
+            (Some(m), true) => {
+                let at = self.line_end(m.comma.map_or(m.value.end, |c| c + 1));
+                (
+                    at,
+                    format!("\n{}{}", indent, items.join(&format!(",\n{}", indent))),
+                )
+            }
+            (Some(m), false) => {
+                let at = m.comma.map_or(m.value.end, |c| c + 1);
+                (at, format!(" {}", items.join(", ")))
+            }
+            (None, true) => {
+                let at = self.line_end(node.start + 1);
+                (
+                    at,
+                    format!("\n{}{}", indent, items.join(&format!(",\n{}", indent))),
+                )
+            }
+            (None, false) => (node.start + 1, format!(" {} ", items.join(", "))),
+        };
+        edits.push(Edit {
+            start: at,
+            end: at,
+            text,
+        });
+        Ok(())
+    }
+
+    // the member with its comma, whole lines are removed with the same-line comment
+    fn remove(&self, m: &Member) -> Edit {
+        let end = m.comma.map_or(m.value.end, |c| c + 1);
+        if !self.starts_line(m.start) {
+            let rest = &self.text[end..];
+            let end = end + rest.len() - rest.trim_start_matches([' ', '\t']).len();
+            return Edit {
+                start: m.start,
+                end,
+                text: String::new(),
+            };
+        }
+        let start = self.text[..m.start].rfind('\n').map_or(0, |i| i + 1);
+        let mut end = self.line_end(end);
+        let rest = &self.text[end..];
+        let trimmed = rest.trim_start_matches([' ', '\t']);
+        if trimmed.starts_with('\n') {
+            end += rest.len() - trimmed.len() + 1;
+        } else if trimmed.starts_with("\r\n") {
+            end += rest.len() - trimmed.len() + 2;
+        } else {
+            // the closing bracket on the same line
+            return Edit {
+                start: m.start,
+                end: end + rest.len() - trimmed.len(),
+                text: String::new(),
+            };
+        }
+        Edit {
+            start,
+            end,
+            text: String::new(),
+        }
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
@@ -0,0 +1,212 @@
+//! Errors of native-json
+//!
+//! The type errors of deserialization report the field path with the line and column:
+//!
+//! ```text
+//! students[3].age: invalid type: string "x", expected u16 at line 17 column 9
+//! ```
+use crate::ValidationErrors;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::error::Category;
+use std::fmt;
+use std::io;
+
+/// Errors of native-json, the kinds can be matched, `anyhow::Error` converts from it by `?`
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// Reading or writing files and streams
+    Io(io::Error),
+    /// Malformed JSON or unexpected end of input
+    Syntax {
+        line: usize,
+        column: usize,
+        source: serde_json::Error,
+    },
+    /// The value does not match the type, or it is not serializable, e.g. `students[3].age`
+    Type {
+        path: String,
+        line: usize,
+        column: usize,
+        source: serde_json::Error,
+    },
+    /// Violations of the validation rules
+    Validation(ValidationErrors),
+    /// The input exceeds a limit of [ReadOptions](crate::ReadOptions), e.g. `max_bytes` or `max_depth`
+    Limit { limit: &'static str, max: usize },
+    /// HTTP response with an error status, the body is captured up to the limit, e.g. the error message of the API
+    Status { status: u16, body: String },
+    /// Other failures, e.g. a JSON Pointer without value
+    Custom(String),
+}
+
+/// Result with [Error]
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl Error {
+    pub fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+
+    /// Field path of the type error, e.g. `students[3].age`, empty if it is not known
+    pub fn path(&self) -> &str {
+        match self {
+            Error::Type { path, .. } => path,
+            _ => "",
+        }
+    }
+
+    /// Line of the input, starting from 1, 0 if it is not known
+    pub fn line(&self) -> usize {
+        match self {
+            Error::Syntax { line, .. } | Error::Type { line, .. } => *line,
+            _ => 0,
+        }
+    }
+
+    /// Column of the input, starting from 1, 0 if it is not known
+    pub fn column(&self) -> usize {
+        match self {
+            Error::Syntax { column, .. } | Error::Type { column, .. } => *column,
+            _ => 0,
+        }
+    }
+
+    // the error of a parsed line of the input, e.g. JSON Lines, the line of the input replaces the line 1 of the text
+    pub(crate) fn at_line(self, n: usize) -> Self {
+        // the message of serde_json ends with the location
+        let relocate = |e: serde_json::Error, column: usize| -> serde_json::Error {
+            let message = e.to_string();
+            let message = message
+                .rsplit_once(" at line ")
+                .map_or(&*message, |(m, _)| m);
+            serde::de::Error::custom(format!("{} at line {} column {}", message, n, column))
+        };
+        match self {
+            Error::Syntax { column, source, .. } => Error::Syntax {
+                line: n,
+                column,
+                source: relocate(source, column),
+            },
+            Error::Type {
+                path,
+                column,
+                source,
+                ..
+            } => Error::Type {
+                path,
+                line: n,
+                column,
+                source: relocate(source, column),
+            },
+            e => e,
+        }
+    }
+
+    fn with_path(path: String, e: serde_json::Error) -> Self {
+        let (line, column) = (e.line(), e.column());
+        match e.classify() {
+            Category::Io => Error::Io(e.into()),
+            Category::Syntax | Category::Eof => Error::Syntax {
+                line,
+                column,
+                source: e,
+            },
+            Category::Data => Error::Type {
+                path,
+                line,
+                column,
+                source: e,
+            },
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{}", e),
+            Error::Syntax { source, .. } => write!(f, "{}", source),
+            Error::Type { path, source, .. } if path.is_empty() => write!(f, "{}", source),
+            Error::Type { path, source, .. } => write!(f, "{}: {}", path, source),
+            Error::Validation(e) => write!(f, "{}", e),
+            Error::Limit { limit, max } => write!(f, "the input exceeds {} of {}", limit, max),
+            Error::Status { status, body } if body.is_empty() => {
+                write!(f, "HTTP status {}", status)
+            }
+            Error::Status { status, body } => write!(f, "HTTP status {}: {}", status, body),
+            Error::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Syntax { source, .. } | Error::Type { source, .. } => Some(source),
+            Error::Validation(e) => Some(e),
+            Error::Limit { .. } | Error::Status { .. } | Error::Custom(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<ValidationErrors> for Error {
+    fn from(e: ValidationErrors) -> Self {
+        Error::Validation(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Self::with_path(String::new(), e)
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for Error {
+    fn from(e: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        // "." of the root
+        let path = match e.path().iter().next() {
+            Some(_) => e.path().to_string(),
+            None => String::new(),
+        };
+        Self::with_path(path, e.into_inner())
+    }
+}
+
+fn deserialize<'de, R, T>(mut de: serde_json::Deserializer<R>) -> Result<T, Error>
+where
+    R: serde_json::de::Read<'de>,
+    T: Deserialize<'de>,
+{
+    let value = serde_path_to_error::deserialize(&mut de)?;
+    de.end()?;
+    Ok(value)
+}
+
+/// Parse from JSON string
+pub fn from_str<'a, T: Deserialize<'a>>(s: &'a str) -> Result<T, Error> {
+    deserialize(serde_json::Deserializer::from_str(s))
+}
+
+/// Parse from JSON bytes
+pub fn from_slice<'a, T: Deserialize<'a>>(v: &'a [u8]) -> Result<T, Error> {
+    deserialize(serde_json::Deserializer::from_slice(v))
+}
+
+/// Parse from the reader
+pub fn from_reader<R: io::Read, T: DeserializeOwned>(reader: R) -> Result<T, Error> {
+    deserialize(serde_json::Deserializer::from_reader(reader))
+}
+
+/// Convert from JSON value
+pub fn from_value<T: DeserializeOwned>(value: serde_json::Value) -> Result<T, Error> {
+    Ok(serde_path_to_error::deserialize(value)?)
+}
@@ -0,0 +1,47 @@
+//! Base fields of `Name extends Base` in the generated schemas
+//!
+//! The base is declared by another `json!`, so its fields are merged when the schema is built, the base requires the same option:
+//!
+//! ```rust
+//! json!{ #[json(schema)] User { id: u64, name: String } }
+//! json!{ #[json(schema)] Admin extends User { permissions: [String] } }
+//!
+//! let schema = Admin::schema(); // id, name and permissions
+//! ```
+use serde_json::Value;
+
+/// JSON Schema with the properties, required keys and definitions of the base, the declared ones take precedence
+pub fn schema(base: Value, mut schema: Value) -> Value {
+    for key in ["properties", "$defs"] {
+        if let Some(Value::Object(base)) = base.get(key) {
+            if schema.get(key).is_none() {
+                schema[key] = Value::Object(Default::default());
+            }
+            if let Some(object) = schema[key].as_object_mut() {
+                for (name, value) in base {
+                    object.entry(name).or_insert_with(|| value.clone());
+                }
+            }
+        }
+    }
+    if let Some(Value::Array(base)) = base.get("required") {
+        let mut required = base.clone();
+        if let Some(Value::Array(own)) = schema.get("required") {
+            required.extend(own.iter().filter(|x| !base.contains(x)).cloned());
+        }
+        schema["required"] = Value::Array(required);
+    }
+    schema
+}
+
+/// Avro record with the fields of the base record first, in the order of serialization
+pub fn avro(base: Value, mut record: Value) -> Value {
+    if let Some(Value::Array(base)) = base.get("fields") {
+        let mut fields = base.clone();
+        if let Some(Value::Array(own)) = record.get("fields") {
+            fields.extend(own.iter().cloned());
+        }
+        record["fields"] = Value::Array(fields);
+    }
+    record
+}
@@ -0,0 +1,165 @@
+//! Flatten to dotted keys and back
+//!
+//! The leaves are keyed by their paths, object keys are joined by `.` and array items are indexed by `[i]`:
+//!
+//! ```rust
+//! use native_json::{flatten, unflatten};
+//!
+//! let map = flatten(&serde_json::json!({ "style": { "size": 14 }, "students": [{ "age": 18 }] }));
+//! // { "students[0].age": 18, "style.size": 14 }
+//! let value = unflatten(map)?;
+//! ```
+//!
+//! The `.`, `[`, `]` and `\` of the keys are escaped by `\`, empty objects and arrays are leaves, so `unflatten`
+//! restores the value exactly.
+use crate::{Error, Result};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+// key of the object in the path
+fn escape(key: &str) -> String {
+    let mut s = String::with_capacity(key.len());
+    for c in key.chars() {
+        if matches!(c, '.' | '[' | ']' | '\\') {
+            s.push('\\');
+        }
+        s.push(c);
+    }
+    s
+}
+
+fn collect(value: &Value, path: String, map: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(object) if !object.is_empty() => {
+            for (key, value) in object {
+                let child = match path.is_empty() {
+                    true => escape(key),
+                    false => format!("{}.{}", path, escape(key)),
+                };
+                collect(value, child, map);
+            }
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (i, item) in items.iter().enumerate() {
+                collect(item, format!("{}[{}]", path, i), map);
+            }
+        }
+        _ => {
+            map.insert(path, value.clone());
+        }
+    }
+}
+
+/// Leaves of the value by the dotted paths, e.g. `style.size` and `students[0].age`, a scalar root is keyed by ""
+pub fn flatten(value: &Value) -> BTreeMap<String, Value> {
+    let mut map = BTreeMap::new();
+    collect(value, String::new(), &mut map);
+    map
+}
+
+pub(crate) enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+pub(crate) fn segments(path: &str) -> Result<Vec<Segment>> {
+    let invalid = || Error::Custom(format!("invalid path `{}`", path));
+    let mut segments = Vec::new();
+    if path.is_empty() {
+        return Ok(segments);
+    }
+    let mut chars = path.chars().peekable();
+    // a key is expected at the start and after `.`
+    let mut key = !path.starts_with('[');
+    loop {
+        if key {
+            let mut s = String::new();
+            while let Some(&c) = chars.peek() {
+                match c {
+                    '.' | '[' => break,
+                    ']' => return Err(invalid()),
+                    '\\' => {
+                        chars.next();
+                        s.push(chars.next().ok_or_else(invalid)?);
+                    }
+                    _ => {
+                        s.push(c);
+                        chars.next();
+                    }
+                }
+            }
+            segments.push(Segment::Key(s));
+        }
+        key = match chars.next() {
+            None => return Ok(segments),
+            Some('.') => true,
+            Some('[') => {
+                let mut digits = String::new();
+                let mut closed = false;
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        closed = true;
+                        break;
+                    }
+                    digits.push(c);
+                }
+                let index = digits.parse().ok().filter(|_| closed);
+                segments.push(Segment::Index(index.ok_or_else(invalid)?));
+                false
+            }
+            _ => return Err(invalid()),
+        };
+    }
+}
+
+// the value at the path, the objects and arrays are created through null, the missing array items are null
+pub(crate) fn entry<'a>(root: &'a mut Value, path: &str) -> Result<&'a mut Value> {
+    let conflict = || Error::Custom(format!("conflicting path `{}`", path));
+    let mut target = root;
+    for segment in segments(path)? {
+        target = match segment {
+            Segment::Key(key) => {
+                if target.is_null() {
+                    *target = Value::Object(Map::new());
+                }
+                match target {
+                    Value::Object(object) => object.entry(key).or_insert(Value::Null),
+                    _ => return Err(conflict()),
+                }
+            }
+            Segment::Index(i) => {
+                if target.is_null() {
+                    *target = Value::Array(Vec::new());
+                }
+                match target {
+                    Value::Array(items) => {
+                        if items.len() <= i {
+                            items.resize(i + 1, Value::Null);
+                        }
+                        &mut items[i]
+                    }
+                    _ => return Err(conflict()),
+                }
+            }
+        };
+    }
+    Ok(target)
+}
+
+/// Restore the value from the leaves by the dotted paths, the missing array items are null
+pub fn unflatten<I, K>(entries: I) -> Result<Value>
+where
+    I: IntoIterator<Item = (K, Value)>,
+    K: AsRef<str>,
+{
+    let mut root = Value::Null;
+    for (path, value) in entries {
+        let path = path.as_ref();
+        let target = entry(&mut root, path)?;
+        if !target.is_null() {
+            return Err(Error::Custom(format!("conflicting path `{}`", path)));
+        }
+        *target = value;
+    }
+    Ok(root)
+}
@@ -0,0 +1,204 @@
+//! HTTP requests with JSON bodies
+//!
+//! The responses are deserialized into the declared structs with the field path of the errors, a response with an
+//! error status is [Error::Status] with the captured body:
+//!
+//! ```rust
+//! let user: User = native_json::get("https://api.example.com/users/1")?;
+//! let created: User = native_json::post("https://api.example.com/users", &new_user)?;
+//!
+//! let options = HttpOptions::new().timeout(Duration::from_secs(5)).header("Authorization", token);
+//! let users: Vec<User> = native_json::http::get_async_with(url, &options).await?;
+//! ```
+//!
+//! The blocking functions must not be called in an async runtime, the async functions require tokio.
+use crate::{error, Error, Result};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, CONTENT_TYPE};
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io;
+use std::time::Duration;
+
+/// Options of the requests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpOptions {
+    timeout: Option<Duration>,
+    headers: Vec<(String, String)>,
+    error_body: usize,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpOptions {
+    /// Timeout of 30 seconds, error bodies are captured up to 4096 bytes
+    pub fn new() -> Self {
+        Self {
+            timeout: Some(Duration::from_secs(30)),
+            headers: Vec::new(),
+            error_body: 4096,
+        }
+    }
+
+    /// Timeout of the whole request, None for no timeout
+    pub fn timeout<D: Into<Option<Duration>>>(mut self, timeout: D) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+
+    /// Add a header, e.g. `Authorization`
+    pub fn header<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Capture the body of an error response up to the bytes, 0 to discard it
+    pub fn error_body(mut self, limit: usize) -> Self {
+        self.error_body = limit;
+        self
+    }
+
+    fn header_map(&self) -> Result<HeaderMap> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static("application/json"));
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| Error::Custom(format!("invalid header name `{}`", name)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|_| Error::Custom(format!("invalid value of header `{}`", name)))?;
+            headers.append(name, value);
+        }
+        Ok(headers)
+    }
+}
+
+fn transport(e: reqwest::Error) -> Error {
+    let kind = match e.is_timeout() {
+        true => io::ErrorKind::TimedOut,
+        false => io::ErrorKind::Other,
+    };
+    Error::Io(io::Error::new(kind, e))
+}
+
+// an empty body is null, e.g. `()` of 204 No Content
+fn decode<T: DeserializeOwned>(status: u16, body: &[u8], options: &HttpOptions) -> Result<T> {
+    if !(200..300).contains(&status) {
+        let body = &body[..body.len().min(options.error_body)];
+        return Err(Error::Status {
+            status,
+            body: String::from_utf8_lossy(body).into_owned(),
+        });
+    }
+    match body.iter().all(u8::is_ascii_whitespace) {
+        true => error::from_slice(b"null"),
+        false => error::from_slice(body),
+    }
+}
+
+fn blocking<T, B>(method: Method, url: &str, body: Option<&B>, options: &HttpOptions) -> Result<T>
+where
+    T: DeserializeOwned,
+    B: Serialize + ?Sized,
+{
+    let client = reqwest::blocking::Client::builder()
+        .timeout(options.timeout)
+        .build()
+        .map_err(transport)?;
+    let mut request = client.request(method, url).headers(options.header_map()?);
+    if let Some(body) = body {
+        request = request
+            .header(CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(body)?);
+    }
+    let response = request.send().map_err(transport)?;
+    let status = response.status().as_u16();
+    let bytes = response.bytes().map_err(transport)?;
+    decode(status, &bytes, options)
+}
+
+async fn nonblocking<T, B>(
+    method: Method,
+    url: &str,
+    body: Option<&B>,
+    options: &HttpOptions,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+    B: Serialize + ?Sized,
+{
+    let mut builder = reqwest::Client::builder();
+    if let Some(timeout) = options.timeout {
+        builder = builder.timeout(timeout);
+    }
+    let client = builder.build().map_err(transport)?;
+    let mut request = client.request(method, url).headers(options.header_map()?);
+    if let Some(body) = body {
+        request = request
+            .header(CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(body)?);
+    }
+    let response = request.send().await.map_err(transport)?;
+    let status = response.status().as_u16();
+    let bytes = response.bytes().await.map_err(transport)?;
+    decode(status, &bytes, options)
+}
+
+/// GET the JSON of the URL
+pub fn get<T: DeserializeOwned>(url: &str) -> Result<T> {
+    get_with(url, &HttpOptions::new())
+}
+
+/// GET the JSON of the URL with the options
+pub fn get_with<T: DeserializeOwned>(url: &str, options: &HttpOptions) -> Result<T> {
+    blocking::<T, ()>(Method::GET, url, None, options)
+}
+
+/// POST the value as JSON, the response is deserialized
+pub fn post<R, T>(url: &str, value: &T) -> Result<R>
+where
+    R: DeserializeOwned,
+    T: Serialize + ?Sized,
+{
+    post_with(url, value, &HttpOptions::new())
+}
+
+/// POST the value as JSON with the options
+pub fn post_with<R, T>(url: &str, value: &T, options: &HttpOptions) -> Result<R>
+where
+    R: DeserializeOwned,
+    T: Serialize + ?Sized,
+{
+    blocking(Method::POST, url, Some(value), options)
+}
+
+/// GET the JSON of the URL asynchronously
+pub async fn get_async<T: DeserializeOwned>(url: &str) -> Result<T> {
+    get_async_with(url, &HttpOptions::new()).await
+}
+
+/// GET the JSON of the URL asynchronously with the options
+pub async fn get_async_with<T: DeserializeOwned>(url: &str, options: &HttpOptions) -> Result<T> {
+    nonblocking::<T, ()>(Method::GET, url, None, options).await
+}
+
+/// POST the value as JSON asynchronously
+pub async fn post_async<R, T>(url: &str, value: &T) -> Result<R>
+where
+    R: DeserializeOwned,
+    T: Serialize + ?Sized,
+{
+    post_async_with(url, value, &HttpOptions::new()).await
+}
+
+/// POST the value as JSON asynchronously with the options
+pub async fn post_async_with<R, T>(url: &str, value: &T, options: &HttpOptions) -> Result<R>
+where
+    R: DeserializeOwned,
+    T: Serialize + ?Sized,
+{
+    nonblocking(Method::POST, url, Some(value), options).await
+}
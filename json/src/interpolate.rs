@@ -0,0 +1,71 @@
+//! Environment variables in string values
+//!
+//! `${VAR}` is replaced by the variable, it is an error if the variable is not set, `${VAR:-default}` falls back to
+//! the default if it is unset or empty, `$$` is a literal `$`. The keys and the other values are not changed:
+//!
+//! ```rust
+//! // {"url": "postgres://${DB_USER}:${DB_PASSWORD}@${DB_HOST:-localhost}/app", "price": "$$5"}
+//! let config: Config = native_json::reader(&"config.json").interpolate(true).read()?;
+//! ```
+use crate::{Error, Result};
+use serde_json::Value;
+
+/// Replace the placeholders of the text
+pub fn expand(text: &str) -> Result<String> {
+    expand_with(text, |name| std::env::var(name).ok())
+}
+
+/// Replace the placeholders of the text by the variables of the lookup
+pub fn expand_with<F>(text: &str, lookup: F) -> Result<String>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(tail) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = tail;
+            continue;
+        }
+        let Some(tail) = rest.strip_prefix("${") else {
+            out.push('$');
+            rest = &rest[1..];
+            continue;
+        };
+        let Some(end) = tail.find('}') else {
+            return Err(Error::Custom(format!("unclosed placeholder in `{}`", text)));
+        };
+        let (name, default) = match tail[..end].split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (&tail[..end], None),
+        };
+        match (lookup(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => out.push_str(default),
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => {
+                return Err(Error::Custom(format!(
+                    "environment variable `{}` is not set",
+                    name
+                )))
+            }
+        }
+        rest = &tail[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Replace the placeholders of all string values recursively
+pub fn interpolate(value: &mut Value) -> Result<()> {
+    match value {
+        Value::String(s) if s.contains('$') => *s = expand(s)?,
+        Value::Array(array) => array.iter_mut().try_for_each(interpolate)?,
+        Value::Object(map) => map.values_mut().try_for_each(interpolate)?,
+        _ => {}
+    }
+    Ok(())
+}
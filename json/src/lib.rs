@@ -25,10 +25,13 @@
 //!            color: "red",
 //!            size: 12,
 //!            bold: true,
-//!            range: null
+//!            range: null,
+//!            limit: null as Option<i32>   // typed null
 //!        },
 //!        array: [5,4,3,2,1],
 //!        vector: vec![1,2,3,4,5],
+//!        tags: [],                    // empty array
+//!        names: [] as Vec<String>,    // empty container with explicit type, so as `{} as HashMap<K, V>`
 //!        hashmap: HashMap::from([ ("a", 1), ("b", 2), ("c", 3) ]);,
 //!        students: [
 //!            {name: "John", age: 18},
@@ -58,8 +61,24 @@
 //!JSON_OBJECT_NAME {
 //!    state: i32?,    // optional field
 //!    type_: String,  // suffix underscore will be removed when serialize & deserialize
+//!    match: String,  // keywords are allowed, access with raw identifier `r#match`
+//!    "content-type": String, // any JSON key, the field is named `content_type`
+//!    24h: f64,       // keys starting with digits are prefixed by underscore, `_24h`
+//!    created: DateTime<Utc> @format(unix), // date & time format: rfc3339, unix or unix_ms
+//!    payload: Vec<u8> @with(hex), // custom serde module, or @serialize_with(path) / @deserialize_with(path)
+//!    data: bytes,    // Vec<u8> serialized as base64 string, @base64(url_safe) to select the alphabet, requires the `base64` feature
+//!    amount: u128 @string, // number serialized as string, e.g. 128-bit integers
+//!    document: raw,  // Box<serde_json::value::RawValue>, the sub-document is carried as unparsed text, null by default
+//!    token: String @secret, // masked as "****" by stringify_redacted()
+//!    #[cfg(unix)]    // conditional field, with its initializer and builder setter
+//!    socket: String,
+//!    age: u16 @range(0, 150), // validation rules: range, min, max, min_len, max_len, regex
+//!    level: Level @init(Level::Warn), // initializer of `new()`, other types are initialized by `Default::default()`
+//!    tutor: School_students_item_tutor, // reuse a generated type, reported at the field if it does not exist
+//!    ...: HashMap<String, serde_json::Value>, // undeclared keys are kept in field `extra`, or `...name: type`
 //!    name : type,
 //!    array: [type],
+//!    map: {String: type}, // HashMap<String, type>, the keys are String or integers, `{String: { ... }}` declares the value struct
 //!    object: {
 //!        name: type,
 //!        ...
@@ -72,6 +91,29 @@
 //!
 //!  1. `JSON_OBJECT_NAME.object` was converted to `JSON_OBJECT_NAME_object`
 //!  2. `JSON_OBJECT_NAME.array's item` was converted to `JSON_OBJECT_NAME_array_item`
+//!  3. `JSON_OBJECT_NAME.map's value` was converted to `JSON_OBJECT_NAME_map_value`
+//!
+//!### Declaration options
+//!
+//!Options are listed by `#[json(...)]` in front of the declaration.
+//!
+//!```rust
+//!json!{
+//!#[json(builder)]
+//!School {
+//!    name: String
+//!}}
+//!```
+//!
+//!  - `builder`: generate a `SchoolBuilder` for each struct, `School::builder().with_name(...).build()`
+//!  - `deny_unknown_fields`: fail to deserialize when any object contains an undeclared key, it is a compile error with a `...rest` field which collects them
+//!  - `serialize_only`, `deserialize_only`: derive only `Serialize` or only `Deserialize`, e.g. for write-only responses with fields which are not deserializable
+//!  - `patch`: generate a `SchoolPatch` for each struct with all fields `Option<T>`, `school.apply(patch)` overwrites the present fields, nested structs are patched recursively. `Option<T>` fields are `Option<Option<T>>` so `null` clears them, the custom serialization of `@string`, `@base64` and so on is kept
+//!  - `reflect`: implement `native_json::Reflect` for each struct, `School::fields()` lists the name, JSON key, type and optionality of the fields, `school.set_path("students.0.age", 19.into())` and `get_path` access the value by the path of JSON keys
+//!  - `typescript`: generate `School::typescript()`, the TypeScript interfaces of the serialized shape for `.d.ts` files
+//!  - `schema`: generate `School::schema()`, the JSON Schema (draft 2020-12) of the serialized shape as `serde_json::Value`
+//!  - `avro`: generate `School::avro_schema()`, the Apache Avro schema (`.avsc`) of the serialized shape as `serde_json::Value`, optional fields are unions with `null` which default to `null`, nested structs are records defined at the first use
+//!  - `utoipa`: derive `utoipa::ToSchema` for each struct, e.g. `#[openapi(components(schemas(School)))]`, the optional and defaulted fields are not required, the nested structs are referenced by their names like `School_students_item`
 //!
 //!## Example of using named JSON object
 //!
@@ -112,107 +154,1822 @@
 //!}
 //!```
 //!
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::io::BufReader;
-use std::io::BufWriter;
-use std::path::Path;
+//!## Declare from a JSON sample
+//!
+//!`include_json!` reads a sample JSON file at compile time and declares the same named structs as `json!`, the field types are inferred from the sample values. The path is relative to the crate root.
+//!
+//!```rust
+//!use native_json::include_json;
+//!
+//!include_json!("fixtures/response.json" as ApiResponse);
+//!```
+//!
+//!## Declare from an OpenAPI document
+//!
+//!`openapi_json!` reads an OpenAPI document (YAML, or JSON by the `.json` extension) at compile time and declares the listed schemas of `components.schemas` as the same named structs as `json!`, the schemas they reference are declared as well. The properties missing from `required` are optional, `allOf` is merged into one struct, `nullable` is `Option<T>`, non-object schemas are type aliases, e.g. `pub type Side = String;`.
+//!
+//!```rust
+//!use native_json::openapi_json;
+//!
+//!openapi_json!("api.yaml", components = ["Order", "Trade"]);
+//!
+//!let order = Order_items_item::new();
+//!```
+//!
+//!## Declare from an example
+//!
+//!`json_type!` promotes an example in instance syntax to a named type, the field types are inferred from the values, use `value as Type` to be explicit. `Config::example()` returns the example value.
+//!
+//!```rust
+//!use native_json::json_type;
+//!
+//!json_type!{ Config = { host: "localhost", port: 8080 as u16, debug: true } }
+//!
+//!let config = Config::example();
+//!```
+//!
+//!
+//!## Validation
+//!
+//!Validation rules on the fields implement `native_json::Validate`, `validate()` reports all violations with the field paths, nested structs are validated recursively. The rules of `Option<T>` fields check the value unless it is `None`. `@regex("...")` requires the `regex` feature, an invalid pattern is a compile error.
+//!
+//!```rust
+//!use native_json::{json, Validate};
+//!
+//!json!{ User {
+//!    name: String @min_len(1) @max_len(32),
+//!    age: u16 @range(0, 150),
+//!    tags: [String] @max_len(8)
+//!}}
+//!
+//!let user = User::new();
+//!if let Err(errors) = user.validate() {
+//!    println!("{}", errors); // name: length must be at least 1
+//!}
+//!```
+//!
+//!
+//!## Decimal
+//!
+//!With the `rust_decimal` or `bigdecimal` feature, `native_json::Decimal` and `native_json::BigDecimal` can be declared as fields, they are initialized to zero and serialized as strings, financial numbers are kept losslessly. Both JSON numbers and strings are accepted on deserialize, `@string` keeps a field a string even if another crate of the build enables `serde-float` of rust_decimal.
+//!
+//!```rust
+//!use native_json::{json, Decimal};
+//!
+//!json!{ Invoice { total: Decimal, tax: Decimal? } }
+//!```
+//!
+//!
+//!## Addresses, paths and UUID
+//!
+//!`IpAddr`, `Ipv4Addr`, `Ipv6Addr` and `SocketAddr` fields are initialized to the unspecified address, `PathBuf` to the empty path. With the `uuid` feature, `native_json::Uuid` fields are initialized to the nil UUID.
+//!
+//!```rust
+//!use native_json::{json, Uuid};
+//!use std::{net::SocketAddr, path::PathBuf};
+//!
+//!json!{ Server { id: Uuid, listen: SocketAddr, root: PathBuf } }
+//!```
+//!
+//!
+//!## Spread
+//!
+//!`..base` in an instance takes all fields of `base`, another instance or a declared struct, the pairs after it override its fields. The spread comes first as in JavaScript, a spread after a pair is a compile error except in a typed object, where `Type { key: value, ..base }` is the Rust struct update. The result has the type of `base`, and `base` is moved like the struct update syntax.
+//!
+//!```rust
+//!let defaults = json!{ host: "localhost", port: 80 };
+//!let config = json!{ ..defaults, port: 8080 };
+//!```
+//!
+//!
+//!## Shorthand
+//!
+//!A key without value takes the variable of the same name, like the shorthand property names of JavaScript.
+//!
+//!```rust
+//!let name = "Tom";
+//!let age = 18;
+//!let user = json!{ name, age, profile: { active: true } };
+//!```
+//!
+//!
+//!## Typed instance
+//!
+//!An object in instance syntax can be typed as a declared struct, `Type { key: value, ... }`, or `[Type; { ... }, ...]` for the object items of an array, `[value; n]` stays a Rust repeat expression. The nested objects are named after the declared type, e.g. `School_students_item`, the arrays are `Vec` and string literals are converted to the field types.
+//!
+//!```rust
+//!json!{ School { name: String, students: [{ name: String, age: u8 }] } }
+//!
+//!let school = json!{ school: School {
+//!    name: "MIT",
+//!    students: [{ name: "Tom", age: 20 }]
+//!}};
+//!let students = json!{ list: [School_students_item; { name: "Ann", age: 19 }] };
+//!```
+//!
+//!
+//!## Conditional fields
+//!
+//!`key: value @if(condition)` includes the field only when the condition holds at runtime, the field is an `Option` which is skipped when `None`. It is not allowed in typed or spread objects, and other annotations are rejected in instances.
+//!
+//!```rust
+//!let verbose = std::env::var("VERBOSE").is_ok();
+//!let json = json!{ name: "job", debug: { level: 3 } @if(verbose) };
+//!```
+//!
+//!
+//!## Extends
+//!
+//!`Name extends Base { ... }` includes all fields of a previously declared struct, they are flattened into the field `base` and accessible through `Deref`. The `schema`, `avro`, `patch` and `reflect` options include the fields of base from its own generated code, so the base is declared with the same options. Every declared struct implements `Validate`, the derived struct validates its base first.
+//!
+//!```rust
+//!json!{ User { id: u64, name: String } }
+//!json!{ Admin extends User { permissions: [String] } }
+//!
+//!let mut admin = Admin::new();
+//!admin.name = "root".to_owned();
+//!```
+//!
+//!
+//!## Methods
+//!
+//!An `impl { ... }` block in a declared object is emitted as the methods of its generated struct.
+//!
+//!```rust
+//!json!{ School {
+//!    students: [{
+//!        age: u8,
+//!        impl {
+//!            pub fn is_adult(&self) -> bool { self.age >= 18 }
+//!        }
+//!    }]
+//!}}
+//!```
+//!
+//!
+//!## Module
+//!
+//!`mod name { declaration }` keeps the generated structs in a module and exports only the root, the nested structs are accessed by path, e.g. `school::School_students_item`. The module imports its parent with `use super::*`.
+//!
+//!```rust
+//!json!{ pub mod school {
+//!    School { name: String, students: [{ name: String }] }
+//!}}
+//!
+//!let mut school = School::new();
+//!school.students.push(school::School_students_item::new());
+//!```
+//!
+//!
+//!## Change tracking
+//!
+//!`native_json::Tracked<T>` keeps a snapshot of a loaded value of a struct declared with the `reflect` option, `dirty_fields()` lists the JSON keys of the fields changed since then and `serialize_dirty()` writes only those keys, the removed ones as `null`. `commit()` takes the current value as the new snapshot. A value which fails to serialize is an error rather than a change of every key.
+//!
+//!```rust
+//!use native_json::Tracked;
+//!
+//!let mut config = Tracked::new(native_json::read::<Config, _>("config.json")?)?;
+//!config.port = 8080;
+//!assert_eq!(config.dirty_fields()?, ["port"]);
+//!let changes = config.serialize_dirty()?; // {"port":8080}
+//!config.commit()?;
+//!```
+//!
+//!
+//!## JSON Pointer
+//!
+//!`pointer("/style/size")` of the `JSON` trait returns the serialized value at a JSON Pointer (RFC 6901), `set_pointer` replaces it and deserializes the result back into the value.
+//!
+//!```rust
+//!use native_json::JSON;
+//!
+//!let size = json.pointer("/style/size"); // Some(Value::from(12))
+//!school.set_pointer("/students/0/age", 19.into())?;
+//!```
+//!
+//!
+//!## Flatten
+//!
+//!`native_json::flatten(&value)` maps the leaves of a `serde_json::Value` by their dotted paths, e.g. `style.size` and `students[0].age`, and `unflatten(map)` restores the value, e.g. for diffing, environment variables and key-value stores. The `.`, `[`, `]` and `\` of the keys are escaped by `\`.
+//!
+//!```rust
+//!use native_json::{flatten, unflatten};
+//!
+//!let map = flatten(&serde_json::to_value(&school)?); // BTreeMap { "students[0].age": 18, ... }
+//!let value = unflatten(map)?;
+//!```
+//!
+//!
+//!## Dynamic values
+//!
+//!`native_json::Value` is `serde_json::Value`, indexed natively by keys and positions. `to_value()` of the `JSON` trait and `T::from_value(value)` of the `FromJSON` trait convert the typed values, the errors report the field path. `ValueExt` accesses the value by the dotted paths of `flatten`: `get_path`, `get_path_mut`, `set_path` which creates the missing objects and arrays, and `remove_path`.
+//!
+//!```rust
+//!use native_json::{FromJSON, Value, ValueExt, JSON};
+//!
+//!let mut value: Value = school.to_value()?;
+//!let size = value["style"]["size"].as_i64();
+//!value.set_path("students[0].age", 19.into())?;
+//!let school = School::from_value(value)?;
+//!```
+//!
+//!
+//!## Merge
+//!
+//!`merge(&other)` and `merge_from_str(partial)` of the `JSON` trait deep-merge with the JSON Merge Patch (RFC 7386) semantics: objects are merged recursively, arrays and the other values are replaced, `null` resets an optional field. Layered configuration takes a few lines.
+//!
+//!```rust
+//!use native_json::JSON;
+//!
+//!let mut config: Config = native_json::read("default.json")?;
+//!config.merge_from_str(&std::fs::read_to_string("user.json")?)?;
+//!config.merge_from_str(r#"{"server": {"port": 8080}}"#)?;
+//!```
+//!
+//!
+//!## JSON Patch
+//!
+//!`native_json::patch::diff(&a, &b)` computes the JSON Patch (RFC 6902) operations which turn `a` into `b`, `native_json::patch::apply(&mut value, &ops)` applies them to any serializable type, all or nothing. `PatchOp` is serialized in the standard format, e.g. to sync state over the network.
+//!
+//!```rust
+//!use native_json::PatchOp;
+//!
+//!let ops: Vec<PatchOp> = native_json::patch::diff(&before, &after)?;
+//!let text = serde_json::to_string(&ops)?; // [{"op":"replace","path":"/port","value":8080}]
+//!native_json::patch::apply(&mut replica, &ops)?;
+//!```
+//!
+//!
+//!## Canonical JSON
+//!
+//!`canonicalize()` of the `JSON` trait returns the canonical form of RFC 8785 (JCS): keys sorted by UTF-16 code units, numbers formatted as ECMAScript doubles and no whitespace. The output is stable across runs and machines, for signatures, content hashes and cache keys.
+//!
+//!```rust
+//!use native_json::JSON;
+//!
+//!let text = json!{ b: 1.0, a: [1e21, 0.5] }.canonicalize()?; // {"a":[1e+21,0.5],"b":1}
+//!```
+//!
+//!
+//!## Key order
+//!
+//!Fields of declared structs are always written in the declaration order. Keys of maps such as `HashMap` are in arbitrary order, `sort_keys(true)` sorts the keys of all objects so the emitted files diff deterministically.
+//!
+//!```rust
+//!use native_json::{StringifyOptions, JSON};
+//!
+//!let text = config.stringify_with(&StringifyOptions::new().indent(2).sort_keys(true))?;
+//!native_json::writer(&"config.json").sort_keys(true).write(&config)?;
+//!```
+//!
+//!With the `preserve_order` feature, `native_json::OrderedMap<K, V>` is a map which keeps the insertion order of the keys, and `serde_json::Value` keeps the order of the parsed objects, so rewriting a user config file keeps the user's order.
+//!
+//!```rust
+//!use native_json::OrderedMap;
+//!
+//!json!{ Config { servers: OrderedMap<String, u16>, extra: serde_json::Value } }
+//!```
+//!
+//!
+//!## Digest
+//!
+//!With the `sha2` or `blake3` feature, `digest(Digest::Sha256)` or `digest(Digest::Blake3)` of the `JSON` trait returns the lowercase hex hash of the canonical JSON string, equal values have the same digest regardless of key order or number formatting. It enables cache keys and change detection without storing full snapshots.
+//!
+//!```rust
+//!use native_json::{Digest, JSON};
+//!
+//!let key = config.digest(Digest::Sha256)?;
+//!```
+//!
+//!
+//!## Output format
+//!
+//!`StringifyOptions` also selects the indentation character, Windows line endings, a trailing newline and `ensure_ascii` escaping of non-ASCII characters, e.g. for generated files checked into version control. `native_json::writer(&path).options(...)` writes files with the same options.
+//!
+//!```rust
+//!use native_json::{StringifyOptions, JSON};
+//!
+//!let options = StringifyOptions::new()
+//!    .indent(1)
+//!    .indent_char('\t')
+//!    .crlf(true)
+//!    .trailing_newline(true)
+//!    .ensure_ascii(true);
+//!let text = config.stringify_with(&options)?;
+//!```
+//!
+//!
+//!## Float format
+//!
+//!`FloatFormat` of `StringifyOptions` controls the floating point numbers: `Shortest` round-trips (the default, `0.30000000000000004`), `Precision(n)` writes fixed digits after the decimal point and `Integer` writes integral values without `.0`.
+//!
+//!```rust
+//!use native_json::{FloatFormat, StringifyOptions, JSON};
+//!
+//!let text = config.stringify_with(&StringifyOptions::new().float_precision(4))?; // 0.3000
+//!let text = config.stringify_with(&StringifyOptions::new().float_format(FloatFormat::Integer))?; // 1
+//!```
+//!
+//!
+//!## NaN and infinities
+//!
+//!serde_json writes NaN and infinities as `null`. `NonFinite` of `StringifyOptions` writes them as strings (`"NaN"`, `"Infinity"`, `"-Infinity"`) or fails with the path of the offending field.
+//!
+//!```rust
+//!use native_json::{NonFinite, StringifyOptions, JSON};
+//!
+//!let options = StringifyOptions::new().non_finite(NonFinite::Error);
+//!let err = report.stringify_with(&options); // non-finite float at `samples[1].value`
+//!```
+//!
+//!
+//!## Secrets
+//!
+//!Fields annotated by `@secret` are masked as `"****"` by `stringify_redacted(indent)` of the `JSON` trait, so tokens do not leak into logs, `stringify` and deserialization keep them intact.
+//!
+//!```rust
+//!use native_json::{json, JSON};
+//!
+//!json!{ Config { user: String, token: String @secret } }
+//!
+//!println!("{}", config.stringify_redacted(0)?); // {"user":"admin","token":"****"}
+//!```
+//!
+//!
+//!## Buffers
+//!
+//!`to_bytes()`, `write_to(&mut writer)` and `stringify_into(&mut buf)` of the `JSON` trait write concise JSON without the intermediate `String` of `stringify`, hot paths can reuse the buffers or stream to sockets directly.
+//!
+//!```rust
+//!use native_json::JSON;
+//!
+//!let mut buf = String::with_capacity(4096);
+//!for event in events {
+//!    buf.clear();
+//!    event.stringify_into(&mut buf)?;
+//!}
+//!event.write_to(&mut stream)?;
+//!```
+//!
+//!`Stringifier` applies the `StringifyOptions` to many documents, the indentation is prepared once and the output is appended to the caller's buffer:
+//!
+//!```rust
+//!use native_json::Stringifier;
+//!
+//!let s = Stringifier::pretty(2); // or Stringifier::new(options)
+//!for event in events {
+//!    buf.clear();
+//!    s.stringify_into(&event, &mut buf)?;
+//!}
+//!s.to_writer(&mut stream, &event)?;
+//!```
+//!
+//!
+//!## Parse helpers
+//!
+//!The `FromJSON` trait adds `T::from_json(s)`, `T::from_slice(bytes)` and `T::from_reader(reader)` to the deserializable types, the errors are the typed `native_json::Error`. `update_from_str(s)` updates a value in place, the fields absent from the input are kept and nested objects are updated recursively.
+//!
+//!```rust
+//!use native_json::FromJSON;
+//!
+//!let mut config = Config::from_json(&text)?;
+//!config.update_from_str(r#"{"server": {"port": 8080}}"#)?;
+//!```
+//!
+//!
+//!## SIMD parsing
+//!
+//!The `simd` feature parses by [simd-json](https://github.com/simd-lite/simd-json). simd-json parses in place, so `native_json::read` loads the file into an owned buffer and parses it by simd-json, and `native_json::simd` parses the owned or mutable input, the borrowed input of `parse` and `from_slice` stays on serde_json. The errors report the path and the byte offset, `read` falls back to serde_json for the line and column.
+//!
+//!```rust
+//!use native_json::simd;
+//!
+//!let school: School = simd::parse(text)?;    // String
+//!let school: School = simd::from_vec(bytes)?; // Vec<u8>, or simd::from_slice(&mut bytes)
+//!let school: School = native_json::read("school.json")?;
+//!```
+//!
+//!
+//!## Error path
+//!
+//!Deserialization failures of `native_json::parse`, `native_json::read` and the `FromJSON` helpers report the path of the failed field with the line and column. `native_json::Error` dereferences to `serde_json::Error` for `line()`, `column()` and the category.
+//!
+//!```rust
+//!let err = native_json::parse::<School>(&text).unwrap_err();
+//!println!("{}", err); // students[3].age: invalid type: string "x", expected u16 at line 17 column 9
+//!assert_eq!(err.path(), "students[3].age");
+//!```
+//!
+//!
+//!## Errors
+//!
+//!`native_json::Error` is returned by `read`, `write`, `stringify` and the other functions, its kinds can be matched: `Io`, `Syntax { line, column }`, `Type { path, line, column }`, `Validation`, `Limit { limit, max }` and `Custom`. It implements `std::error::Error`, so `?` converts it into `anyhow::Error` as well.
+//!
+//!```rust
+//!match native_json::read::<Config, _>("config.json") {
+//!    Ok(config) => run(config),
+//!    Err(native_json::Error::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => run(Config::new()),
+//!    Err(e) => return Err(e.into()),
+//!}
+//!```
+//!
+//!
+//!## Async I/O
+//!
+//!With the `tokio` feature, `read_async`, `write_async` and `writer(&path).write_async(&value)` access files by `tokio::fs` without blocking the runtime. `read_async_blocking` and `write_async_blocking` run the whole work on the blocking thread pool, for very large documents.
+//!
+//!```rust
+//!let config: Config = native_json::read_async("config.json").await?;
+//!native_json::write_async("config.json", &config).await?;
+//!native_json::write_async_blocking("state.json", Arc::new(state)).await?;
+//!```
+//!
+//!
+//!## Atomic write
+//!
+//!`native_json::write_atomic(path, &value)` and `writer(&path).atomic(true)` write a temporary file in the same directory, fsync it and rename it over the target, a crash mid-write never leaves a truncated file. The permissions of the existing file are kept.
+//!
+//!```rust
+//!native_json::write_atomic("state.json", &state)?;
+//!native_json::writer(&"state.json").indent(4).atomic(true).write(&state)?;
+//!```
+//!
+//!
+//!## Backup
+//!
+//!`writer(&path).backup(n)` keeps N rotating backups `config.json.1..n` of the overwritten file, `config.json.1` is the latest. `restore_latest(path)` restores the latest backup which can be deserialized, e.g. when the file is corrupted by a manual edit.
+//!
+//!```rust
+//!native_json::writer(&"config.json").backup(3).atomic(true).write(&config)?;
+//!
+//!let config: Config = native_json::read("config.json")
+//!    .or_else(|_| native_json::restore_latest("config.json"))?;
+//!```
+//!
+//!
+//!## File locking
+//!
+//!`read_locked(path)` and `write_locked(path, &value)` take an advisory lock on the sidecar file `config.json.lock`, shared for reading and exclusive for writing, so cooperating processes never read a half-written file. `writer(&path).exclusive(true)` takes the exclusive lock for the builder.
+//!
+//!```rust
+//!native_json::write_locked("shared.json", &state)?;
+//!let state: State = native_json::read_locked("shared.json")?;
+//!
+//!native_json::writer(&"shared.json").exclusive(true).backup(1).write(&state)?;
+//!```
+//!
+//!
+//!## Directories and permissions
+//!
+//!`writer(&path).create_dirs(true)` creates the missing parent directories, `.mode(0o600)` sets the permissions of the written file on unix, e.g. for secrets which should not be world-readable.
+//!
+//!```rust
+//!native_json::writer(&settings_path)
+//!    .create_dirs(true)
+//!    .mode(0o600)
+//!    .atomic(true)
+//!    .write(&settings)?;
+//!```
+//!
+//!
+//!## Compact and append
+//!
+//!`writer(&path).compact()` writes without any whitespace. `.append(true)` appends the value as one concise line, JSON Lines for log-style persistence, each line is a single write so concurrent appenders do not interleave.
+//!
+//!```rust
+//!native_json::writer(&"state.json").compact().write(&state)?;
+//!native_json::writer(&"events.jsonl").append(true).write(&event)?;
+//!```
+//!
+//!
+//!## Progress
+//!
+//!`on_progress(f)` of `writer(&path)` and `reader(&path)` observes the bytes of large files for progress bars, `f(done, total)` is called after every 64 KiB and at the end. The total is the file size when reading, and it is unknown when writing unless appending. The observer is `Send + Sync` so `write_async` with progress can be spawned, it is called the same way.
+//!
+//!```rust
+//!let bar = ProgressBar::new(0);
+//!let state: State = native_json::reader(&"state.json")
+//!    .on_progress(|done, total| {
+//!        bar.set_length(total.unwrap_or(0));
+//!        bar.set_position(done);
+//!    })
+//!    .read()?;
+//!native_json::writer(&"state.json").on_progress(|done, _| bar.set_position(done)).write(&state)?;
+//!```
+//!
+//!
+//!## Missing files
+//!
+//!`read_or_default(path)` and `read_or_else(path, f)` return the default value if the file does not exist, `read_or_create(path, f)` also writes it back. Malformed files are still errors.
+//!
+//!```rust
+//!let config: Config = native_json::read_or_default("config.json")?;
+//!let config = native_json::read_or_else("config.json", Config::new)?;
+//!let config = native_json::read_or_create("config.json", Config::new)?;
+//!```
+//!
+//!
+//!## Schema validation
+//!
+//!With the `jsonschema` feature, `read_validated(path, &schema)` validates the document against a JSON Schema before it is deserialized, e.g. the schema generated by the `schema` option. The error is `Error::Validation` with all the violations and their paths, such as `students[3].age`, rather than the first type error. `schema::validate(&value, &schema)` validates a `serde_json::Value`.
+//!
+//!```rust
+//!let school: School = native_json::read_validated("school.json", &School::schema())?;
+//!```
+//!
+//!
+//!## Memory-mapped files
+//!
+//!With the `mmap` feature, `read_mmap(path)` parses a large file from its memory-mapped pages instead of copying it through a buffer. `mmap::MappedFile` keeps the mapping, so the borrowed fields such as `&'a str` point into it. Both are `unsafe` like `memmap2::Mmap::map`: it is undefined behavior if the file is modified or truncated while it is mapped, e.g. by another process. Compressed files are not detected.
+//!
+//!```rust
+//!use native_json::mmap::MappedFile;
+//!
+//!// SAFETY: the file is not modified while it is mapped
+//!let school: School = unsafe { native_json::read_mmap("school.json")? };
+//!
+//!let file = unsafe { MappedFile::open("index.json")? };
+//!let index: Index<'_> = file.parse()?;
+//!```
+//!
+//!
+//!## Update
+//!
+//!`update(path, f)` reads the file or the default value, modifies it by the closure and writes it back atomically. `update_locked(path, f)` holds an exclusive advisory lock during the update, so the concurrent updates of processes are not lost.
+//!
+//!```rust
+//!let config = native_json::update("config.json", |config: &mut Config| {
+//!    config.port = 8080;
+//!})?;
+//!native_json::update_locked("counter.json", |c: &mut Counter| c.count += 1)?;
+//!```
+//!
+//!
+//!## Hot reload
+//!
+//!`watch(path)` polls the file in a background thread and reloads it when it stops changing, `current()` is the latest snapshot which was parsed successfully. The reloads and the parse errors are received by `recv_timeout` and `try_recv`, `on_change` registers a callback. The thread stops when the watcher is dropped.
+//!
+//!```rust
+//!let config = native_json::watch::<Config, _>("config.json")?;
+//!config.on_change(|c| println!("port {}", c.port));
+//!let port = config.current().port;
+//!
+//!let options = WatchOptions::new()
+//!    .interval(Duration::from_secs(1))
+//!    .debounce(Duration::from_millis(200));
+//!let config = native_json::watch_with::<Config, _>("config.json", options)?;
+//!if let Some(Err(e)) = config.try_recv() {
+//!    eprintln!("config.json: {}", e);
+//!}
+//!```
+//!
+//!
+//!## Layered configuration
+//!
+//!`config::Loader` overlays the layers in priority order on the default value of the struct: files, environment variables `APP__STYLE__SIZE=14`, command line options `--style.size 14` and overrides, the later layers win. `source(path)` tells which layer each value came from. `apply_cli_overrides(args)` applies the options to a value in place, with the `clap` feature `config::command::<Config>(name)` builds a `clap::Command` of the options with the defaults in `--help`.
+//!
+//!```rust
+//!use native_json::config::Loader;
+//!
+//!let config = Loader::new()
+//!    .file("config.json")
+//!    .optional_file("config.local.json")
+//!    .env("APP")
+//!    .args(std::env::args().skip(1))
+//!    .set("port", 8080)
+//!    .load::<Config>()?;
+//!
+//!println!("size {} from {}", config.style.size, config.source("style.size").unwrap());
+//!```
+//!
+//!
+//!## Environment variables
+//!
+//!`reader(&path).interpolate(true)` replaces the placeholders in string values by environment variables: `${VAR}` fails if the variable is not set, `${VAR:-default}` falls back to the default if it is unset or empty, `$$` is a literal `$`.
+//!
+//!```rust
+//!// {"url": "postgres://${DB_USER}@${DB_HOST:-localhost}/app", "price": "$$5"}
+//!let db: Database = native_json::reader(&"database.json").interpolate(true).read()?;
+//!```
+//!
+//!
+//!## Comments and trailing commas
+//!
+//!`ReadOptions` enables the lenient mode for `read_with`, `parse_with` and `reader(&path)`: `//` and `/* */` comments and trailing commas are accepted, e.g. the config files of VS Code. The errors still report the original lines and columns.
+//!
+//!```rust
+//!let options = ReadOptions::new().allow_comments(true).allow_trailing_commas(true);
+//!let settings: Settings = native_json::read_with("settings.json", &options)?;
+//!let settings: Settings = native_json::parse_with(&text, &options)?;
+//!let settings: Settings = native_json::reader(&"settings.json").allow_comments(true).read()?;
+//!```
+//!
+//!
+//!## Read limits
+//!
+//!`max_bytes` and `max_depth` of `ReadOptions` reject the untrusted input before it is deserialized, for `read_with`, `parse_with` and `reader(&path)`. A file is not read beyond `max_bytes`, and serde_json rejects 128 levels of nesting regardless. The error is `Error::Limit { limit, max }`, which axum answers with `413 Payload Too Large` for `max_bytes`.
+//!
+//!```rust
+//!let options = ReadOptions::new().max_bytes(1 << 20).max_depth(32);
+//!let request: Request = native_json::parse_with(&body, &options)?;
+//!```
+//!
+//!
+//!## Duplicate keys
+//!
+//!The fields of the declared structs reject the duplicate keys, but the maps and `Value` take the last value, `deny_duplicate_keys(true)` of `ReadOptions` rejects them anywhere in the input, the `Error::Type` reports the path of the key such as `labels.env`. The input is checked before it is deserialized, for `read_with`, `parse_with` and `reader(&path)`.
+//!
+//!```rust
+//!let options = ReadOptions::new().deny_duplicate_keys(true);
+//!let cluster: Cluster = native_json::read_with("cluster.json", &options)?;
+//!```
+//!
+//!
+//!## Comment-preserving documents
+//!
+//!`Document` parses a JSON file with comments, `get()` deserializes it into a declared struct and `set(&value)` edits only the changed values in place, so the comments, the key order and the blank lines of the user are preserved.
+//!
+//!```rust
+//!let mut doc = Document::read("settings.json")?;
+//!let mut settings: Settings = doc.get()?;
+//!settings.size = 14;
+//!doc.set(&settings)?;
+//!doc.write("settings.json")?;
+//!```
+//!
+//!
+//!## YAML
+//!
+//!With the `serde_yaml` feature, `read_yaml` and `write_yaml` load and save the declared structs as YAML files, `Format::Yaml` selects YAML for `writer(&path)` and `reader(&path)`. The errors report the field path and the location.
+//!
+//!```rust
+//!let mut config: Config = native_json::read_yaml("config.yaml")?;
+//!config.replicas = 5;
+//!native_json::write_yaml("config.yaml", &config)?;
+//!native_json::writer(&"config.yaml").format(Format::Yaml).atomic(true).write(&config)?;
+//!```
+//!
+//!
+//!## TOML
+//!
+//!With the `toml` feature, `read_toml` and `write_toml` load and save the declared structs as TOML files, e.g. Cargo-style configs, `Format::Toml` selects TOML for `writer(&path)` and `reader(&path)`. The errors report the field path and the location, TOML datetimes which are declared as strings, `None` in arrays and top-level arrays are reported clearly.
+//!
+//!```rust
+//!let mut manifest: Manifest = native_json::read_toml("manifest.toml")?;
+//!manifest.package.version = "1.1.0".to_owned();
+//!native_json::write_toml("manifest.toml", &manifest)?;
+//!```
+//!
+//!
+//!## MessagePack
+//!
+//!With the `msgpack` feature, `to_msgpack()` of the `JSON` trait and `from_msgpack(&bytes)` of the `FromJSON` trait encode and decode MessagePack, the structs are maps with the JSON keys. The declared structs can be shipped over the wire compactly while the JSON declaration stays the single source of truth.
+//!
+//!```rust
+//!let bytes = packet.to_msgpack()?;
+//!let packet = Packet::from_msgpack(&bytes)?;
+//!```
+//!
+//!
+//!## CBOR
+//!
+//!With the `cbor` feature, `to_cbor()` of the `JSON` trait and `from_cbor(&bytes)` of the `FromJSON` trait encode and decode CBOR, the structs are maps with the JSON keys, e.g. for IoT devices and COSE workflows. The errors report the field path.
+//!
+//!```rust
+//!let bytes = reading.to_cbor()?;
+//!let reading = Reading::from_cbor(&bytes)?;
+//!```
+//!
+//!
+//!## Compression
+//!
+//!With the `gzip` or `zstd` feature, `writer(&path).compress(Compression::Zstd(level))` compresses the file, `write` and `writer(&path)` also compress by the extension `.json.gz` or `.json.zst`. The reading functions detect the compression by the magic bytes, large snapshots and logs take a fraction of the disk space with no extra code.
+//!
+//!```rust
+//!native_json::writer(&"snapshot.json").compress(Compression::Zstd(3)).write(&snapshot)?;
+//!native_json::write("events.json.gz", &events)?;
+//!let snapshot: Snapshot = native_json::read("snapshot.json")?;
+//!```
+//!
+//!
+//!## JSON Lines
+//!
+//!`lines::LinesWriter` writes one record per line with a flushing policy, `lines::LinesReader` iterates the records of a JSON Lines (NDJSON) file or reader, `skip_corrupt(true)` skips and counts the lines which fail to parse, e.g. a partial line of a crash.
+//!
+//!```rust
+//!use native_json::lines::{Flush, LinesReader, LinesWriter};
+//!
+//!let mut log = LinesWriter::append("events.jsonl")?.flush_policy(Flush::EveryLine);
+//!log.write(&event)?;
+//!
+//!let mut reader = LinesReader::<Event>::open("events.jsonl")?.skip_corrupt(true);
+//!for event in &mut reader {
+//!    let event = event?;
+//!}
+//!```
+//!
+//!With the `rayon` feature, `lines::par_read` parses the lines of a large file in parallel for batch jobs, the file is read by chunks and the records are yielded in the order of the lines:
+//!
+//!```rust
+//!let events: Vec<Event> = native_json::lines::par_read("events.jsonl")?.collect::<Result<_, _>>()?;
+//!```
+//!
+//!
+//!## Streaming arrays
+//!
+//!`stream_array::<Item, _>(path)` iterates the elements of a top-level array, one element is parsed and in memory at a time, multi-GB exports are processed in constant memory. An element which does not match the type yields the error with its index, e.g. `[3].age`, and the iteration continues; malformed JSON ends it. `ArrayReader::new(reader)` streams from any `BufRead`.
+//!
+//!```rust
+//!for user in native_json::stream_array::<User, _>("users.json")? {
+//!    let user = user?;
+//!}
+//!```
+//!
+//!
+//!## Streaming array writer
+//!
+//!`ArrayWriter` writes a top-level array element by element, `write(&item)` or `write_all(iter)` serializes one element at a time, huge datasets are exported with constant memory. `pretty(true)` writes one indented element per line. `finish()` closes the array and returns the writer, the array is also closed on drop.
+//!
+//!```rust
+//!let mut writer = ArrayWriter::create("users.json")?.pretty(true);
+//!writer.write_all(users)?;
+//!writer.finish()?;
+//!```
+//!
+//!
+//!## Concatenated documents
+//!
+//!`parse_many::<T>(&text)` parses a buffer of several concatenated or whitespace separated documents, as produced by some APIs and `kubectl`, every document is yielded with its byte offset. The errors report the line and column in the whole buffer. `stream::from_reader::<_, T>(reader)` returns the re-exported `StreamDeserializer` of a reader, its `byte_offset()` is the offset after the last document.
+//!
+//!```rust
+//!for document in native_json::parse_many::<Pod>(&output) {
+//!    let (offset, pod) = document?;
+//!}
+//!```
+//!
+//!`parse` fails with `Error::Syntax` if anything but whitespace follows the document, `parse_prefix::<T>(&text)` parses the first document of a mixed-content buffer instead and returns the remaining text, starting right after the document.
+//!
+//!```rust
+//!let (header, body): (Header, &str) = native_json::parse_prefix(&buffer)?;
+//!```
+//!
+//!
+//!## Following JSON Lines
+//!
+//!`lines::tail::<T, _>(path)` follows a growing JSON Lines file like `tail -f`, the iterator blocks until the next record is appended, `poll()` returns the next record without blocking. A partial line is kept until its newline is written, a truncated file is read again from the start, and a rotated file is read to the end before the new file at the path is followed. Only the records appended after the first poll are read unless `from_start(true)`.
+//!
+//!```rust
+//!for event in lines::tail::<Event, _>("events.jsonl").interval(Duration::from_millis(100)) {
+//!    ship(event?)?;
+//!}
+//!```
+//!
+//!
+//!## HTTP
+//!
+//!With the `http` feature, `get::<T>(url)` and `post(url, &value)` fetch JSON over HTTP into the declared structs with the field paths of the errors, `http::get_async` and `http::post_async` are the async variants on tokio. `HttpOptions` sets the timeout, the headers and how much of an error body is captured, a response with an error status is `Error::Status { status, body }`.
+//!
+//!```rust
+//!let user: User = native_json::get("https://api.example.com/users/1")?;
+//!let created: User = native_json::post("https://api.example.com/users", &new_user)?;
+//!
+//!let options = HttpOptions::new().timeout(Duration::from_secs(5)).header("Authorization", token);
+//!let users: Vec<User> = native_json::http::get_async_with(url, &options).await?;
+//!```
+//!
+//!
+//!## JSON-RPC
+//!
+//!`json! { rpc name { params } -> result }` declares a JSON-RPC 2.0 method, the params and an object result are the structs `NameParams` and `NameResult`. `rpc::Client` assigns the ids of the requests and batches, `rpc::Router` dispatches a request or a batch to the handlers, the malformed requests and params are answered with the standard error codes.
+//!
+//!```rust
+//!json! { rpc subtract { minuend: i32, subtrahend: i32 } -> i32 }
+//!
+//!let client = rpc::Client::new();
+//!let request = client.request::<Subtract>(SubtractParams { minuend: 42, subtrahend: 23 });
+//!
+//!let router = rpc::Router::new().method::<Subtract, _>(|p| Ok(p.minuend - p.subtrahend));
+//!let response = router.handle(&request.to_string()?).unwrap();
+//!assert_eq!(rpc::Client::response::<Subtract>(&response)?, 19);
+//!```
+//!
+//!
+//!## WebSocket Messages
+//!
+//!`json! { ws Name { Variant { fields }, ... } }` declares the messages of a stream as an enum tagged by the key `type`, or the key of `@tag("e")`. The tag is the variant name as written, e.g. `book_ticker` is the variant `BookTicker`, the fields are the struct `NameVariant` and a variant without fields is a unit variant. `Name::parse_message` and `encode_message` convert the text frames, `kind()` is the tag.
+//!
+//!```rust
+//!json! { ws Message {
+//!    Ping { ts: i64 },
+//!    Trade { price: f64, qty: f64 },
+//!    Pong,
+//!}}
+//!
+//!match Message::parse_message(&frame)? {
+//!    Message::Ping(_) => socket.send(Message::Pong.encode_message()?)?,
+//!    Message::Trade(trade) => book.fill(trade.price, trade.qty),
+//!    Message::Pong => {}
+//!}
+//!```
+//!
+//!
+//!## axum
+//!
+//!With the `axum` feature, `native_json::axum::Json<T>` is the drop-in replacement of `axum::Json` for the declared structs. A rejected body is answered with the status and a JSON error body with the field path, the expected type and the location, `native_json::Error` is a response as well. The server errors such as `Error::Io` are answered with the status reason only, so the file paths and OS errors are not disclosed.
+//!
+//!```rust
+//!use native_json::axum::Json;
+//!
+//!async fn create(Json(school): Json<School>) -> Result<Json<School>, native_json::Error> {
+//!    Ok(Json(save(school)?))
+//!}
+//!```
+//!
+//!```text
+//!HTTP/1.1 422 Unprocessable Entity
+//!
+//!{"error":"students[0].age: invalid type: string \"x\", expected u16 at line 1 column 55","path":"students[0].age","expected":"u16","line":1,"column":55}
+//!```
+//!
+//!
+//!## Redis
+//!
+//!With the `redis` feature, `native_json::redis::RedisJson` adds `set_json(key, &value, ttl)` and `get_json::<T>(key)` to the connections of redis, the values are stored as concise JSON and a missing or expired key is `None`. `set_json_compressed` stores a gzip or zstd compressed value, which is detected when it is read.
+//!
+//!```rust
+//!use native_json::redis::RedisJson;
+//!
+//!cache.set_json("user:1", &user, Some(Duration::from_secs(60)))?;
+//!let user: Option<User> = cache.get_json("user:1")?;
+//!```
+//!
+//!
+//!## sqlx
+//!
+//!With the `sqlx` feature, `native_json::sqlx::Json<T>` stores the declared structs in Postgres `jsonb` or `json` columns, the decoding errors report the field path.
+//!
+//!```rust
+//!use native_json::sqlx::Json;
+//!
+//!sqlx::query("INSERT INTO schools (id, data) VALUES ($1, $2)").bind(1).bind(Json(&school)).execute(&pool).await?;
+//!let (Json(school),): (Json<School>,) = sqlx::query_as("SELECT data FROM schools").fetch_one(&pool).await?;
+//!```
+//!
+//!
+//!## WebAssembly
+//!
+//!With the `wasm` feature, `to_js_value()` and `T::from_js_value(value)` convert the declared structs to and from `JsValue` by serde-wasm-bindgen, the structs are plain objects with the JSON keys, and `native_json::Error` converts into a JS `Error` by `?`.
+//!
+//!```rust
+//!#[wasm_bindgen]
+//!pub fn rename(user: JsValue, name: String) -> Result<JsValue, JsValue> {
+//!    let mut user = User::from_js_value(user)?;
+//!    user.name = name;
+//!    Ok(user.to_js_value()?)
+//!}
+//!```
+//!
+//!
+//!## Python
+//!
+//!With the `python` feature, `to_py(py)` and `T::from_py(&object)` convert the declared structs to and from Python dicts, lists, numbers and strings by PyO3 without JSON text in between. `native_json::python::PyJson<T>` is the argument or the return value of `#[pyfunction]`, the errors are `ValueError` with the field path.
+//!
+//!```rust
+//!use native_json::python::PyJson;
+//!
+//!#[pyfunction]
+//!fn promote(PyJson(mut school): PyJson<School>) -> PyJson<School> {
+//!    school.students.iter_mut().for_each(|x| x.grade += 1);
+//!    PyJson(school)
+//!}
+//!```
+//!
+//!
+//!## Arrow and Parquet
+//!
+//!With the `arrow` feature, `native_json::arrow::to_record_batch(&rows)` converts a slice of declared structs into an Arrow `RecordBatch`, the schema is inferred from the declaration, nested structs are struct columns and arrays are list columns. The `parquet` feature adds `write_parquet` and `read_parquet`.
+//!
+//!```rust
+//!use native_json::arrow;
+//!
+//!let batch = arrow::to_record_batch(&students)?;
+//!let students: Vec<Student> = arrow::from_record_batch(&batch)?;
+//!arrow::write_parquet(File::create("students.parquet")?, &students)?;
+//!```
+//!
+//!
+//!## CSV
+//!
+//!With the `csv` feature, `native_json::csv::to_csv(writer, &rows)` writes a slice of flat declared structs with a header of the JSON keys, and `from_csv(reader)` reads them back, the cells are converted to the field types and the errors report the field path and the line. `CsvOptions::new().flatten(true)` flattens one level of nested structs into dotted columns, e.g. `address.city`.
+//!
+//!```rust
+//!use native_json::csv::{self, CsvOptions};
+//!
+//!csv::to_csv(File::create("students.csv")?, &students)?;
+//!let students: Vec<Student> = csv::from_csv(File::open("students.csv")?)?;
+//!let people: Vec<Person> = csv::from_csv_with(reader, &CsvOptions::new().flatten(true))?;
+//!```
+//!
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "tokio")]
+mod async_io;
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "base64")]
+pub mod base64;
+pub mod canonical;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+pub mod compress;
+pub mod config;
+#[cfg(feature = "csv")]
+pub mod csv;
+#[cfg(any(feature = "chrono", feature = "time"))]
+pub mod datetime;
+#[cfg(any(feature = "sha2", feature = "blake3"))]
+pub mod digest;
+pub mod document;
+pub mod error;
+pub mod extends;
+pub mod flatten;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod interpolate;
+pub mod lines;
+pub mod merge;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod nonfinite;
+pub mod parse;
+pub mod patch;
+mod progress;
+#[cfg(feature = "python")]
+pub mod python;
+#[cfg(feature = "redis")]
+pub mod redis;
+pub mod reflect;
+pub mod rpc;
+#[cfg(feature = "jsonschema")]
+pub mod schema;
+pub mod secret;
+#[cfg(feature = "simd")]
+pub mod simd;
+#[cfg(feature = "sqlx")]
+pub mod sqlx;
+pub mod stream;
+pub mod string;
+pub mod stringify;
+#[cfg(feature = "toml")]
+pub mod toml;
+pub mod track;
+pub mod validate;
+pub mod value;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod watch;
+#[cfg(feature = "serde_yaml")]
+pub mod yaml;
+
+#[cfg(feature = "tokio")]
+pub use async_io::{read_async, read_async_blocking, write_async, write_async_blocking};
+pub use compress::Compression;
+#[cfg(any(feature = "sha2", feature = "blake3"))]
+pub use digest::Digest;
+pub use document::Document;
+pub use error::from_str as parse;
+pub use error::{Error, Result};
+pub use flatten::{flatten, unflatten};
+#[cfg(feature = "http")]
+pub use http::{get, post, HttpOptions};
+pub use native_json_macro::*;
+pub use nonfinite::NonFinite;
+pub use parse::{from_str as parse_with, ReadOptions};
+pub use patch::PatchOp;
+pub use reflect::{FieldInfo, Reflect};
+pub use serde::de::DeserializeOwned;
+pub use serde::{Deserialize, Serialize};
+pub use stream::{parse_many, parse_prefix, stream_array, ArrayReader, ArrayWriter};
+pub use stringify::{FloatFormat, Stringifier, StringifyOptions};
+pub use track::Tracked;
+pub use validate::{Validate, ValidationError, ValidationErrors};
+pub use value::{Value, ValueExt};
+pub use watch::{watch, watch_with, WatchOptions, Watcher};
+
+/// Decimal fields, serialized as string, numbers and strings are deserialized
+#[cfg(feature = "bigdecimal")]
+pub use bigdecimal::BigDecimal;
+#[cfg(feature = "rust_decimal")]
+pub use rust_decimal::Decimal;
+/// UUID fields, initialized to the nil UUID
+#[cfg(feature = "uuid")]
+pub use uuid::Uuid;
+/// Map fields which keep the insertion order of the keys through parse and stringify
+#[cfg(feature = "preserve_order")]
+pub type OrderedMap<K, V> = indexmap::IndexMap<K, V>;
+
+// #[serde(default, skip_serializing_if = "is_default")]
+pub fn is_default<T: Default + PartialEq>(t: &T) -> bool {
+    t == &T::default()
+}
+
+pub trait JSON: Serialize {
+    /// Return a concise JSON string
+    fn string(&self) -> Result<String> {
+        self.stringify(0)
+    }
+
+    /// Stringify a native-json object
+    ///
+    /// indent
+    ///
+    /// - 0 : output concise JSON string
+    /// - N : pretty output with N spaces indentation
+    fn stringify(&self, indent: usize) -> Result<String> {
+        self.stringify_with(&StringifyOptions::new().indent(indent))
+    }
+
+    /// Stringify with the options, e.g. `StringifyOptions::new().indent(2).sort_keys(true)`
+    fn stringify_with(&self, options: &StringifyOptions) -> Result<String> {
+        stringify::to_string(self, options)
+    }
+
+    /// Dynamic value of the serialized shape, e.g. to mix typed and dynamic access
+    fn to_value(&self) -> Result<Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    /// Concise JSON bytes, `to_vec` would shadow the method of arrays and vectors
+    fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+
+    /// MessagePack bytes, structs are maps with the JSON keys
+    #[cfg(feature = "msgpack")]
+    fn to_msgpack(&self) -> Result<Vec<u8>> {
+        msgpack::to_vec(self)
+    }
+
+    /// CBOR bytes, structs are maps with the JSON keys
+    #[cfg(feature = "cbor")]
+    fn to_cbor(&self) -> Result<Vec<u8>> {
+        cbor::to_vec(self)
+    }
+
+    /// JS value of WebAssembly, structs are plain objects with the JSON keys
+    #[cfg(feature = "wasm")]
+    fn to_js_value(&self) -> Result<wasm_bindgen::JsValue> {
+        wasm::to_js_value(self)
+    }
+
+    /// Python object of PyO3, structs are dicts with the JSON keys
+    #[cfg(feature = "python")]
+    fn to_py<'py>(&self, py: pyo3::Python<'py>) -> Result<pyo3::Bound<'py, pyo3::PyAny>> {
+        python::to_py(py, self)
+    }
+
+    /// Write concise JSON into the writer without an intermediate string, e.g. a socket
+    fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<()> {
+        Ok(serde_json::to_writer(writer, self)?)
+    }
+
+    /// Append concise JSON to the string, so the buffer can be reused
+    fn stringify_into(&self, buf: &mut String) -> Result<()> {
+        let mut bytes = std::mem::take(buf).into_bytes();
+        let len = bytes.len();
+        let result = serde_json::to_writer(&mut bytes, self);
+        if result.is_err() {
+            bytes.truncate(len);
+        }
+        // serde_json writes valid UTF-8 only
+        *buf = String::from_utf8(bytes).map_err(Error::custom)?;
+        Ok(result?)
+    }
+
+    /// Stringify with the fields annotated by `@secret` masked, e.g. for logging
+    fn stringify_redacted(&self, indent: usize) -> Result<String> {
+        let _redact = secret::Redact::new();
+        self.stringify(indent)
+    }
+
+    /// Canonical JSON string (RFC 8785), sorted keys, normalized numbers and no whitespace
+    fn canonicalize(&self) -> Result<String> {
+        let value = serde_json::to_value(self)?;
+        Ok(canonical::canonicalize(&value))
+    }
+
+    /// Hex digest of the canonical JSON string, stable for cache keys and change detection
+    #[cfg(any(feature = "sha2", feature = "blake3"))]
+    fn digest(&self, algo: Digest) -> Result<String> {
+        Ok(algo.hex(self.canonicalize()?.as_bytes()))
+    }
+
+    /// Serialized value at the JSON Pointer (RFC 6901), e.g. `/style/size`
+    fn pointer(&self, pointer: &str) -> Option<serde_json::Value> {
+        let value = serde_json::to_value(self).ok()?;
+        value.pointer(pointer).cloned()
+    }
+
+    /// Replace the value at the JSON Pointer, the result is deserialized back into self
+    fn set_pointer(&mut self, pointer: &str, value: serde_json::Value) -> Result<()>
+    where
+        Self: DeserializeOwned,
+    {
+        let mut root = serde_json::to_value(&*self)?;
+        match root.pointer_mut(pointer) {
+            Some(target) => *target = value,
+            None => return Err(Error::Custom(format!("no value at pointer `{}`", pointer))),
+        }
+        *self = error::from_value(root)?;
+        Ok(())
+    }
+
+    /// Deep-merge the other value (RFC 7386), objects are merged recursively, the other values are replaced and null resets the optionals
+    fn merge(&mut self, other: &Self) -> Result<()>
+    where
+        Self: DeserializeOwned,
+    {
+        let patch = serde_json::to_value(other)?;
+        self.merge_value(&patch)
+    }
+
+    /// Deep-merge a partial JSON string, e.g. a user configuration over the defaults
+    fn merge_from_str(&mut self, partial: &str) -> Result<()>
+    where
+        Self: DeserializeOwned,
+    {
+        let patch: serde_json::Value = serde_json::from_str(partial)?;
+        self.merge_value(&patch)
+    }
+
+    /// Override the values by the command line options `--style.size 14` or `--style.size=14`, see [config::Loader::args]
+    fn apply_cli_overrides<I, S>(&mut self, args: I) -> Result<()>
+    where
+        Self: DeserializeOwned,
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let args: Vec<String> = args.into_iter().map(Into::into).collect();
+        let mut root = serde_json::to_value(&*self)?;
+        config::apply_args(&mut root, &args, &mut Default::default())?;
+        *self = error::from_value(root)?;
+        Ok(())
+    }
+
+    /// Deep-merge a JSON merge patch
+    fn merge_value(&mut self, patch: &serde_json::Value) -> Result<()>
+    where
+        Self: DeserializeOwned,
+    {
+        let mut root = serde_json::to_value(&*self)?;
+        merge::merge_patch(&mut root, patch);
+        *self = error::from_value(root)?;
+        Ok(())
+    }
+}
+
+impl<T> JSON for T where T: Serialize {}
+
+/// Parse helpers of the deserializable types, `FromStr` would conflict with `from_str`
+pub trait FromJSON: DeserializeOwned {
+    /// Parse from JSON string
+    fn from_json(s: &str) -> Result<Self, Error> {
+        error::from_str(s)
+    }
 
-pub use native_json_macro::*;
-pub use serde::de::DeserializeOwned;
-pub use serde::{Deserialize, Serialize};
-pub use serde_json::from_str as parse;
-pub use serde_json::Error;
+    /// Parse from JSON bytes
+    fn from_slice(v: &[u8]) -> Result<Self, Error> {
+        error::from_slice(v)
+    }
 
-// #[serde(default, skip_serializing_if = "is_default")]
-pub fn is_default<T: Default + PartialEq>(t: &T) -> bool {
-    t == &T::default()
-}
+    /// Convert from a dynamic value, the errors report the field path
+    fn from_value(value: Value) -> Result<Self, Error> {
+        error::from_value(value)
+    }
 
-pub trait JSON: Serialize {
-    /// Return a concise JSON string
-    fn string(&self) -> anyhow::Result<String> {
-        self.stringify(0)
+    /// Decode from MessagePack bytes
+    #[cfg(feature = "msgpack")]
+    fn from_msgpack(v: &[u8]) -> Result<Self, Error> {
+        msgpack::from_slice(v)
     }
 
-    /// Stringify a native-json object
-    ///
-    /// indent
-    ///
-    /// - 0 : output concise JSON string
-    /// - N : pretty output with N spaces indentation
-    fn stringify(&self, indent: usize) -> anyhow::Result<String> {
-        // concise
-        if indent == 0 {
-            return Ok(serde_json::to_string(self)?);
-        }
+    /// Decode from CBOR bytes
+    #[cfg(feature = "cbor")]
+    fn from_cbor(v: &[u8]) -> Result<Self, Error> {
+        cbor::from_slice(v)
+    }
+
+    /// Convert from a JS value of WebAssembly
+    #[cfg(feature = "wasm")]
+    fn from_js_value(value: wasm_bindgen::JsValue) -> Result<Self, Error> {
+        wasm::from_js_value(value)
+    }
+
+    /// Convert from a Python object of PyO3
+    #[cfg(feature = "python")]
+    fn from_py(object: &pyo3::Bound<'_, pyo3::PyAny>) -> Result<Self, Error> {
+        python::from_py(object)
+    }
 
-        // pretty
-        let buf = Vec::new();
-        let spaces = vec![' ' as u8; indent];
-        let formatter = serde_json::ser::PrettyFormatter::with_indent(&spaces);
-        let mut ser = serde_json::Serializer::with_formatter(buf, formatter);
-        self.serialize(&mut ser)?;
-        let output = String::from_utf8(ser.into_inner())?;
+    /// Parse from the reader, e.g. a file or socket
+    fn from_reader<R: io::Read>(reader: R) -> Result<Self, Error> {
+        error::from_reader(reader)
+    }
 
-        Ok(output)
+    /// Update in place, the fields absent from the input are kept and nested objects are updated recursively
+    fn update_from_str(&mut self, s: &str) -> Result<(), Error>
+    where
+        Self: Serialize,
+    {
+        let input: serde_json::Value = serde_json::from_str(s)?;
+        let mut value = serde_json::to_value(&*self)?;
+        merge::overlay(&mut value, &input);
+        *self = error::from_value(value)?;
+        Ok(())
     }
 }
 
-impl<T> JSON for T where T: Serialize {}
+impl<T> FromJSON for T where T: DeserializeOwned {}
+
+// the decompressed reader of the file
+fn open(path: &Path) -> Result<Box<dyn io::Read>> {
+    compress::decompress(File::open(path)?)
+}
+
+// the decompressed text of the file
+fn read_string(path: &Path) -> Result<String> {
+    let mut text = String::new();
+    open(path)?.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Deserialize from file, gzip and zstd compressed files are detected, parsed by simd-json with the `simd` feature
+pub fn read<T, P: AsRef<Path>>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    // simd-json rewrites the buffer, serde_json parses the file again to report the error with the line and column
+    #[cfg(feature = "simd")]
+    {
+        let mut bytes = Vec::new();
+        open(path.as_ref())?.read_to_end(&mut bytes)?;
+        simd::from_vec(bytes).or_else(|_| error::from_reader(open(path.as_ref())?))
+    }
+    #[cfg(not(feature = "simd"))]
+    {
+        let reader = open(path.as_ref())?;
+        let value = error::from_reader(reader)?;
+        Ok(value)
+    }
+}
+
+/// Deserialize from file with the options, e.g. JSON with comments
+pub fn read_with<T, P: AsRef<Path>>(path: P, options: &ReadOptions) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    if options.is_plain() {
+        return read(path);
+    }
+    parse::from_str(&parse::read_text(open(path.as_ref())?, options)?, options)
+}
+
+/// Deserialize from the memory-mapped file without buffering it, see [mmap] for borrowed fields
+///
+/// # Safety
+///
+/// The file must not be modified or truncated while it is read, see [mmap::MappedFile::open].
+#[cfg(feature = "mmap")]
+pub unsafe fn read_mmap<T, P: AsRef<Path>>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    // SAFETY: the requirement of the caller
+    unsafe { mmap::MappedFile::open(path)? }.parse()
+}
+
+/// Deserialize from the file validated against the JSON Schema, see [schema]
+#[cfg(feature = "jsonschema")]
+pub fn read_validated<T, P: AsRef<Path>>(path: P, schema: &serde_json::Value) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let value = error::from_reader(open(path.as_ref())?)?;
+    schema::validate(&value, schema)?;
+    error::from_value(value)
+}
+
+/// Deserialize from YAML file
+#[cfg(feature = "serde_yaml")]
+pub fn read_yaml<T, P: AsRef<Path>>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    yaml::from_reader(open(path.as_ref())?)
+}
+
+/// Serialize into YAML file
+#[cfg(feature = "serde_yaml")]
+pub fn write_yaml<T, P: AsRef<Path>>(path: P, value: &T) -> Result<()>
+where
+    T: Serialize,
+{
+    writer(&path).format(Format::Yaml).write(value)
+}
+
+/// Deserialize from TOML file
+#[cfg(feature = "toml")]
+pub fn read_toml<T, P: AsRef<Path>>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    toml::from_str(&read_string(path.as_ref())?)
+}
+
+/// Serialize into TOML file
+#[cfg(feature = "toml")]
+pub fn write_toml<T, P: AsRef<Path>>(path: P, value: &T) -> Result<()>
+where
+    T: Serialize,
+{
+    writer(&path).format(Format::Toml).write(value)
+}
+
+// deserialize from file, None if the file does not exist
+fn read_existing<T: DeserializeOwned>(path: &Path) -> Result<Option<T>> {
+    match File::open(path) {
+        Ok(file) => Ok(Some(error::from_reader(compress::decompress(file)?)?)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
 
-/// Deserialize from file
-pub fn read<T, P: AsRef<Path>>(path: P) -> anyhow::Result<T>
+/// Deserialize from file, or the value of the closure if the file does not exist, e.g. `read_or_else(path, Config::new)`,
+/// it still fails if the file is malformed
+pub fn read_or_else<T, P, F>(path: P, f: F) -> Result<T>
 where
     T: DeserializeOwned,
+    P: AsRef<Path>,
+    F: FnOnce() -> T,
+{
+    Ok(read_existing(path.as_ref())?.unwrap_or_else(f))
+}
+
+/// Deserialize from file, or the default value if the file does not exist
+pub fn read_or_default<T, P: AsRef<Path>>(path: P) -> Result<T>
+where
+    T: DeserializeOwned + Default,
+{
+    read_or_else(path, T::default)
+}
+
+/// Deserialize from file, or write the value of the closure back if the file does not exist,
+/// the missing directories are created
+pub fn read_or_create<T, P, F>(path: P, f: F) -> Result<T>
+where
+    T: Serialize + DeserializeOwned,
+    P: AsRef<Path>,
+    F: FnOnce() -> T,
 {
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let value = serde_json::from_reader(reader)?;
+    let path = path.as_ref();
+    if let Some(value) = read_existing(path)? {
+        return Ok(value);
+    }
+    let value = f();
+    writer(&path).create_dirs(true).atomic(true).write(&value)?;
     Ok(value)
 }
 
-/// Serialize into file
-pub fn write<T, P: AsRef<Path>>(path: P, value: &T) -> anyhow::Result<()>
+/// Serialize into file, it is compressed if the extension is `.gz` or `.zst`
+pub fn write<T, P: AsRef<Path>>(path: P, value: &T) -> Result<()>
+where
+    T: Serialize,
+{
+    writer(&path).write(value)
+}
+
+/// Serialize into file atomically, by a temporary file in the same directory which is renamed over the target
+pub fn write_atomic<T, P: AsRef<Path>>(path: P, value: &T) -> Result<()>
 where
     T: Serialize,
 {
+    writer(&path).atomic(true).write(value)
+}
+
+// temporary file in the same directory, so it can be renamed over the target
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let n = COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(".{}.{}.{}.tmp", name, std::process::id(), n))
+}
+
+// write and fsync the temporary file, then rename it over the target, a crash never leaves a truncated file,
+// the permissions are the mode if any, otherwise those of the existing file
+fn atomic<F>(path: &Path, mode: Option<u32>, f: F) -> Result<()>
+where
+    F: FnOnce(&mut BufWriter<File>) -> Result<()>,
+{
+    let temp = temp_path(path);
+    let result = (|| {
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp)?;
+        if let Some(permissions) = permissions(mode) {
+            file.set_permissions(permissions)?;
+        } else if let Ok(meta) = fs::metadata(path) {
+            file.set_permissions(meta.permissions())?;
+        }
+        let mut writer = BufWriter::new(file);
+        f(&mut writer)?;
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::rename(&temp, path)?;
+        sync_dir(path);
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+// permissions of the unix mode, which is ignored on the other platforms
+fn permissions(mode: Option<u32>) -> Option<fs::Permissions> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        mode.map(fs::Permissions::from_mode)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = mode;
+        None
+    }
+}
+
+// create the missing parent directories of the file
+fn create_dirs(path: &Path) -> Result<()> {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => Ok(fs::create_dir_all(dir)?),
+        _ => Ok(()),
+    }
+}
+
+// persist the renamed directory entry, best effort
+fn sync_dir(path: &Path) {
+    #[cfg(unix)]
+    {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        if let Ok(dir) = File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+}
+
+// backup `config.json.{n}` of the file
+fn backup_path(path: &Path, n: usize) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.{}", name, n))
+}
+
+// shift `config.json.{i}` to `config.json.{i + 1}` and copy the file to `config.json.1`, the oldest is dropped
+fn rotate(path: &Path, n: usize) -> Result<()> {
+    if n == 0 || !path.exists() {
+        return Ok(());
+    }
+    for i in (1..n).rev() {
+        let from = backup_path(path, i);
+        if from.exists() {
+            fs::rename(&from, backup_path(path, i + 1))?;
+        }
+    }
+    fs::copy(path, backup_path(path, 1))?;
+    Ok(())
+}
+
+/// Restore the latest backup `config.json.1..n` which can be deserialized, it is copied over the file
+pub fn restore_latest<T, P: AsRef<Path>>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let path = path.as_ref();
+    let mut error = None;
+    for i in 1.. {
+        let backup = backup_path(path, i);
+        if !backup.exists() {
+            break;
+        }
+        match read(&backup) {
+            Ok(value) => {
+                atomic(path, None, |writer| {
+                    io::copy(&mut File::open(&backup)?, writer)?;
+                    Ok(())
+                })?;
+                return Ok(value);
+            }
+            Err(e) => error = Some(e),
+        }
+    }
+    let name = path.display();
+    Err(error.unwrap_or_else(|| Error::Custom(format!("no backup of `{}`", name))))
+}
+
+// advisory lock of the sidecar file `config.json.lock`, which is not replaced by atomic writes,
+// it is released when the file is dropped
+fn lock(path: &Path, exclusive: bool) -> Result<File> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
     let file = OpenOptions::new()
+        .read(true)
         .write(true)
         .create(true)
-        .truncate(true)
-        .open(path)?;
-    let writer = BufWriter::new(file);
-    Ok(serde_json::to_writer_pretty(writer, value)?)
+        .truncate(false)
+        .open(path.with_file_name(format!("{}.lock", name)))?;
+    match exclusive {
+        true => file.lock()?,
+        false => file.lock_shared()?,
+    }
+    Ok(file)
+}
+
+/// Deserialize from file with a shared advisory lock, which waits for the writers of [write_locked]
+pub fn read_locked<T, P: AsRef<Path>>(path: P) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    let _lock = lock(path.as_ref(), false)?;
+    read(path)
+}
+
+/// Serialize into file with an exclusive advisory lock, so the processes sharing the file do not interleave
+pub fn write_locked<T, P: AsRef<Path>>(path: P, value: &T) -> Result<()>
+where
+    T: Serialize,
+{
+    let _lock = lock(path.as_ref(), true)?;
+    write(path, value)
+}
+
+/// File format of [Writer] and [Reader]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    #[default]
+    Json,
+    #[cfg(feature = "serde_yaml")]
+    Yaml,
+    #[cfg(feature = "toml")]
+    Toml,
 }
 
 pub struct Writer<'a> {
     path: &'a Path,
-    indent: usize,
+    options: StringifyOptions,
+    atomic: bool,
+    backup: usize,
+    exclusive: bool,
+    create_dirs: bool,
+    mode: Option<u32>,
+    append: bool,
+    format: Format,
+    compression: Compression,
+    progress: Option<Box<progress::Observer<'a>>>,
+}
+
+// read, modify and write back atomically, holding the exclusive lock if any
+fn modify<T, F>(path: &Path, locked: bool, f: F) -> Result<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+    F: FnOnce(&mut T),
+{
+    let _lock = match locked {
+        true => Some(lock(path, true)?),
+        false => None,
+    };
+    let mut value = read_existing(path)?.unwrap_or_default();
+    f(&mut value);
+    writer(&path).atomic(true).write(&value)?;
+    Ok(value)
+}
+
+/// Read the file or the default value, modify it by the closure and write it back atomically, the value is returned
+pub fn update<T, P, F>(path: P, f: F) -> Result<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+    P: AsRef<Path>,
+    F: FnOnce(&mut T),
+{
+    modify(path.as_ref(), false, f)
+}
+
+/// [update] with an exclusive advisory lock, so the concurrent updates of processes are not lost
+pub fn update_locked<T, P, F>(path: P, f: F) -> Result<T>
+where
+    T: Serialize + DeserializeOwned + Default,
+    P: AsRef<Path>,
+    F: FnOnce(&mut T),
+{
+    modify(path.as_ref(), true, f)
 }
 
 impl<'a> Writer<'a> {
     /// Indentation
     pub fn indent(mut self, n: usize) -> Self {
-        self.indent = n;
+        self.options = self.options.indent(n);
+        self
+    }
+
+    /// Concise output without any whitespace, the same as `indent(0)`
+    pub fn compact(self) -> Self {
+        self.indent(0)
+    }
+
+    /// Sort the keys of all objects
+    pub fn sort_keys(mut self, sort: bool) -> Self {
+        self.options = self.options.sort_keys(sort);
+        self
+    }
+
+    /// Replace all options of stringify, e.g. `StringifyOptions::new().indent(1).indent_char('\t')`
+    pub fn options(mut self, options: StringifyOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Write by a temporary file which is renamed over the target, see [write_atomic]
+    pub fn atomic(mut self, atomic: bool) -> Self {
+        self.atomic = atomic;
+        self
+    }
+
+    /// Keep N rotating backups `config.json.1..n` of the overwritten file, `config.json.1` is the latest
+    pub fn backup(mut self, n: usize) -> Self {
+        self.backup = n;
+        self
+    }
+
+    /// Hold an exclusive advisory lock while writing, see [write_locked]
+    pub fn exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// Append the value as one concise line, JSON Lines for log-style persistence, it is not atomic
+    pub fn append(mut self, append: bool) -> Self {
+        self.append = append;
+        self
+    }
+
+    /// Create the missing parent directories, e.g. of `~/.config/app/settings.json`
+    pub fn create_dirs(mut self, create: bool) -> Self {
+        self.create_dirs = create;
+        self
+    }
+
+    /// Permissions of the written file, e.g. `0o600` for secrets which are only readable by the owner
+    #[cfg(unix)]
+    pub fn mode(mut self, mode: u32) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// File format, JSON by default
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Compression, by the extension `.gz` or `.zst` of the file by default
+    pub fn compress(mut self, compression: Compression) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    /// Observer of the progress `(bytes_written, total)`, the total is unknown unless appending
+    pub fn on_progress<F: Fn(u64, Option<u64>) + Send + Sync + 'a>(mut self, f: F) -> Self {
+        self.progress = Some(Box::new(f));
         self
     }
 
+    // serialize in the format and compress
+    fn encode<W: io::Write, T: Serialize>(&self, writer: W, value: &T) -> Result<()> {
+        compress::compress(writer, self.compression, |writer| {
+            self.serialize(writer, value)
+        })
+    }
+
+    // encode through the progress observer if any
+    fn observe<W: io::Write, T: Serialize>(&self, writer: W, value: &T) -> Result<()> {
+        let observer = match &self.progress {
+            Some(f) => f.as_ref(),
+            None => return self.encode(writer, value),
+        };
+        let mut writer = progress::Progress::new(writer, None, observer);
+        self.encode(&mut writer, value)?;
+        writer.report();
+        Ok(())
+    }
+
+    // serialize in the format
+    fn serialize<W: io::Write, T: Serialize>(&self, writer: W, value: &T) -> Result<()> {
+        match self.format {
+            Format::Json => stringify::to_writer(writer, value, &self.options),
+            #[cfg(feature = "serde_yaml")]
+            Format::Yaml => yaml::to_writer(writer, value),
+            #[cfg(feature = "toml")]
+            Format::Toml => {
+                let mut writer = writer;
+                Ok(writer.write_all(toml::to_string(value)?.as_bytes())?)
+            }
+        }
+    }
+
+    // the value in a concise line for appending, YAML is appended as a document, TOML as is
+    fn line<T: Serialize>(&self, value: &T) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        match self.format {
+            Format::Json => {
+                let options = self.options.clone().indent(0).trailing_newline(true);
+                stringify::to_writer(&mut bytes, value, &options)?;
+            }
+            #[cfg(feature = "serde_yaml")]
+            Format::Yaml => {
+                bytes.extend_from_slice(b"---\n");
+                yaml::to_writer(&mut bytes, value)?;
+            }
+            #[cfg(feature = "toml")]
+            Format::Toml => self.serialize(&mut bytes, value)?,
+        }
+        if self.compression == Compression::None {
+            return Ok(bytes);
+        }
+        // a compressed member or frame, the decoders read the concatenated ones
+        let mut compressed = Vec::new();
+        compress::compress(&mut compressed, self.compression, |writer| {
+            Ok(writer.write_all(&bytes)?)
+        })?;
+        Ok(compressed)
+    }
+
     /// Write the value into file
-    pub fn write<T>(&self, value: &T) -> anyhow::Result<()>
+    pub fn write<T>(&self, value: &T) -> Result<()>
     where
         T: Serialize,
     {
-        let spaces = vec![' ' as u8; self.indent];
+        if self.create_dirs {
+            create_dirs(self.path)?;
+        }
+        let _lock = match self.exclusive {
+            true => Some(lock(self.path, true)?),
+            false => None,
+        };
+        rotate(self.path, self.backup)?;
+        if self.append {
+            let mut file = OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(self.path)?;
+            if let Some(permissions) = permissions(self.mode) {
+                file.set_permissions(permissions)?;
+            }
+            // a single write, so the lines of concurrent appenders do not interleave
+            let line = self.line(value)?;
+            file.write_all(&line)?;
+            if let Some(f) = &self.progress {
+                f(line.len() as u64, Some(line.len() as u64));
+            }
+            return Ok(());
+        }
+        if self.atomic {
+            return atomic(self.path, self.mode, |writer| self.observe(writer, value));
+        }
         let file = OpenOptions::new()
             .write(true)
             .create(true)
             .truncate(true)
             .open(self.path)?;
-        let writer = BufWriter::new(file);
-        let formatter = serde_json::ser::PrettyFormatter::with_indent(&spaces);
-        let mut ser = serde_json::Serializer::with_formatter(writer, formatter);
-        Ok(value.serialize(&mut ser)?)
+        if let Some(permissions) = permissions(self.mode) {
+            file.set_permissions(permissions)?;
+        }
+        self.observe(BufWriter::new(file), value)
     }
 }
 
@@ -223,6 +1980,119 @@ where
 {
     Writer {
         path: path.as_ref(),
-        indent: 2,
+        options: StringifyOptions::new().indent(2),
+        atomic: false,
+        backup: 0,
+        exclusive: false,
+        create_dirs: false,
+        mode: None,
+        append: false,
+        format: Format::Json,
+        compression: Compression::from_path(path.as_ref()),
+        progress: None,
+    }
+}
+
+pub struct Reader<'a> {
+    path: &'a Path,
+    options: ReadOptions,
+    interpolate: bool,
+    format: Format,
+    progress: Option<Box<progress::Observer<'a>>>,
+}
+
+impl<'a> Reader<'a> {
+    /// Accept `//` and `/* */` comments
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.options = self.options.allow_comments(allow);
+        self
+    }
+
+    /// Accept trailing commas
+    pub fn allow_trailing_commas(mut self, allow: bool) -> Self {
+        self.options = self.options.allow_trailing_commas(allow);
+        self
+    }
+
+    /// Replace all options of parse
+    pub fn options(mut self, options: ReadOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Replace `${VAR}` and `${VAR:-default}` in string values by environment variables, see [interpolate]
+    pub fn interpolate(mut self, interpolate: bool) -> Self {
+        self.interpolate = interpolate;
+        self
+    }
+
+    /// File format, JSON by default
+    pub fn format(mut self, format: Format) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Observer of the progress `(bytes_read, total)`, the bytes of the file including the compressed ones
+    pub fn on_progress<F: Fn(u64, Option<u64>) + Send + Sync + 'a>(mut self, f: F) -> Self {
+        self.progress = Some(Box::new(f));
+        self
+    }
+
+    // deserialize in the format through the progress observer
+    fn observe<T: DeserializeOwned>(&self, observer: &progress::Observer) -> Result<T> {
+        let file = File::open(self.path)?;
+        let total = file.metadata()?.len();
+        let reader = compress::decompress(progress::Progress::new(file, Some(total), observer))?;
+        match self.format {
+            Format::Json if self.options.is_plain() => error::from_reader(reader),
+            Format::Json => {
+                parse::from_str(&parse::read_text(reader, &self.options)?, &self.options)
+            }
+            #[cfg(feature = "serde_yaml")]
+            Format::Yaml => yaml::from_reader(reader),
+            #[cfg(feature = "toml")]
+            Format::Toml => toml::from_str(&parse::read_text(reader, &ReadOptions::new())?),
+        }
+    }
+
+    // deserialize in the format
+    fn decode<T: DeserializeOwned>(&self) -> Result<T> {
+        if let Some(f) = &self.progress {
+            return self.observe(f.as_ref());
+        }
+        match self.format {
+            Format::Json => read_with(self.path, &self.options),
+            #[cfg(feature = "serde_yaml")]
+            Format::Yaml => read_yaml(self.path),
+            #[cfg(feature = "toml")]
+            Format::Toml => read_toml(self.path),
+        }
+    }
+
+    /// Read the value from file
+    pub fn read<T>(&self) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        if !self.interpolate {
+            return self.decode();
+        }
+        let mut value: serde_json::Value = self.decode()?;
+        interpolate::interpolate(&mut value)?;
+        error::from_value(value)
+    }
+}
+
+/// Build a file reader
+pub fn reader<'a, P>(path: &'a P) -> Reader<'a>
+where
+    P: AsRef<Path>,
+{
+    Reader {
+        path: path.as_ref(),
+        options: ReadOptions::new(),
+        interpolate: false,
+        format: Format::Json,
+        progress: None,
     }
 }
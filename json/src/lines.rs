@@ -0,0 +1,420 @@
+//! JSON Lines (NDJSON)
+//!
+//! One concise JSON document per line, the standard format of event logs and data pipelines:
+//!
+//! ```rust
+//! let mut log = LinesWriter::append("events.jsonl")?.flush_policy(Flush::EveryLine);
+//! log.write(&event)?;
+//!
+//! // the corrupt lines, e.g. a partial line of a crash, are skipped and counted
+//! let mut reader = LinesReader::<Event>::open("events.jsonl")?.skip_corrupt(true);
+//! for event in &mut reader {
+//!     let event = event?;
+//! }
+//! println!("{} corrupt lines", reader.skipped());
+//!
+//! // follow the appended records like `tail -f`
+//! for event in lines::tail::<Event, _>("events.jsonl") {
+//!     let event = event?;
+//! }
+//!
+//! // parse the lines in parallel, requires the `rayon` feature
+//! let events: Vec<Event> = lines::par_read("events.jsonl")?.collect::<Result<_, _>>()?;
+//! ```
+use crate::{error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Flushing policy of [LinesWriter]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flush {
+    /// Flush when the buffer is full and when the writer is dropped
+    Buffered,
+    /// Flush after every line, the records survive a crash of the process
+    EveryLine,
+    /// Flush after every N lines
+    Every(usize),
+}
+
+/// Writer of one record per line
+pub struct LinesWriter<T, W: Write = BufWriter<File>> {
+    writer: W,
+    flush: Flush,
+    pending: usize,
+    buf: Vec<u8>,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Serialize> LinesWriter<T> {
+    /// Create or truncate the file
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Append to the file, it is created if it does not exist
+    pub fn append<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = OpenOptions::new().append(true).create(true).open(path)?;
+        Ok(Self::new(BufWriter::new(file)))
+    }
+}
+
+impl<T: Serialize, W: Write> LinesWriter<T, W> {
+    /// Write into the writer, e.g. stdout or a socket
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            flush: Flush::Buffered,
+            pending: 0,
+            buf: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Flushing policy, buffered by default
+    pub fn flush_policy(mut self, flush: Flush) -> Self {
+        self.flush = flush;
+        self
+    }
+
+    /// Write the record as one line, a record which fails to serialize writes nothing
+    pub fn write(&mut self, value: &T) -> Result<()> {
+        self.buf.clear();
+        serde_json::to_writer(&mut self.buf, value)?;
+        self.buf.push(b'\n');
+        self.writer.write_all(&self.buf)?;
+        self.pending += 1;
+        match self.flush {
+            Flush::EveryLine => self.flush(),
+            Flush::Every(n) if self.pending >= n => self.flush(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Flush the written records
+    pub fn flush(&mut self) -> Result<()> {
+        self.pending = 0;
+        Ok(self.writer.flush()?)
+    }
+
+    /// Flush and return the writer
+    pub fn into_inner(mut self) -> Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Iterator of the records, one per line, blank lines are skipped
+pub struct LinesReader<T, R: BufRead = BufReader<Box<dyn io::Read>>> {
+    reader: R,
+    line: String,
+    number: usize,
+    skip_corrupt: bool,
+    skipped: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> LinesReader<T> {
+    /// Open the file, gzip and zstd compressed files are detected
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(BufReader::new(crate::open(path.as_ref())?)))
+    }
+}
+
+impl<T: DeserializeOwned, R: BufRead> LinesReader<T, R> {
+    /// Read from the reader, e.g. stdin or a socket
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: String::new(),
+            number: 0,
+            skip_corrupt: false,
+            skipped: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Skip the lines which fail to parse instead of yielding the errors
+    pub fn skip_corrupt(mut self, skip: bool) -> Self {
+        self.skip_corrupt = skip;
+        self
+    }
+
+    /// Number of the skipped corrupt lines
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// Number of the last read line, starting from 1
+    pub fn line(&self) -> usize {
+        self.number
+    }
+}
+
+impl<T: DeserializeOwned, R: BufRead> Iterator for LinesReader<T, R> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.line.clear();
+            match self.reader.read_line(&mut self.line) {
+                Ok(0) => return None,
+                Ok(_) => self.number += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+            let line = self.line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            match error::from_str(line) {
+                Ok(value) => return Some(Ok(value)),
+                Err(_) if self.skip_corrupt => self.skipped += 1,
+                Err(e) => return Some(Err(e.at_line(self.number))),
+            }
+        }
+    }
+}
+
+/// Follower of a growing file, see [tail]
+pub struct Tail<T> {
+    path: PathBuf,
+    file: Option<BufReader<File>>,
+    identity: Option<(u64, u64)>,
+    position: u64,
+    from_start: bool,
+    interval: Duration,
+    line: String,
+    _marker: PhantomData<fn() -> T>,
+}
+
+// device and inode, a rotated file is a different file at the same path
+#[cfg(unix)]
+fn identity(meta: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity(_: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+impl<T: DeserializeOwned> Tail<T> {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the existing records first, only the records appended after the first poll are read by default
+    pub fn from_start(mut self, from_start: bool) -> Self {
+        self.from_start = from_start;
+        self
+    }
+
+    /// Interval of polling the file when there is no new record, 250ms by default
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    // false if the file does not exist yet
+    fn open(&mut self) -> io::Result<bool> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(e) => return Err(e),
+        };
+        self.identity = identity(&file.metadata()?);
+        self.position = match self.from_start {
+            true => 0,
+            false => file.seek(SeekFrom::End(0))?,
+        };
+        // the files which appear after the start, e.g. after rotation, are read from the start
+        self.from_start = true;
+        self.file = Some(BufReader::new(file));
+        Ok(true)
+    }
+
+    /// The next appended record if there is one, it does not block
+    ///
+    /// A partial line is kept until its newline is written. A truncated file is read again from the start, a
+    /// rotated file is read to the end before the new file at the path is followed.
+    pub fn poll(&mut self) -> Option<Result<T>> {
+        loop {
+            if self.file.is_none() {
+                match self.open() {
+                    Ok(true) => {}
+                    Ok(false) => return None,
+                    Err(e) => return Some(Err(e.into())),
+                }
+            }
+            let file = self.file.as_mut()?;
+            match file.read_line(&mut self.line) {
+                Ok(0) => {}
+                Ok(n) => {
+                    self.position += n as u64;
+                    if !self.line.ends_with('\n') {
+                        continue;
+                    }
+                    let line = std::mem::take(&mut self.line);
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    return Some(error::from_str(line.trim()));
+                }
+                Err(e) => {
+                    self.line.clear();
+                    return Some(Err(e.into()));
+                }
+            }
+            let meta = match fs::metadata(&self.path) {
+                Ok(meta) => meta,
+                // rotated and the new file is not created yet
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return None,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if identity(&meta) != self.identity {
+                self.file = None;
+                self.line.clear();
+            } else if meta.len() < self.position {
+                self.line.clear();
+                self.position = 0;
+                if let Err(e) = file.seek(SeekFrom::Start(0)) {
+                    return Some(Err(e.into()));
+                }
+            } else {
+                return None;
+            }
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Iterator for Tail<T> {
+    type Item = Result<T>;
+
+    /// Block until the next record is appended, it never ends
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.poll() {
+                return Some(result);
+            }
+            std::thread::sleep(self.interval);
+        }
+    }
+}
+
+/// Follow the file like `tail -f`, the appended records are yielded, the file may not exist yet
+pub fn tail<T, P>(path: P) -> Tail<T>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    Tail {
+        path: path.as_ref().to_path_buf(),
+        file: None,
+        identity: None,
+        position: 0,
+        from_start: false,
+        interval: Duration::from_millis(250),
+        line: String::new(),
+        _marker: PhantomData,
+    }
+}
+
+/// Iterator of the records parsed in parallel, see [par_read]
+#[cfg(feature = "rayon")]
+pub struct ParLines<T, R: BufRead = BufReader<Box<dyn io::Read>>> {
+    reader: R,
+    chunk_size: usize,
+    number: usize,
+    // text of the chunk and the (number, range) of its lines
+    text: String,
+    lines: Vec<(usize, std::ops::Range<usize>)>,
+    records: std::vec::IntoIter<Result<T>>,
+    done: bool,
+}
+
+#[cfg(feature = "rayon")]
+impl<T: DeserializeOwned + Send, R: BufRead> ParLines<T, R> {
+    /// Read from the reader, e.g. stdin
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            chunk_size: 4 << 20,
+            number: 0,
+            text: String::new(),
+            lines: Vec::new(),
+            records: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    /// Bytes of the lines which are read and parsed together, 4 MiB by default
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes.max(1);
+        self
+    }
+
+    // read the next chunk and parse its lines in parallel
+    fn parse_chunk(&mut self) -> io::Result<()> {
+        use rayon::prelude::*;
+
+        self.text.clear();
+        self.lines.clear();
+        while self.text.len() < self.chunk_size {
+            let start = self.text.len();
+            if self.reader.read_line(&mut self.text)? == 0 {
+                self.done = true;
+                break;
+            }
+            self.number += 1;
+            if !self.text[start..].trim().is_empty() {
+                self.lines.push((self.number, start..self.text.len()));
+            }
+        }
+        let text = &self.text;
+        let records: Vec<Result<T>> = self
+            .lines
+            .par_iter()
+            .map(|(number, range)| {
+                error::from_str(text[range.clone()].trim()).map_err(|e| e.at_line(*number))
+            })
+            .collect();
+        self.records = records.into_iter();
+        Ok(())
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: DeserializeOwned + Send, R: BufRead> Iterator for ParLines<T, R> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(record) = self.records.next() {
+                return Some(record);
+            } else if self.done {
+                return None;
+            } else if let Err(e) = self.parse_chunk() {
+                self.done = true;
+                return Some(Err(e.into()));
+            }
+        }
+    }
+}
+
+/// Parse the records of the file in parallel by rayon, the records are yielded in the order of the lines
+///
+/// The file is read by chunks, the lines of a chunk are parsed on the thread pool while the memory is bounded by
+/// the chunk size. Gzip and zstd compressed files are detected.
+#[cfg(feature = "rayon")]
+pub fn par_read<T, P>(path: P) -> Result<ParLines<T>>
+where
+    T: DeserializeOwned + Send,
+    P: AsRef<Path>,
+{
+    Ok(ParLines::new(BufReader::new(crate::open(path.as_ref())?)))
+}
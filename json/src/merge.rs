@@ -0,0 +1,45 @@
+//! JSON Merge Patch (RFC 7386)
+//!
+//! ```rust
+//! let mut config: Config = native_json::read("default.json")?;
+//! config.merge_from_str(&std::fs::read_to_string("user.json")?)?;
+//! ```
+use serde_json::Value;
+
+/// Apply the merge patch to the target, null removes the key, objects are merged recursively, the other values are replaced
+pub fn merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Default::default());
+    }
+    let Value::Object(map) = target else {
+        return;
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            map.remove(key);
+        } else {
+            merge_patch(map.entry(key.as_str()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Overlay the value on the target, objects are merged recursively, the other values including null are replaced
+pub fn overlay(target: &mut Value, value: &Value) {
+    match (target, value) {
+        (Value::Object(map), Value::Object(other)) => {
+            for (key, value) in other {
+                match map.get_mut(key) {
+                    Some(target) => overlay(target, value),
+                    None => {
+                        map.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, value) => *target = value.clone(),
+    }
+}
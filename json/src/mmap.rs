@@ -0,0 +1,50 @@
+//! Memory-mapped reads of large files
+//!
+//! The file is parsed from the mapped pages without copying it into a buffer, the borrowed fields such as
+//! `&'a str` and `Cow<'a, str>` point into the mapping:
+//!
+//! ```rust
+//! use native_json::mmap::MappedFile;
+//!
+//! // SAFETY: the file is not modified while it is mapped
+//! let school: School = unsafe { native_json::read_mmap("school.json")? };
+//!
+//! let file = unsafe { MappedFile::open("index.json")? };
+//! let index: Index<'_> = file.parse()?;
+//! ```
+//!
+//! The functions are unsafe as `memmap2::Mmap::map`, compressed files are not detected.
+use crate::{error, Result};
+use serde::Deserialize;
+use std::fs::File;
+use std::path::Path;
+
+/// Read-only mapping of a file
+pub struct MappedFile {
+    map: memmap2::Mmap,
+}
+
+impl MappedFile {
+    /// Map the whole file
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified or truncated, by this or another process, while it is mapped, otherwise it is
+    /// undefined behavior.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the file is not modified while it is mapped, which is the requirement of the caller
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self { map })
+    }
+
+    /// Bytes of the file
+    pub fn bytes(&self) -> &[u8] {
+        &self.map
+    }
+
+    /// Parse the file, the borrowed fields point into the mapping
+    pub fn parse<'a, T: Deserialize<'a>>(&'a self) -> Result<T> {
+        error::from_slice(&self.map)
+    }
+}
@@ -0,0 +1,38 @@
+//! MessagePack interop
+//!
+//! The declared structs are encoded as MessagePack maps with the JSON keys, so the JSON declaration stays the single
+//! source of truth of the data model:
+//!
+//! ```rust
+//! let bytes = user.to_msgpack()?;
+//! let user = User::from_msgpack(&bytes)?;
+//! ```
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+impl From<rmp_serde::encode::Error> for Error {
+    fn from(e: rmp_serde::encode::Error) -> Self {
+        Error::Custom(e.to_string())
+    }
+}
+
+impl From<serde_path_to_error::Error<rmp_serde::decode::Error>> for Error {
+    fn from(e: serde_path_to_error::Error<rmp_serde::decode::Error>) -> Self {
+        match e.path().iter().next() {
+            Some(_) => Error::Custom(format!("{}: {}", e.path(), e.inner())),
+            None => Error::Custom(e.into_inner().to_string()),
+        }
+    }
+}
+
+/// Encode the value, structs are maps with the JSON keys
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+    Ok(rmp_serde::to_vec_named(value)?)
+}
+
+/// Decode the value, the errors report the field path
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    let mut de = rmp_serde::Deserializer::new(bytes);
+    Ok(serde_path_to_error::deserialize(&mut de)?)
+}
@@ -0,0 +1,415 @@
+//! Policy of non-finite floats
+//!
+//! serde_json writes NaN and infinities as `null`, [NonFinite] of the stringify options selects a string or an error
+//! with the field path instead:
+//!
+//! ```rust
+//! let options = StringifyOptions::new().non_finite(NonFinite::Error);
+//! let err = json!{ data: { ratio: f64::NAN } }.stringify_with(&options); // non-finite float at `data.ratio`
+//! ```
+use serde::ser::{self, Error as _, Serialize, Serializer};
+use std::cell::RefCell;
+
+/// Serialization of NaN and infinities
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFinite {
+    /// `null`, the same as serde_json
+    Null,
+    /// `"NaN"`, `"Infinity"` or `"-Infinity"`
+    String,
+    /// Fail with the field path, e.g. `students[3].score`
+    Error,
+}
+
+struct State {
+    policy: NonFinite,
+    // keys and indices of the current value, e.g. ["students", "[3]", "score"]
+    path: RefCell<Vec<String>>,
+}
+
+impl State {
+    fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in self.path.borrow().iter() {
+            if !path.is_empty() && !segment.starts_with('[') {
+                path.push('.');
+            }
+            path.push_str(segment);
+        }
+        path
+    }
+
+    fn scoped<R>(&self, segment: String, f: impl FnOnce() -> R) -> R {
+        self.path.borrow_mut().push(segment);
+        let result = f();
+        self.path.borrow_mut().pop();
+        result
+    }
+}
+
+/// The value whose non-finite floats are serialized by the policy
+struct Checked<'a, T: ?Sized> {
+    value: &'a T,
+    state: &'a State,
+}
+
+/// Serialize the value with the policy of non-finite floats
+pub fn serialize<T, S>(value: &T, policy: NonFinite, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize + ?Sized,
+    S: Serializer,
+{
+    let state = State {
+        policy,
+        path: RefCell::new(Vec::new()),
+    };
+    Checked {
+        value,
+        state: &state,
+    }
+    .serialize(serializer)
+}
+
+impl<'a, T: Serialize + ?Sized> Serialize for Checked<'a, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let state = self.state;
+        self.value.serialize(Proxy {
+            inner: serializer,
+            state,
+        })
+    }
+}
+
+struct Proxy<'a, S> {
+    inner: S,
+    state: &'a State,
+}
+
+impl<'a, S: Serializer> Proxy<'a, S> {
+    fn wrap<T: ?Sized>(&self, value: &'a T) -> Checked<'a, T> {
+        Checked {
+            value,
+            state: self.state,
+        }
+    }
+
+    fn non_finite(self, v: f64) -> Result<S::Ok, S::Error> {
+        match self.state.policy {
+            NonFinite::Null => self.inner.serialize_unit(),
+            NonFinite::String if v.is_nan() => self.inner.serialize_str("NaN"),
+            NonFinite::String if v > 0.0 => self.inner.serialize_str("Infinity"),
+            NonFinite::String => self.inner.serialize_str("-Infinity"),
+            NonFinite::Error => {
+                let msg = format!("non-finite float at `{}`", self.state.path());
+                Err(S::Error::custom(msg))
+            }
+        }
+    }
+}
+
+/// Compound values of the proxy, the items are checked
+struct Compound<'a, C> {
+    inner: C,
+    state: &'a State,
+    index: usize,
+    key: String,
+}
+
+impl<'a, C> Compound<'a, C> {
+    fn new(inner: C, state: &'a State) -> Self {
+        Self {
+            inner,
+            state,
+            index: 0,
+            key: String::new(),
+        }
+    }
+
+    fn item<T: ?Sized, R>(&mut self, value: &T, f: impl FnOnce(&mut C, &Checked<T>) -> R) -> R {
+        let segment = format!("[{}]", self.index);
+        self.index += 1;
+        let checked = Checked {
+            value,
+            state: self.state,
+        };
+        self.state.scoped(segment, || f(&mut self.inner, &checked))
+    }
+
+    fn field<T: ?Sized, R>(
+        &mut self,
+        key: String,
+        value: &T,
+        f: impl FnOnce(&mut C, &Checked<T>) -> R,
+    ) -> R {
+        let checked = Checked {
+            value,
+            state: self.state,
+        };
+        self.state.scoped(key, || f(&mut self.inner, &checked))
+    }
+}
+
+macro_rules! delegate {
+    ($($method:ident($($arg:ident: $t:ty),*);)*) => {
+        $(fn $method(self, $($arg: $t),*) -> Result<S::Ok, S::Error> {
+            self.inner.$method($($arg),*)
+        })*
+    };
+}
+
+impl<'a, S: Serializer> Serializer for Proxy<'a, S> {
+    type Ok = S::Ok;
+    type Error = S::Error;
+    type SerializeSeq = Compound<'a, S::SerializeSeq>;
+    type SerializeTuple = Compound<'a, S::SerializeTuple>;
+    type SerializeTupleStruct = Compound<'a, S::SerializeTupleStruct>;
+    type SerializeTupleVariant = Compound<'a, S::SerializeTupleVariant>;
+    type SerializeMap = Compound<'a, S::SerializeMap>;
+    type SerializeStruct = Compound<'a, S::SerializeStruct>;
+    type SerializeStructVariant = Compound<'a, S::SerializeStructVariant>;
+
+    delegate! {
+        serialize_bool(v: bool);
+        serialize_i8(v: i8);
+        serialize_i16(v: i16);
+        serialize_i32(v: i32);
+        serialize_i64(v: i64);
+        serialize_i128(v: i128);
+        serialize_u8(v: u8);
+        serialize_u16(v: u16);
+        serialize_u32(v: u32);
+        serialize_u64(v: u64);
+        serialize_u128(v: u128);
+        serialize_char(v: char);
+        serialize_str(v: &str);
+        serialize_bytes(v: &[u8]);
+        serialize_none();
+        serialize_unit();
+        serialize_unit_struct(name: &'static str);
+        serialize_unit_variant(name: &'static str, index: u32, variant: &'static str);
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<S::Ok, S::Error> {
+        match v.is_finite() {
+            true => self.inner.serialize_f32(v),
+            false => self.non_finite(v as f64),
+        }
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<S::Ok, S::Error> {
+        match v.is_finite() {
+            true => self.inner.serialize_f64(v),
+            false => self.non_finite(v),
+        }
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<S::Ok, S::Error> {
+        let checked = self.wrap(value);
+        self.inner.serialize_some(&checked)
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let checked = self.wrap(value);
+        self.inner.serialize_newtype_struct(name, &checked)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<S::Ok, S::Error> {
+        let checked = self.wrap(value);
+        let inner = self.inner;
+        let segment = variant.to_owned();
+        self.state.scoped(segment, || {
+            inner.serialize_newtype_variant(name, index, variant, &checked)
+        })
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, S::Error> {
+        Ok(Compound::new(self.inner.serialize_seq(len)?, self.state))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple, S::Error> {
+        Ok(Compound::new(self.inner.serialize_tuple(len)?, self.state))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct, S::Error> {
+        let inner = self.inner.serialize_tuple_struct(name, len)?;
+        Ok(Compound::new(inner, self.state))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant, S::Error> {
+        let inner = self
+            .inner
+            .serialize_tuple_variant(name, index, variant, len)?;
+        Ok(Compound::new(inner, self.state))
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap, S::Error> {
+        Ok(Compound::new(self.inner.serialize_map(len)?, self.state))
+    }
+
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStruct, S::Error> {
+        let inner = self.inner.serialize_struct(name, len)?;
+        Ok(Compound::new(inner, self.state))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant, S::Error> {
+        let inner = self
+            .inner
+            .serialize_struct_variant(name, index, variant, len)?;
+        Ok(Compound::new(inner, self.state))
+    }
+
+    fn is_human_readable(&self) -> bool {
+        self.inner.is_human_readable()
+    }
+}
+
+impl<'a, C: ser::SerializeSeq> ser::SerializeSeq for Compound<'a, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.item(value, |inner, checked| inner.serialize_element(checked))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, C: ser::SerializeTuple> ser::SerializeTuple for Compound<'a, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.item(value, |inner, checked| inner.serialize_element(checked))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, C: ser::SerializeTupleStruct> ser::SerializeTupleStruct for Compound<'a, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.item(value, |inner, checked| inner.serialize_field(checked))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, C: ser::SerializeTupleVariant> ser::SerializeTupleVariant for Compound<'a, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        self.item(value, |inner, checked| inner.serialize_field(checked))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, C: ser::SerializeMap> ser::SerializeMap for Compound<'a, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), C::Error> {
+        self.key = match serde_json::to_value(key) {
+            Ok(serde_json::Value::String(s)) => s,
+            Ok(other) => other.to_string(),
+            Err(_) => "?".to_owned(),
+        };
+        self.inner.serialize_key(key)
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), C::Error> {
+        let key = std::mem::take(&mut self.key);
+        self.field(key, value, |inner, checked| inner.serialize_value(checked))
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, C: ser::SerializeStruct> ser::SerializeStruct for Compound<'a, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.field(key.to_owned(), value, |inner, checked| {
+            inner.serialize_field(key, checked)
+        })
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
+
+impl<'a, C: ser::SerializeStructVariant> ser::SerializeStructVariant for Compound<'a, C> {
+    type Ok = C::Ok;
+    type Error = C::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), C::Error> {
+        self.field(key.to_owned(), value, |inner, checked| {
+            inner.serialize_field(key, checked)
+        })
+    }
+
+    fn skip_field(&mut self, key: &'static str) -> Result<(), C::Error> {
+        self.inner.skip_field(key)
+    }
+
+    fn end(self) -> Result<C::Ok, C::Error> {
+        self.inner.end()
+    }
+}
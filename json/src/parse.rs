@@ -0,0 +1,290 @@
+//! Options of parse
+//!
+//! The lenient mode accepts JSON with comments and trailing commas, e.g. the config files of VS Code:
+//!
+//! ```rust
+//! let options = ReadOptions::new().allow_comments(true).allow_trailing_commas(true);
+//! let config: Config = native_json::read_with("settings.json", &options)?;
+//! let config: Config = native_json::parse_with("{\"port\": 80, // http\n}", &options)?;
+//! ```
+//!
+//! The limits reject the untrusted input before it is deserialized, e.g. the bodies of requests:
+//!
+//! ```rust
+//! let options = ReadOptions::new().max_bytes(1 << 20).max_depth(32);
+//! let request: Request = native_json::parse_with(&body, &options)?;
+//! ```
+use crate::{error, Error, Result};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::fmt;
+use std::io::{self, Read};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadOptions {
+    allow_comments: bool,
+    allow_trailing_commas: bool,
+    max_bytes: Option<usize>,
+    max_depth: Option<usize>,
+    deny_duplicate_keys: bool,
+}
+
+impl ReadOptions {
+    /// Strict JSON
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Accept `// line` and `/* block */` comments
+    pub fn allow_comments(mut self, allow: bool) -> Self {
+        self.allow_comments = allow;
+        self
+    }
+
+    /// Accept a comma after the last element of arrays and objects
+    pub fn allow_trailing_commas(mut self, allow: bool) -> Self {
+        self.allow_trailing_commas = allow;
+        self
+    }
+
+    /// Reject the input larger than N bytes, a file is not read beyond the limit
+    pub fn max_bytes(mut self, n: usize) -> Self {
+        self.max_bytes = Some(n);
+        self
+    }
+
+    /// Reject the arrays and objects nested deeper than N levels, serde_json rejects 128 levels regardless
+    pub fn max_depth(mut self, n: usize) -> Self {
+        self.max_depth = Some(n);
+        self
+    }
+
+    /// Reject the objects with duplicate keys instead of taking the last value, the error reports the key path
+    pub fn deny_duplicate_keys(mut self, deny: bool) -> Self {
+        self.deny_duplicate_keys = deny;
+        self
+    }
+
+    pub(crate) fn is_strict(&self) -> bool {
+        !self.allow_comments && !self.allow_trailing_commas
+    }
+
+    // strict JSON without limits, which is deserialized from the reader directly
+    pub(crate) fn is_plain(&self) -> bool {
+        self.is_strict()
+            && self.max_bytes.is_none()
+            && self.max_depth.is_none()
+            && !self.deny_duplicate_keys
+    }
+}
+
+// end of the string literal which starts at i, after the closing quote
+fn skip_string(bytes: &[u8], mut i: usize) -> usize {
+    i += 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 2,
+            b'"' => return i + 1,
+            _ => i += 1,
+        }
+    }
+    bytes.len()
+}
+
+// blank the comments, the newlines are kept so the errors report the original lines and columns
+fn strip_comments(bytes: &mut [u8]) {
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'"', _) => i = skip_string(bytes, i),
+            (b'/', Some(b'/')) => {
+                while i < bytes.len() && bytes[i] != b'\n' {
+                    bytes[i] = b' ';
+                    i += 1;
+                }
+            }
+            (b'/', Some(b'*')) => {
+                // an unterminated comment is kept, serde_json reports the syntax error at its start
+                let Some(p) = bytes[i + 2..].windows(2).position(|w| w == b"*/") else {
+                    return;
+                };
+                let end = i + 2 + p + 2;
+                for b in &mut bytes[i..end] {
+                    if !b.is_ascii_whitespace() {
+                        *b = b' ';
+                    }
+                }
+                i = end;
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+// blank the commas which are followed by `]` or `}`
+fn strip_trailing_commas(bytes: &mut [u8]) {
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => i = skip_string(bytes, i),
+            b',' => {
+                let next = bytes[i + 1..].iter().find(|b| !b.is_ascii_whitespace());
+                if matches!(next, Some(b']' | b'}')) {
+                    bytes[i] = b' ';
+                }
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+}
+
+/// Strict JSON of the text, comments and trailing commas are replaced by spaces if they are allowed
+pub fn strip<'a>(text: &'a str, options: &ReadOptions) -> Cow<'a, str> {
+    if options.is_strict() || !text.contains(['/', ',']) {
+        return Cow::Borrowed(text);
+    }
+    let mut bytes = text.as_bytes().to_vec();
+    if options.allow_comments {
+        strip_comments(&mut bytes);
+    }
+    if options.allow_trailing_commas {
+        strip_trailing_commas(&mut bytes);
+    }
+    // only ASCII bytes outside of the string literals are replaced, or all bytes of a comment, it is still UTF-8
+    Cow::Owned(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+// whether the arrays and objects are nested deeper than max
+fn exceeds_depth(bytes: &[u8], max: usize) -> bool {
+    let mut depth = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => i = skip_string(bytes, i),
+            b'[' | b'{' => {
+                depth += 1;
+                if depth > max {
+                    return true;
+                }
+                i += 1;
+            }
+            b']' | b'}' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    false
+}
+
+// any value, the objects with duplicate keys are rejected
+struct Unique;
+
+impl<'de> Deserialize<'de> for Unique {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(UniqueVisitor)
+    }
+}
+
+// the duplicate value fails, so the path of the error ends with the key
+struct Duplicate;
+
+impl<'de> DeserializeSeed<'de> for Duplicate {
+    type Value = Unique;
+
+    fn deserialize<D: Deserializer<'de>>(self, _: D) -> Result<Unique, D::Error> {
+        Err(de::Error::custom("duplicate key"))
+    }
+}
+
+struct UniqueVisitor;
+
+impl<'de> Visitor<'de> for UniqueVisitor {
+    type Value = Unique;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any JSON value")
+    }
+
+    fn visit_bool<E>(self, _: bool) -> Result<Unique, E> {
+        Ok(Unique)
+    }
+
+    fn visit_i64<E>(self, _: i64) -> Result<Unique, E> {
+        Ok(Unique)
+    }
+
+    fn visit_u64<E>(self, _: u64) -> Result<Unique, E> {
+        Ok(Unique)
+    }
+
+    fn visit_f64<E>(self, _: f64) -> Result<Unique, E> {
+        Ok(Unique)
+    }
+
+    fn visit_str<E>(self, _: &str) -> Result<Unique, E> {
+        Ok(Unique)
+    }
+
+    fn visit_unit<E>(self) -> Result<Unique, E> {
+        Ok(Unique)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Unique, A::Error> {
+        while seq.next_element::<Unique>()?.is_some() {}
+        Ok(Unique)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Unique, A::Error> {
+        let mut keys = HashSet::new();
+        while let Some(key) = map.next_key::<String>()? {
+            match keys.insert(key) {
+                true => map.next_value::<Unique>()?,
+                false => map.next_value_seed(Duplicate)?,
+            };
+        }
+        Ok(Unique)
+    }
+}
+
+// the text of the reader, it is not read beyond max_bytes
+pub(crate) fn read_text<R: Read>(reader: R, options: &ReadOptions) -> Result<String> {
+    let limit = options.max_bytes.map_or(u64::MAX, |max| max as u64 + 1);
+    let mut bytes = Vec::new();
+    reader.take(limit).read_to_end(&mut bytes)?;
+    if let Some(max) = options.max_bytes.filter(|max| bytes.len() > *max) {
+        return Err(Error::Limit {
+            limit: "max_bytes",
+            max,
+        });
+    }
+    String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+}
+
+/// Deserialize from the text with the options
+pub fn from_str<T: DeserializeOwned>(text: &str, options: &ReadOptions) -> Result<T> {
+    if let Some(max) = options.max_bytes.filter(|max| text.len() > *max) {
+        return Err(Error::Limit {
+            limit: "max_bytes",
+            max,
+        });
+    }
+    let text = strip(text, options);
+    if let Some(max) = options
+        .max_depth
+        .filter(|max| exceeds_depth(text.as_bytes(), *max))
+    {
+        return Err(Error::Limit {
+            limit: "max_depth",
+            max,
+        });
+    }
+    if options.deny_duplicate_keys {
+        error::from_str::<Unique>(&text)?;
+    }
+    error::from_str(&text)
+}
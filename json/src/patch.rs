@@ -0,0 +1,196 @@
+//! JSON Patch (RFC 6902)
+//!
+//! [diff] computes the operations which turn a value into another, [apply] applies them later, e.g. to sync state over the network:
+//!
+//! ```rust
+//! let ops = native_json::patch::diff(&before, &after)?;
+//! let text = serde_json::to_string(&ops)?; // [{"op":"replace","path":"/port","value":8080}]
+//! native_json::patch::apply(&mut copy, &ops)?;
+//! ```
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+/// An operation of JSON Patch, the paths are JSON Pointers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum PatchOp {
+    Add { path: String, value: Value },
+    Remove { path: String },
+    Replace { path: String, value: Value },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: Value },
+}
+
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
+
+fn diff_value(path: &str, a: &Value, b: &Value, ops: &mut Vec<PatchOp>) {
+    match (a, b) {
+        _ if a == b => {}
+        (Value::Object(x), Value::Object(y)) => {
+            for (key, value) in x {
+                let path = format!("{}/{}", path, escape(key));
+                match y.get(key) {
+                    Some(other) => diff_value(&path, value, other, ops),
+                    None => ops.push(PatchOp::Remove { path }),
+                }
+            }
+            for (key, value) in y.iter().filter(|(k, _)| !x.contains_key(*k)) {
+                let path = format!("{}/{}", path, escape(key));
+                let value = value.clone();
+                ops.push(PatchOp::Add { path, value });
+            }
+        }
+        (Value::Array(x), Value::Array(y)) => {
+            for (i, (value, other)) in x.iter().zip(y).enumerate() {
+                diff_value(&format!("{}/{}", path, i), value, other, ops);
+            }
+            for value in y.iter().skip(x.len()) {
+                let path = format!("{}/-", path);
+                let value = value.clone();
+                ops.push(PatchOp::Add { path, value });
+            }
+            // remove from the end, so the indices are stable
+            for i in (y.len()..x.len()).rev() {
+                let path = format!("{}/{}", path, i);
+                ops.push(PatchOp::Remove { path });
+            }
+        }
+        _ => {
+            let path = path.to_owned();
+            let value = b.clone();
+            ops.push(PatchOp::Replace { path, value });
+        }
+    }
+}
+
+/// Operations which turn the serialized `a` into `b`
+pub fn diff<T: Serialize>(a: &T, b: &T) -> Result<Vec<PatchOp>> {
+    let mut ops = Vec::new();
+    let a = serde_json::to_value(a)?;
+    let b = serde_json::to_value(b)?;
+    diff_value("", &a, &b, &mut ops);
+    Ok(ops)
+}
+
+// parent pointer and the unescaped last token
+fn split(path: &str) -> Result<(&str, String)> {
+    match path.rfind('/') {
+        Some(i) => Ok((&path[..i], unescape(&path[i + 1..]))),
+        None => Err(Error::Custom(format!("invalid pointer `{}`", path))),
+    }
+}
+
+fn index(token: &str, len: usize, path: &str) -> Result<usize> {
+    match token.parse::<usize>() {
+        Ok(i) if i < len && (token == "0" || !token.starts_with('0')) => Ok(i),
+        _ => Err(Error::Custom(format!("invalid array index at `{}`", path))),
+    }
+}
+
+fn add(root: &mut Value, path: &str, value: Value) -> Result<()> {
+    if path.is_empty() {
+        *root = value;
+        return Ok(());
+    }
+    let (parent, token) = split(path)?;
+    match root.pointer_mut(parent) {
+        Some(Value::Object(map)) => {
+            map.insert(token, value);
+        }
+        Some(Value::Array(array)) if token == "-" => array.push(value),
+        Some(Value::Array(array)) => {
+            let i = index(&token, array.len() + 1, path)?;
+            array.insert(i, value);
+        }
+        _ => return Err(Error::Custom(format!("no container at `{}`", parent))),
+    }
+    Ok(())
+}
+
+fn remove(root: &mut Value, path: &str) -> Result<Value> {
+    let (parent, token) = split(path)?;
+    let removed = match root.pointer_mut(parent) {
+        Some(Value::Object(map)) => map.remove(&token),
+        Some(Value::Array(array)) => {
+            let i = index(&token, array.len(), path)?;
+            Some(array.remove(i))
+        }
+        _ => None,
+    };
+    match removed {
+        Some(value) => Ok(value),
+        None => Err(Error::Custom(format!("no value at `{}`", path))),
+    }
+}
+
+fn get(root: &Value, path: &str) -> Result<Value> {
+    match root.pointer(path) {
+        Some(value) => Ok(value.clone()),
+        None => Err(Error::Custom(format!("no value at `{}`", path))),
+    }
+}
+
+/// Apply the operations to a JSON value, the value is unchanged if any operation fails
+pub fn apply_value(value: &mut Value, ops: &[PatchOp]) -> Result<()> {
+    let mut root = value.clone();
+    for op in ops {
+        match op {
+            PatchOp::Add { path, value } => add(&mut root, path, value.clone())?,
+            PatchOp::Remove { path } => {
+                remove(&mut root, path)?;
+            }
+            PatchOp::Replace { path, value } => match root.pointer_mut(path) {
+                Some(target) => *target = value.clone(),
+                None => return Err(Error::Custom(format!("no value at `{}`", path))),
+            },
+            PatchOp::Move { from, path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(Error::Custom(format!("cannot move `{}` into itself", from)));
+                }
+                let value = remove(&mut root, from)?;
+                add(&mut root, path, value)?;
+            }
+            PatchOp::Copy { from, path } => {
+                let value = get(&root, from)?;
+                add(&mut root, path, value)?;
+            }
+            PatchOp::Test { path, value } => {
+                if &get(&root, path)? != value {
+                    return Err(Error::Custom(format!("test failed at `{}`", path)));
+                }
+            }
+        }
+    }
+    *value = root;
+    Ok(())
+}
+
+/// Apply the operations to the serialized value, the result is deserialized back
+pub fn apply<T>(value: &mut T, ops: &[PatchOp]) -> Result<()>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let mut root = serde_json::to_value(&*value)?;
+    apply_value(&mut root, ops)?;
+    *value = crate::error::from_value(root)?;
+    Ok(())
+}
+
+/// Deserialize a present value as `Some`, e.g. `null` of an `Option<T>` field in `NamePatch` of `#[json(patch)]`
+/// is `Some(None)` which clears the field, the absent key is `None` with `#[serde(default)]`
+pub fn present<'de, T, D>(d: D) -> std::result::Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(d).map(Some)
+}
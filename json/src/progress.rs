@@ -0,0 +1,67 @@
+//! Progress of reading and writing files
+use std::io::{self, Read, Write};
+
+// observer of (bytes done, total bytes), shareable so the futures of `write_async` are Send
+pub(crate) type Observer<'a> = dyn Fn(u64, Option<u64>) + Send + Sync + 'a;
+
+// the observer is called after every STEP bytes and at the end
+pub(crate) const STEP: u64 = 64 << 10;
+
+// reader or writer which reports (bytes done, total bytes) to the observer, the total is unknown while writing
+pub(crate) struct Progress<'a, T> {
+    inner: T,
+    done: u64,
+    reported: Option<u64>,
+    total: Option<u64>,
+    observer: &'a Observer<'a>,
+}
+
+impl<'a, T> Progress<'a, T> {
+    pub(crate) fn new(inner: T, total: Option<u64>, observer: &'a Observer<'a>) -> Self {
+        Self {
+            inner,
+            done: 0,
+            reported: None,
+            total,
+            observer,
+        }
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.done += n as u64;
+        if self.done - self.reported.unwrap_or(0) >= STEP {
+            self.report();
+        }
+    }
+
+    // report the bytes done unless they are reported
+    pub(crate) fn report(&mut self) {
+        if self.reported != Some(self.done) {
+            self.reported = Some(self.done);
+            (self.observer)(self.done, self.total);
+        }
+    }
+}
+
+impl<T: Read> Read for Progress<'_, T> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        match n {
+            0 if !buf.is_empty() => self.report(),
+            n => self.advance(n),
+        }
+        Ok(n)
+    }
+}
+
+impl<T: Write> Write for Progress<'_, T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.advance(n);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,155 @@
+//! Python interop by PyO3
+//!
+//! The declared structs are converted to and from Python dicts, lists, numbers and strings directly, without JSON
+//! text in between. [PyJson] is the argument or the return value of `#[pyfunction]`:
+//!
+//! ```rust
+//! use native_json::python::PyJson;
+//!
+//! #[pyfunction]
+//! fn promote(PyJson(mut school): PyJson<School>) -> PyJson<School> {
+//!     school.students.iter_mut().for_each(|x| x.grade += 1);
+//!     PyJson(school)
+//! }
+//!
+//! let dict = school.to_py(py)?;
+//! let school = School::from_py(&dict)?;
+//! ```
+//!
+//! The errors are `ValueError` with the field path, e.g. `students[0].age: invalid type: string "x", expected u16`.
+use crate::{error, Error, Result};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBool, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{Map, Number, Value};
+
+/// `ValueError` of the message
+impl From<Error> for PyErr {
+    fn from(e: Error) -> Self {
+        PyValueError::new_err(e.to_string())
+    }
+}
+
+/// Argument or return value of the Python functions, see the [module](self)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PyJson<T>(pub T);
+
+impl<'py, T: DeserializeOwned> FromPyObject<'py> for PyJson<T> {
+    fn extract_bound(object: &Bound<'py, PyAny>) -> PyResult<Self> {
+        Ok(PyJson(from_py(object)?))
+    }
+}
+
+impl<'py, T: Serialize> IntoPyObject<'py> for PyJson<T> {
+    type Target = PyAny;
+    type Output = Bound<'py, PyAny>;
+    type Error = PyErr;
+
+    fn into_pyobject(self, py: Python<'py>) -> PyResult<Self::Output> {
+        Ok(to_py(py, &self.0)?)
+    }
+}
+
+/// Python object of the value, structs are dicts with the JSON keys
+pub fn to_py<'py, T: Serialize + ?Sized>(py: Python<'py>, value: &T) -> Result<Bound<'py, PyAny>> {
+    let value = serde_json::to_value(value)?;
+    into_py(py, &value).map_err(Error::custom)
+}
+
+/// Convert from a Python object, the errors report the field path
+pub fn from_py<T: DeserializeOwned>(object: &Bound<'_, PyAny>) -> Result<T> {
+    error::from_value(to_value(object, "")?)
+}
+
+fn into_py<'py>(py: Python<'py>, value: &Value) -> PyResult<Bound<'py, PyAny>> {
+    let object = match value {
+        Value::Null => py.None().into_bound(py),
+        Value::Bool(b) => PyBool::new(py, *b).to_owned().into_any(),
+        Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(i), _) => i.into_pyobject(py)?.into_any(),
+            (_, Some(u)) => u.into_pyobject(py)?.into_any(),
+            _ => PyFloat::new(py, n.as_f64().unwrap_or(f64::NAN)).into_any(),
+        },
+        Value::String(s) => PyString::new(py, s).into_any(),
+        Value::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(into_py(py, item)?)?;
+            }
+            list.into_any()
+        }
+        Value::Object(map) => {
+            let dict = PyDict::new(py);
+            for (key, value) in map {
+                dict.set_item(key, into_py(py, value)?)?;
+            }
+            dict.into_any()
+        }
+    };
+    Ok(object)
+}
+
+// the error at the path of the object
+fn invalid(path: &str, message: String) -> Error {
+    match path.is_empty() {
+        true => Error::Custom(message),
+        false => Error::Custom(format!("{}: {}", path, message)),
+    }
+}
+
+// the JSON value of None, bools, numbers, strings, lists, tuples and dicts with string keys
+fn to_value(object: &Bound<'_, PyAny>, path: &str) -> Result<Value> {
+    if object.is_none() {
+        return Ok(Value::Null);
+    }
+    // bool is a subclass of int
+    if let Ok(b) = object.downcast::<PyBool>() {
+        return Ok(Value::Bool(b.is_true()));
+    }
+    if object.is_instance_of::<PyInt>() {
+        if let Ok(i) = object.extract::<i64>() {
+            return Ok(i.into());
+        }
+        return match object.extract::<u64>() {
+            Ok(u) => Ok(u.into()),
+            Err(_) => Err(invalid(path, "integer out of range".to_owned())),
+        };
+    }
+    if let Ok(f) = object.downcast::<PyFloat>() {
+        let number = Number::from_f64(f.value());
+        return number
+            .map(Value::Number)
+            .ok_or_else(|| invalid(path, format!("non-finite number {}", f.value())));
+    }
+    if let Ok(s) = object.downcast::<PyString>() {
+        let s = s.to_str().map_err(|e| invalid(path, e.to_string()))?;
+        return Ok(Value::String(s.to_owned()));
+    }
+    if let Ok(dict) = object.downcast::<PyDict>() {
+        let mut map = Map::new();
+        for (key, value) in dict.iter() {
+            let key = match key.downcast::<PyString>() {
+                Ok(key) => key.to_string(),
+                Err(_) => return Err(invalid(path, format!("key {} is not a string", key))),
+            };
+            let child = match path.is_empty() {
+                true => key.clone(),
+                false => format!("{}.{}", path, key),
+            };
+            map.insert(key, to_value(&value, &child)?);
+        }
+        return Ok(Value::Object(map));
+    }
+    if object.is_instance_of::<PyList>() || object.is_instance_of::<PyTuple>() {
+        let mut items = Vec::new();
+        for (i, item) in object.try_iter().map_err(Error::custom)?.enumerate() {
+            let item = item.map_err(Error::custom)?;
+            items.push(to_value(&item, &format!("{}[{}]", path, i))?);
+        }
+        return Ok(Value::Array(items));
+    }
+    let name = object.get_type().to_string();
+    Err(invalid(path, format!("unsupported type {}", name)))
+}
@@ -0,0 +1,84 @@
+//! Redis cache of the declared structs
+//!
+//! [RedisJson] is implemented for the connections of redis, the values are stored as concise JSON, optionally
+//! compressed:
+//!
+//! ```rust
+//! use native_json::redis::RedisJson;
+//!
+//! let mut cache = redis::Client::open("redis://127.0.0.1/")?.get_connection()?;
+//! cache.set_json("user:1", &user, Some(Duration::from_secs(60)))?;
+//! let user: Option<User> = cache.get_json("user:1")?;
+//!
+//! cache.set_json_compressed("report", &report, None, Compression::Zstd(3))?;
+//! ```
+//!
+//! The compressed values are detected by the magic bytes when they are read.
+use crate::{compress, error, Compression, Error, Result, JSON};
+use ::redis::{ConnectionLike, RedisError};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io;
+use std::time::Duration;
+
+fn transport(e: RedisError) -> Error {
+    let kind = match e.is_timeout() {
+        true => io::ErrorKind::TimedOut,
+        false => io::ErrorKind::Other,
+    };
+    Error::Io(io::Error::new(kind, e))
+}
+
+/// JSON values of the keys
+pub trait RedisJson: ConnectionLike + Sized {
+    /// Set the key to the value, it expires after the TTL if any
+    fn set_json<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        self.set_json_compressed(key, value, ttl, Compression::None)
+    }
+
+    /// Set the key to the compressed value, e.g. large documents
+    fn set_json_compressed<T: Serialize>(
+        &mut self,
+        key: &str,
+        value: &T,
+        ttl: Option<Duration>,
+        compression: Compression,
+    ) -> Result<()> {
+        let mut bytes = value.to_bytes()?;
+        if compression != Compression::None {
+            let mut compressed = Vec::new();
+            compress::compress(&mut compressed, compression, |writer| {
+                Ok(writer.write_all(&bytes)?)
+            })?;
+            bytes = compressed;
+        }
+        let mut command = ::redis::cmd("SET");
+        command.arg(key).arg(bytes);
+        if let Some(ttl) = ttl {
+            // the least TTL is 1 millisecond, 0 is rejected by redis
+            command.arg("PX").arg(ttl.as_millis().max(1) as u64);
+        }
+        command.query::<()>(self).map_err(transport)
+    }
+
+    /// Value of the key, None if the key does not exist or it has expired
+    fn get_json<T: DeserializeOwned>(&mut self, key: &str) -> Result<Option<T>> {
+        let bytes: Option<Vec<u8>> = ::redis::cmd("GET")
+            .arg(key)
+            .query(self)
+            .map_err(transport)?;
+        match bytes {
+            Some(bytes) => Ok(Some(error::from_slice(&compress::decompress_bytes(
+                bytes,
+            )?)?)),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<C: ConnectionLike> RedisJson for C {}
@@ -0,0 +1,67 @@
+//! Runtime reflection of declared structs
+//!
+//! The `reflect` option of declaration generates an implementation of [Reflect]:
+//!
+//! ```rust
+//! json!{
+//! #[json(reflect)]
+//! School { name: String, students: [{ name: String, age: u8 }] }
+//! }
+//!
+//! for field in School::fields() {
+//!     println!("{}: {}", field.key, field.ty);
+//! }
+//! school.set_path("students.0.age", 19.into())?;
+//! ```
+use crate::{Result, JSON};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+
+/// A field of the declared struct
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldInfo {
+    /// Rust field name, e.g. `type_`
+    pub name: &'static str,
+    /// JSON key, e.g. `type`
+    pub key: &'static str,
+    /// Declared type, `T` of the optional field `Option<T>`
+    pub ty: &'static str,
+    pub optional: bool,
+}
+
+/// Convert the path of JSON keys separated by dots to a JSON Pointer, e.g. `students.0.age`
+pub fn to_pointer(path: &str) -> String {
+    if path.is_empty() {
+        return "".to_owned();
+    }
+    path.split('.')
+        .map(|x| format!("/{}", x.replace('~', "~0").replace('/', "~1")))
+        .collect()
+}
+
+pub trait Reflect: Sized {
+    /// Fields in the declaration order
+    fn fields() -> &'static [FieldInfo];
+
+    /// Field with the JSON key
+    fn field(key: &str) -> Option<&'static FieldInfo> {
+        Self::fields().iter().find(|x| x.key == key)
+    }
+
+    /// Serialized value at the path of JSON keys, array items are indexed by numbers
+    fn get_path(&self, path: &str) -> Option<Value>
+    where
+        Self: Serialize,
+    {
+        JSON::pointer(self, &to_pointer(path))
+    }
+
+    /// Replace the value at the path, the result is deserialized back into self
+    fn set_path(&mut self, path: &str, value: Value) -> Result<()>
+    where
+        Self: Serialize + DeserializeOwned,
+    {
+        JSON::set_pointer(self, &to_pointer(path), value)
+    }
+}
@@ -0,0 +1,450 @@
+//! JSON-RPC 2.0
+//!
+//! The methods are declared with `json!`, the parameters and the result are declared structs or types:
+//!
+//! ```rust
+//! json! { rpc subtract { minuend: i32, subtrahend: i32 } -> i32 }
+//!
+//! // client, the ids are assigned by the client
+//! let client = rpc::Client::new();
+//! let request = client.request::<Subtract>(SubtractParams { minuend: 42, subtrahend: 23 });
+//! let difference = rpc::Client::response::<Subtract>(&send(request.to_string()?)?)?;
+//!
+//! // server, single and batch requests
+//! let router = rpc::Router::new().method::<Subtract, _>(|p| Ok(p.minuend - p.subtrahend));
+//! if let Some(response) = router.handle(&body) {
+//!     reply(response);
+//! }
+//! ```
+use crate::{error, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicI64, Ordering};
+
+/// Method of JSON-RPC, implemented by `json! { rpc name { params } -> result }`
+pub trait Method {
+    /// Name of the method on the wire
+    const NAME: &'static str;
+    type Params: Serialize + DeserializeOwned;
+    type Result: Serialize + DeserializeOwned;
+}
+
+/// The member `"jsonrpc": "2.0"`, other versions are rejected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Version;
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str("2.0")
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let version = String::deserialize(deserializer)?;
+        match version.as_str() {
+            "2.0" => Ok(Version),
+            v => Err(serde::de::Error::custom(format!(
+                "unsupported JSON-RPC version `{}`",
+                v
+            ))),
+        }
+    }
+}
+
+/// Id of a request, echoed by the response
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Id {
+    Number(i64),
+    String(String),
+}
+
+impl From<i64> for Id {
+    fn from(id: i64) -> Self {
+        Id::Number(id)
+    }
+}
+
+impl From<String> for Id {
+    fn from(id: String) -> Self {
+        Id::String(id)
+    }
+}
+
+impl From<&str> for Id {
+    fn from(id: &str) -> Self {
+        Id::String(id.to_owned())
+    }
+}
+
+impl fmt::Display for Id {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Id::Number(id) => write!(f, "{}", id),
+            Id::String(id) => write!(f, "{:?}", id),
+        }
+    }
+}
+
+// `()` and the unit structs are serialized as null, the params are omitted instead
+fn omit_params<P: Serialize>(params: &Option<P>) -> bool {
+    match params {
+        None => true,
+        Some(p) if std::mem::size_of::<P>() == 0 => {
+            matches!(serde_json::to_value(p), Ok(Value::Null))
+        }
+        Some(_) => false,
+    }
+}
+
+/// Request, or notification without id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request<P = Value> {
+    pub jsonrpc: Version,
+    pub method: String,
+    #[serde(default, skip_serializing_if = "omit_params")]
+    pub params: Option<P>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Id>,
+}
+
+impl<P: Serialize> Request<P> {
+    pub fn new<M: Into<String>, I: Into<Id>>(method: M, params: P, id: I) -> Self {
+        Self {
+            jsonrpc: Version,
+            method: method.into(),
+            params: Some(params),
+            id: Some(id.into()),
+        }
+    }
+
+    /// Request without id, the server sends no response
+    pub fn notification<M: Into<String>>(method: M, params: P) -> Self {
+        Self {
+            jsonrpc: Version,
+            method: method.into(),
+            params: Some(params),
+            id: None,
+        }
+    }
+
+    pub fn is_notification(&self) -> bool {
+        self.id.is_none()
+    }
+
+    /// Serialize into JSON text
+    #[allow(clippy::inherent_to_string)]
+    pub fn to_string(&self) -> Result<String> {
+        Ok(serde_json::to_string(self)?)
+    }
+}
+
+/// Result or error of a response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome<R> {
+    Result(R),
+    Error(Error),
+}
+
+/// Response, the id is null if the id of the request is unknown, e.g. a parse error
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response<R = Value> {
+    pub jsonrpc: Version,
+    #[serde(flatten)]
+    pub outcome: Outcome<R>,
+    pub id: Option<Id>,
+}
+
+impl<R> Response<R> {
+    pub fn result(id: Option<Id>, result: R) -> Self {
+        Self {
+            jsonrpc: Version,
+            outcome: Outcome::Result(result),
+            id,
+        }
+    }
+
+    pub fn error(id: Option<Id>, error: Error) -> Self {
+        Self {
+            jsonrpc: Version,
+            outcome: Outcome::Error(error),
+            id,
+        }
+    }
+
+    pub fn into_result(self) -> std::result::Result<R, Error> {
+        match self.outcome {
+            Outcome::Result(result) => Ok(result),
+            Outcome::Error(e) => Err(e),
+        }
+    }
+}
+
+impl Response<Value> {
+    /// Deserialize the result into the type, e.g. a response of a batch
+    pub fn decode<R: DeserializeOwned>(self) -> Result<Response<R>> {
+        let outcome = match self.outcome {
+            Outcome::Result(result) => Outcome::Result(error::from_value(result)?),
+            Outcome::Error(e) => Outcome::Error(e),
+        };
+        Ok(Response {
+            jsonrpc: Version,
+            outcome,
+            id: self.id,
+        })
+    }
+}
+
+/// Error object of a response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Error {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl Error {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+
+    pub fn new<M: Into<String>>(code: i64, message: M) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Additional information of the error, null if it is not serializable
+    pub fn data<T: Serialize>(mut self, data: &T) -> Self {
+        self.data = Some(serde_json::to_value(data).unwrap_or_default());
+        self
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "JSON-RPC error {}: {}", self.code, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for crate::Error {
+    fn from(e: Error) -> Self {
+        crate::Error::Custom(e.to_string())
+    }
+}
+
+/// Builder of requests with unique ids, the ids are numbers from 1
+#[derive(Debug)]
+pub struct Client {
+    next: AtomicI64,
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Client {
+    pub fn new() -> Self {
+        Self {
+            next: AtomicI64::new(1),
+        }
+    }
+
+    pub fn next_id(&self) -> Id {
+        Id::Number(self.next.fetch_add(1, Ordering::Relaxed))
+    }
+
+    /// Request of the method with the next id
+    pub fn request<M: Method>(&self, params: M::Params) -> Request<M::Params> {
+        Request::new(M::NAME, params, self.next_id())
+    }
+
+    /// Notification of the method
+    pub fn notification<M: Method>(&self, params: M::Params) -> Request<M::Params> {
+        Request::notification(M::NAME, params)
+    }
+
+    /// Start a batch of requests
+    pub fn batch(&self) -> Batch<'_> {
+        Batch {
+            client: self,
+            requests: Vec::new(),
+        }
+    }
+
+    /// Parse the response of the method, an error response is the JSON-RPC error
+    pub fn response<M: Method>(text: &str) -> Result<M::Result> {
+        let response: Response<M::Result> = error::from_str(text)?;
+        Ok(response.into_result()?)
+    }
+}
+
+/// Batch of requests, see [Client::batch]
+#[derive(Debug)]
+pub struct Batch<'a> {
+    client: &'a Client,
+    requests: Vec<Request>,
+}
+
+impl Batch<'_> {
+    /// Add a request, its id matches the response
+    pub fn request<M: Method>(&mut self, params: &M::Params) -> Result<Id> {
+        let id = self.client.next_id();
+        let params = serde_json::to_value(params)?;
+        self.requests
+            .push(Request::new(M::NAME, params, id.clone()));
+        Ok(id)
+    }
+
+    /// Add a notification
+    pub fn notification<M: Method>(&mut self, params: &M::Params) -> Result<()> {
+        let params = serde_json::to_value(params)?;
+        self.requests.push(Request::notification(M::NAME, params));
+        Ok(())
+    }
+
+    pub fn requests(&self) -> &[Request] {
+        &self.requests
+    }
+
+    /// Serialize into JSON text
+    #[allow(clippy::inherent_to_string)]
+    pub fn to_string(&self) -> Result<String> {
+        Ok(serde_json::to_string(&self.requests)?)
+    }
+}
+
+/// Responses of a batch by the ids
+#[derive(Debug, Default)]
+pub struct Responses {
+    responses: HashMap<Option<Id>, Response>,
+}
+
+impl Responses {
+    /// Parse the response of a batch, a single error response is accepted, e.g. a parse error of the batch
+    pub fn parse(text: &str) -> Result<Self> {
+        let responses: Vec<Response> = match error::from_str::<Value>(text)? {
+            Value::Array(items) => error::from_value(Value::Array(items))?,
+            single => vec![error::from_value(single)?],
+        };
+        let responses = responses.into_iter().map(|r| (r.id.clone(), r)).collect();
+        Ok(Self { responses })
+    }
+
+    pub fn get(&self, id: &Id) -> Option<&Response> {
+        self.responses.get(&Some(id.clone()))
+    }
+
+    /// Take the result of the request, an error response is the JSON-RPC error
+    pub fn take<M: Method>(&mut self, id: &Id) -> Result<M::Result> {
+        let response = match self.responses.remove(&Some(id.clone())) {
+            Some(response) => response,
+            None => match self.responses.get(&None) {
+                // the whole batch failed
+                Some(Response {
+                    outcome: Outcome::Error(e),
+                    ..
+                }) => return Err(e.clone().into()),
+                _ => return Err(crate::Error::Custom(format!("no response of id {}", id))),
+            },
+        };
+        Ok(response.decode::<M::Result>()?.into_result()?)
+    }
+}
+
+type Handler = Box<dyn Fn(Value) -> std::result::Result<Value, Error> + Send + Sync>;
+
+/// Dispatcher of requests to the handlers of the methods
+#[derive(Default)]
+pub struct Router {
+    handlers: HashMap<String, Handler>,
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle the method, the params which do not match are invalid params with the field path
+    pub fn method<M, F>(mut self, f: F) -> Self
+    where
+        M: Method,
+        F: Fn(M::Params) -> std::result::Result<M::Result, Error> + Send + Sync + 'static,
+    {
+        let handler = move |params: Value| {
+            let params = error::from_value::<M::Params>(params)
+                .map_err(|e| Error::new(Error::INVALID_PARAMS, e.to_string()))?;
+            let result = f(params)?;
+            serde_json::to_value(result)
+                .map_err(|e| Error::new(Error::INTERNAL_ERROR, e.to_string()))
+        };
+        self.handlers.insert(M::NAME.to_owned(), Box::new(handler));
+        self
+    }
+
+    /// Handle the request, None for a notification
+    pub fn dispatch(&self, request: Request) -> Option<Response> {
+        let outcome = match self.handlers.get(&request.method) {
+            Some(handler) => handler(request.params.unwrap_or_default()),
+            None => Err(Error::new(
+                Error::METHOD_NOT_FOUND,
+                format!("method `{}` not found", request.method),
+            )),
+        };
+        let id = request.id?;
+        Some(match outcome {
+            Ok(result) => Response::result(Some(id), result),
+            Err(e) => Response::error(Some(id), e),
+        })
+    }
+
+    fn dispatch_value(&self, value: Value) -> Option<Response> {
+        match error::from_value::<Request>(value) {
+            Ok(request) => self.dispatch(request),
+            Err(e) => Some(Response::error(
+                None,
+                Error::new(Error::INVALID_REQUEST, e.to_string()),
+            )),
+        }
+    }
+
+    /// Handle the JSON text of a request or a batch, None if there is nothing to respond, e.g. notifications
+    pub fn handle(&self, text: &str) -> Option<String> {
+        let response = match error::from_str::<Value>(text) {
+            Err(e) => {
+                let error = Error::new(Error::PARSE_ERROR, e.to_string());
+                serde_json::to_string(&Response::<Value>::error(None, error))
+            }
+            Ok(Value::Array(items)) if items.is_empty() => {
+                let error = Error::new(Error::INVALID_REQUEST, "empty batch");
+                serde_json::to_string(&Response::<Value>::error(None, error))
+            }
+            Ok(Value::Array(items)) => {
+                let responses: Vec<_> = items
+                    .into_iter()
+                    .filter_map(|item| self.dispatch_value(item))
+                    .collect();
+                if responses.is_empty() {
+                    return None;
+                }
+                serde_json::to_string(&responses)
+            }
+            Ok(value) => serde_json::to_string(&self.dispatch_value(value)?),
+        };
+        response.ok()
+    }
+}
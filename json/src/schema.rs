@@ -0,0 +1,42 @@
+//! Validation by JSON Schema
+//!
+//! The document is validated before it is deserialized, so all the violations are reported with their paths rather
+//! than the first type error, e.g. of the schema generated by the `schema` option of `json!`:
+//!
+//! ```rust
+//! let school: School = native_json::read_validated("school.json", &School::schema())?;
+//!
+//! let value: serde_json::Value = native_json::parse(&text)?;
+//! native_json::schema::validate(&value, &schema)?;
+//! ```
+use crate::{Error, Result, ValidationErrors};
+use jsonschema::paths::{Location, LocationSegment};
+use serde_json::Value;
+
+// field path of the JSON Pointer, e.g. `/students/3/age` is `students[3].age`
+fn path(location: &Location) -> String {
+    let mut path = String::new();
+    for segment in location {
+        match segment {
+            LocationSegment::Property(name) => {
+                if !path.is_empty() {
+                    path.push('.');
+                }
+                path.push_str(&name);
+            }
+            LocationSegment::Index(index) => path.push_str(&format!("[{}]", index)),
+        }
+    }
+    path
+}
+
+/// Validate the value against the schema, `Error::Validation` has all the violations
+pub fn validate(value: &Value, schema: &Value) -> Result<()> {
+    let validator = jsonschema::validator_for(schema)
+        .map_err(|e| Error::Custom(format!("invalid schema: {}", e)))?;
+    let mut errors = ValidationErrors::new();
+    for e in validator.iter_errors(value) {
+        errors.add(&path(e.instance_path()), e.to_string());
+    }
+    errors.into_result().map_err(Error::Validation)
+}
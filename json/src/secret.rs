@@ -0,0 +1,54 @@
+//! Redaction of secret fields
+//!
+//! Fields annotated by `@secret` are masked by [JSON::stringify_redacted](crate::JSON::stringify_redacted), the other serializations keep them intact:
+//!
+//! ```rust
+//! json!{ Config { user: String, token: String @secret } }
+//!
+//! log::info!("{}", config.stringify_redacted(0)?); // {"user":"admin","token":"****"}
+//! ```
+use serde::{Serialize, Serializer};
+use std::cell::Cell;
+
+/// Replacement of the secret values
+pub const MASK: &str = "****";
+
+thread_local! {
+    static REDACT: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Redact the secret fields serialized in the current thread until it is dropped
+pub struct Redact {
+    previous: bool,
+}
+
+impl Redact {
+    pub fn new() -> Self {
+        let previous = REDACT.with(|x| x.replace(true));
+        Self { previous }
+    }
+}
+
+impl Default for Redact {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Redact {
+    fn drop(&mut self) {
+        REDACT.with(|x| x.set(self.previous));
+    }
+}
+
+// #[serde(serialize_with = "native_json::secret::serialize")]
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Serialize,
+    S: Serializer,
+{
+    if REDACT.with(|x| x.get()) {
+        return serializer.serialize_str(MASK);
+    }
+    value.serialize(serializer)
+}
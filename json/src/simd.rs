@@ -0,0 +1,73 @@
+//! SIMD accelerated parsing by simd-json
+//!
+//! simd-json parses in place, so it takes the mutable or owned input, the borrowed input of `parse` and
+//! `from_slice` stays on serde_json. `read` loads the file into an owned buffer and parses it by simd-json when the
+//! `simd` feature is enabled:
+//!
+//! ```rust
+//! use native_json::simd;
+//!
+//! let school: School = simd::parse(text)?;
+//! let students: Vec<Student> = simd::from_vec(bytes)?;
+//! let school: School = native_json::read("school.json")?;
+//! ```
+//!
+//! The input is rewritten while parsing, so the errors report the path and the byte offset rather than the line and
+//! column, `read` parses the file again by serde_json on error to report them.
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+// serde_json error of the message, the line and column are unknown
+fn source(e: &simd_json::Error) -> serde_json::Error {
+    serde::de::Error::custom(e)
+}
+
+fn with_path(path: String, e: simd_json::Error) -> Error {
+    if e.is_syntax() || e.is_eof() {
+        return Error::Syntax {
+            line: 0,
+            column: 0,
+            source: source(&e),
+        };
+    }
+    Error::Type {
+        path,
+        line: 0,
+        column: 0,
+        source: source(&e),
+    }
+}
+
+impl From<simd_json::Error> for Error {
+    fn from(e: simd_json::Error) -> Self {
+        with_path(String::new(), e)
+    }
+}
+
+impl From<serde_path_to_error::Error<simd_json::Error>> for Error {
+    fn from(e: serde_path_to_error::Error<simd_json::Error>) -> Self {
+        // "." of the root
+        let path = match e.path().iter().next() {
+            Some(_) => e.path().to_string(),
+            None => String::new(),
+        };
+        with_path(path, e.into_inner())
+    }
+}
+
+/// Parse from JSON bytes in place, the bytes are rewritten
+pub fn from_slice<'a, T: Deserialize<'a>>(v: &'a mut [u8]) -> Result<T> {
+    let mut de = simd_json::Deserializer::from_slice(v)?;
+    Ok(serde_path_to_error::deserialize(&mut de)?)
+}
+
+/// Parse from the owned JSON bytes
+pub fn from_vec<T: DeserializeOwned>(mut v: Vec<u8>) -> Result<T> {
+    from_slice(&mut v)
+}
+
+/// Parse from the owned JSON string
+pub fn parse<T: DeserializeOwned>(s: String) -> Result<T> {
+    from_vec(s.into_bytes())
+}
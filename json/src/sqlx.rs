@@ -0,0 +1,75 @@
+//! sqlx column of the declared structs
+//!
+//! [Json] stores the value in a Postgres `jsonb` or `json` column, the decoding errors report the field path:
+//!
+//! ```rust
+//! use native_json::sqlx::Json;
+//!
+//! sqlx::query("INSERT INTO schools (id, data) VALUES ($1, $2)")
+//!     .bind(1)
+//!     .bind(Json(&school))
+//!     .execute(&pool)
+//!     .await?;
+//!
+//! let (Json(school),): (Json<School>,) = sqlx::query_as("SELECT data FROM schools WHERE id = 1")
+//!     .fetch_one(&pool)
+//!     .await?;
+//! ```
+use crate::error;
+use ::sqlx::encode::IsNull;
+use ::sqlx::error::BoxDynError;
+use ::sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueFormat, PgValueRef, Postgres};
+use ::sqlx::{Decode, Encode, Type};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// JSON column of the value, `jsonb` by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+
+impl<T> Type<Postgres> for Json<T> {
+    fn type_info() -> PgTypeInfo {
+        <::sqlx::types::Json<T> as Type<Postgres>>::type_info()
+    }
+
+    fn compatible(ty: &PgTypeInfo) -> bool {
+        <::sqlx::types::Json<T> as Type<Postgres>>::compatible(ty)
+    }
+}
+
+impl<T: Serialize> Encode<'_, Postgres> for Json<T> {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> Result<IsNull, BoxDynError> {
+        // the version byte of jsonb, which is replaced by a space for json
+        ::sqlx::types::Json(&self.0).encode_by_ref(buf)
+    }
+}
+
+impl<T: DeserializeOwned> Decode<'_, Postgres> for Json<T> {
+    fn decode(value: PgValueRef<'_>) -> Result<Self, BoxDynError> {
+        let mut bytes = value.as_bytes()?;
+        // the binary jsonb starts with the version 1, which is never the first byte of JSON text
+        if value.format() == PgValueFormat::Binary && bytes.first() == Some(&1) {
+            bytes = &bytes[1..];
+        }
+        Ok(Json(error::from_slice(bytes)?))
+    }
+}
+
+impl<T> std::ops::Deref for Json<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> std::ops::DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Json<T> {
+    fn from(value: T) -> Self {
+        Json(value)
+    }
+}
@@ -0,0 +1,374 @@
+//! Streaming of huge arrays
+//!
+//! [stream_array] parses a top-level JSON array element by element, only one element is in memory at a time:
+//!
+//! ```rust
+//! for user in native_json::stream_array::<User, _>("users.json")? {
+//!     let user = user?;
+//! }
+//! ```
+//!
+//! [ArrayWriter] writes the elements one at a time:
+//!
+//! ```rust
+//! let mut writer = ArrayWriter::create("users.json")?.pretty(true);
+//! writer.write_all(users)?;
+//! writer.finish()?;
+//! ```
+//!
+//! [parse_many] parses the concatenated documents of a buffer, e.g. the output of `kubectl get -o json -w`:
+//!
+//! ```rust
+//! for document in native_json::parse_many::<Pod>(&output) {
+//!     let (offset, pod) = document?;
+//! }
+//! ```
+//!
+//! [parse_prefix] parses the first document of a mixed-content buffer and returns the rest:
+//!
+//! ```rust
+//! let (header, body): (Header, &str) = native_json::parse_prefix(&buffer)?;
+//! ```
+use crate::{error, Error, Result};
+use serde::de::{DeserializeOwned, IgnoredAny};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::Path;
+
+pub use serde_json::StreamDeserializer;
+
+/// Iterator of the elements of a top-level array
+pub struct ArrayReader<T, R: BufRead = BufReader<Box<dyn io::Read>>> {
+    reader: R,
+    buf: Vec<u8>,
+    index: usize,
+    started: bool,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned, R: BufRead> ArrayReader<T, R> {
+    /// Read the array from the reader, e.g. a socket
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+            index: 0,
+            started: false,
+            done: false,
+            _marker: PhantomData,
+        }
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>> {
+        Ok(self.reader.fill_buf()?.first().copied())
+    }
+
+    // the next non-whitespace byte, it is not consumed
+    fn skip_whitespace(&mut self) -> Result<Option<u8>> {
+        loop {
+            match self.peek()? {
+                Some(b) if b.is_ascii_whitespace() => self.reader.consume(1),
+                other => return Ok(other),
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: &[u8]) -> Result<u8> {
+        match self.skip_whitespace()? {
+            Some(b) if expected.contains(&b) => {
+                self.reader.consume(1);
+                Ok(b)
+            }
+            Some(b) => Err(Error::Custom(format!(
+                "unexpected `{}` after element {} of the array",
+                b as char, self.index
+            ))),
+            None => Err(Error::custom("unexpected end of the array")),
+        }
+    }
+
+    // the raw bytes of the next element into the buffer
+    fn element(&mut self) -> Result<()> {
+        self.buf.clear();
+        self.skip_whitespace()?;
+        let mut depth = 0usize;
+        let mut string = false;
+        let mut escape = false;
+        loop {
+            let Some(b) = self.peek()? else {
+                return Err(Error::custom("unexpected end of the array"));
+            };
+            let scalar = depth == 0 && !string && !self.buf.is_empty() && self.buf[0] != b'"';
+            if scalar && (b == b',' || b == b']' || b.is_ascii_whitespace()) {
+                return Ok(());
+            }
+            self.reader.consume(1);
+            self.buf.push(b);
+            match b {
+                _ if escape => escape = false,
+                b'\\' if string => escape = true,
+                b'"' => string = !string,
+                _ if string => {}
+                b'{' | b'[' => depth += 1,
+                b'}' | b']' => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+            let closed = matches!(b, b'}' | b']') || (b == b'"' && !string);
+            if depth == 0 && !string && closed {
+                return Ok(());
+            }
+        }
+    }
+
+    fn next_element(&mut self) -> Result<Option<T>> {
+        if !self.started {
+            self.started = true;
+            self.expect(b"[")?;
+            if self.skip_whitespace()? == Some(b']') {
+                self.reader.consume(1);
+                return Ok(None);
+            }
+        } else if self.expect(b",]")? == b']' {
+            return Ok(None);
+        }
+        self.element()?;
+        let index = self.index;
+        self.index += 1;
+        error::from_slice(&self.buf).map(Some).map_err(|e| match e {
+            Error::Type {
+                path,
+                line,
+                column,
+                source,
+            } => {
+                let path = match path.starts_with('[') || path.is_empty() {
+                    true => format!("[{}]{}", index, path),
+                    false => format!("[{}].{}", index, path),
+                };
+                Error::Type {
+                    path,
+                    line,
+                    column,
+                    source,
+                }
+            }
+            e => e,
+        })
+    }
+}
+
+impl<T: DeserializeOwned, R: BufRead> Iterator for ArrayReader<T, R> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        match self.next_element() {
+            Ok(Some(value)) => Some(Ok(value)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            // the element is skipped, the next one can be parsed
+            Err(e @ Error::Type { .. }) => Some(Err(e)),
+            Err(e) => {
+                self.done = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
+/// Stream the elements of the top-level array of the file, gzip and zstd compressed files are detected
+pub fn stream_array<T, P>(path: P) -> Result<ArrayReader<T>>
+where
+    T: DeserializeOwned,
+    P: AsRef<Path>,
+{
+    Ok(ArrayReader::new(BufReader::new(crate::open(
+        path.as_ref(),
+    )?)))
+}
+
+/// Writer of a top-level array, element by element
+///
+/// [finish](ArrayWriter::finish) closes the array, it is closed on drop ignoring the errors if it is not finished.
+pub struct ArrayWriter<T, W: Write = BufWriter<File>> {
+    writer: Option<W>,
+    pretty: bool,
+    count: usize,
+    buf: Vec<u8>,
+    _marker: PhantomData<fn(&T)>,
+}
+
+impl<T: Serialize> ArrayWriter<T> {
+    /// Create or truncate the file
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<T: Serialize, W: Write> ArrayWriter<T, W> {
+    /// Write into the writer, e.g. stdout or a socket
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Some(writer),
+            pretty: false,
+            count: 0,
+            buf: Vec::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// One element per line indented by 2 spaces, concise by default
+    pub fn pretty(mut self, pretty: bool) -> Self {
+        self.pretty = pretty;
+        self
+    }
+
+    /// Write the element, an element which fails to serialize writes nothing
+    pub fn write(&mut self, value: &T) -> Result<()> {
+        self.buf.clear();
+        self.buf.push(match self.count {
+            0 => b'[',
+            _ => b',',
+        });
+        if self.pretty {
+            self.buf.extend_from_slice(b"\n  ");
+            let start = self.buf.len();
+            serde_json::to_writer_pretty(&mut self.buf, value)?;
+            // the strings have no raw newlines, every newline is between the tokens
+            let mut indented = Vec::with_capacity(self.buf.len() - start);
+            for &b in &self.buf[start..] {
+                indented.push(b);
+                if b == b'\n' {
+                    indented.extend_from_slice(b"  ");
+                }
+            }
+            self.buf.truncate(start);
+            self.buf.extend_from_slice(&indented);
+        } else {
+            serde_json::to_writer(&mut self.buf, value)?;
+        }
+        if let Some(writer) = self.writer.as_mut() {
+            writer.write_all(&self.buf)?;
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Write the elements of the iterator
+    pub fn write_all<I>(&mut self, values: I) -> Result<()>
+    where
+        I: IntoIterator,
+        I::Item: Borrow<T>,
+    {
+        for value in values {
+            self.write(value.borrow())?;
+        }
+        Ok(())
+    }
+
+    /// Number of the written elements
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Close the array, flush and return the writer
+    pub fn finish(mut self) -> Result<W> {
+        Ok(self.close()?.expect("the writer is taken only on finish"))
+    }
+}
+
+impl<T, W: Write> ArrayWriter<T, W> {
+    fn close(&mut self) -> io::Result<Option<W>> {
+        let Some(mut writer) = self.writer.take() else {
+            return Ok(None);
+        };
+        let end: &[u8] = match (self.count, self.pretty) {
+            (0, false) => b"[]",
+            (0, true) => b"[]\n",
+            (_, false) => b"]",
+            (_, true) => b"\n]\n",
+        };
+        writer.write_all(end)?;
+        writer.flush()?;
+        Ok(Some(writer))
+    }
+}
+
+impl<T, W: Write> Drop for ArrayWriter<T, W> {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
+}
+
+/// Iterator of the concatenated documents of a buffer, see [parse_many]
+pub struct Documents<'a, T> {
+    text: &'a str,
+    bounds: StreamDeserializer<'a, serde_json::de::StrRead<'a>, IgnoredAny>,
+    de: serde_json::Deserializer<serde_json::de::StrRead<'a>>,
+    done: bool,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<'a, T: Deserialize<'a>> Iterator for Documents<'a, T> {
+    type Item = Result<(usize, T)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let end = self.bounds.byte_offset();
+        let rest = &self.text[end..];
+        let start = end + rest.len() - rest.trim_start().len();
+        // the bounds are scanned ahead, the document is parsed by the deserializer of the whole buffer for the
+        // positions of the errors
+        let result = match self.bounds.next()? {
+            Ok(_) => serde_path_to_error::deserialize(&mut self.de).map_err(Error::from),
+            Err(e) => Err(Error::from(e)),
+        };
+        self.done = result.is_err();
+        Some(result.map(|value| (start, value)))
+    }
+}
+
+/// Parse the concatenated or whitespace separated documents, every document is yielded with its byte offset
+///
+/// The errors report the line and column in the whole buffer, the iteration ends at the first error.
+pub fn parse_many<'a, T: Deserialize<'a>>(s: &'a str) -> Documents<'a, T> {
+    Documents {
+        text: s,
+        bounds: serde_json::Deserializer::from_str(s).into_iter(),
+        de: serde_json::Deserializer::from_str(s),
+        done: false,
+        _marker: PhantomData,
+    }
+}
+
+/// Parse the first document and return the text after it, while `parse` fails if anything but whitespace remains
+///
+/// The errors report the field path and the line and column, e.g. of an HTTP response followed by a trailer.
+pub fn parse_prefix<'a, T: Deserialize<'a>>(s: &'a str) -> Result<(T, &'a str)> {
+    let mut bounds = serde_json::Deserializer::from_str(s).into_iter::<IgnoredAny>();
+    // the whole text is parsed again for the error
+    let end = match bounds.next() {
+        Some(Ok(_)) => bounds.byte_offset(),
+        _ => s.len(),
+    };
+    let value = error::from_str(&s[..end])?;
+    Ok((value, &s[end..]))
+}
+
+/// Stream the concatenated documents of the reader, e.g. a socket, `byte_offset` is the offset after the last
+/// document
+pub fn from_reader<R: io::Read, T: DeserializeOwned>(
+    reader: R,
+) -> StreamDeserializer<'static, serde_json::de::IoRead<R>, T> {
+    serde_json::Deserializer::from_reader(reader).into_iter()
+}
@@ -0,0 +1,78 @@
+//! Numbers serialized as strings
+//!
+//! The `@string` annotation keeps the native type in Rust, e.g. 128-bit integers of blockchain and finance APIs:
+//!
+//! ```rust
+//! json!{ Transfer {
+//!     amount: u128 @string,  // "340282366920938463463374607431768211455"
+//!     fee: i64 @string       // "-1"
+//! }}
+//! ```
+//!
+//! Any type implementing `Display` and `FromStr` is supported, plain JSON numbers are also accepted on deserialize.
+use serde::de::{Error, Visitor};
+use serde::{Deserializer, Serializer};
+use std::fmt::{self, Display};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+pub fn serialize<T: Display, S: Serializer>(value: &T, s: S) -> Result<S::Ok, S::Error> {
+    s.collect_str(value)
+}
+
+pub fn deserialize<'de, T, D>(d: D) -> Result<T, D::Error>
+where
+    T: FromStr,
+    T::Err: Display,
+    D: Deserializer<'de>,
+{
+    d.deserialize_any(StringVisitor(PhantomData))
+}
+
+struct StringVisitor<T>(PhantomData<T>);
+
+impl<T> StringVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    fn parse<E: Error>(&self, s: &str) -> Result<T, E> {
+        T::from_str(s).map_err(|e| E::custom(format!("invalid number {s:?}: {e}")))
+    }
+}
+
+impl<'de, T> Visitor<'de> for StringVisitor<T>
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a number or a string of number")
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<T, E> {
+        self.parse(v)
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> Result<T, E> {
+        self.parse(&v.to_string())
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<T, E> {
+        self.parse(&v.to_string())
+    }
+
+    fn visit_i128<E: Error>(self, v: i128) -> Result<T, E> {
+        self.parse(&v.to_string())
+    }
+
+    fn visit_u128<E: Error>(self, v: u128) -> Result<T, E> {
+        self.parse(&v.to_string())
+    }
+
+    fn visit_f64<E: Error>(self, v: f64) -> Result<T, E> {
+        self.parse(&v.to_string())
+    }
+}
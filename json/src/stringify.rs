@@ -0,0 +1,427 @@
+//! Options of stringify
+//!
+//! Fields of declared structs are written in the declaration order, the keys of maps such as `HashMap` are
+//! in arbitrary order unless `sort_keys` is set:
+//!
+//! ```rust
+//! let options = StringifyOptions::new().indent(2).sort_keys(true);
+//! let text = config.stringify_with(&options)?;
+//!
+//! // tab indentation, Windows line endings, a trailing newline and non-ASCII characters escaped
+//! let options = StringifyOptions::new()
+//!     .indent(1)
+//!     .indent_char('\t')
+//!     .crlf(true)
+//!     .trailing_newline(true)
+//!     .ensure_ascii(true);
+//! ```
+use crate::nonfinite::{self, NonFinite};
+use crate::{Error, Result};
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter};
+use serde_json::{Map, Value};
+use std::io;
+
+/// Format of floating point numbers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// Shortest representation which round-trips, e.g. `1.0`, `0.30000000000000004`
+    Shortest,
+    /// Fixed digits after the decimal point, e.g. `0.3000`
+    Precision(usize),
+    /// Shortest representation, integral values without `.0`, e.g. `1`
+    Integer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StringifyOptions {
+    indent: usize,
+    indent_char: char,
+    crlf: bool,
+    trailing_newline: bool,
+    ensure_ascii: bool,
+    sort_keys: bool,
+    float: FloatFormat,
+    non_finite: NonFinite,
+}
+
+impl Default for StringifyOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StringifyOptions {
+    /// Concise output in the declaration order
+    pub fn new() -> Self {
+        Self {
+            indent: 0,
+            indent_char: ' ',
+            crlf: false,
+            trailing_newline: false,
+            ensure_ascii: false,
+            sort_keys: false,
+            float: FloatFormat::Shortest,
+            non_finite: NonFinite::Null,
+        }
+    }
+
+    /// Pretty output with N characters indentation, 0 for concise output
+    pub fn indent(mut self, n: usize) -> Self {
+        self.indent = n;
+        self
+    }
+
+    /// Character of indentation, space by default, e.g. '\t'
+    pub fn indent_char(mut self, c: char) -> Self {
+        self.indent_char = c;
+        self
+    }
+
+    /// Windows line endings "\r\n" for pretty output
+    pub fn crlf(mut self, crlf: bool) -> Self {
+        self.crlf = crlf;
+        self
+    }
+
+    /// End the output with a line ending
+    pub fn trailing_newline(mut self, newline: bool) -> Self {
+        self.trailing_newline = newline;
+        self
+    }
+
+    /// Escape non-ASCII characters as \uXXXX
+    pub fn ensure_ascii(mut self, ascii: bool) -> Self {
+        self.ensure_ascii = ascii;
+        self
+    }
+
+    /// Format of floating point numbers
+    pub fn float_format(mut self, format: FloatFormat) -> Self {
+        self.float = format;
+        self
+    }
+
+    /// Fixed digits after the decimal point, the same as `float_format(FloatFormat::Precision(n))`
+    pub fn float_precision(mut self, n: usize) -> Self {
+        self.float = FloatFormat::Precision(n);
+        self
+    }
+
+    /// Serialization of NaN and infinities, `null` by default
+    pub fn non_finite(mut self, policy: NonFinite) -> Self {
+        self.non_finite = policy;
+        self
+    }
+
+    /// Sort the keys of all objects, so the output is deterministic
+    pub fn sort_keys(mut self, sort: bool) -> Self {
+        self.sort_keys = sort;
+        self
+    }
+
+    fn newline(&self) -> &'static [u8] {
+        match self.crlf {
+            true => b"\r\n",
+            false => b"\n",
+        }
+    }
+}
+
+fn sort_value(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut pairs: Vec<_> = map.into_iter().collect();
+            pairs.sort_by(|a, b| a.0.cmp(&b.0));
+            let map: Map<_, _> = pairs.into_iter().map(|(k, v)| (k, sort_value(v))).collect();
+            Value::Object(map)
+        }
+        Value::Array(array) => Value::Array(array.into_iter().map(sort_value).collect()),
+        other => other,
+    }
+}
+
+// concise output if the indent is empty, otherwise the same layout as serde_json::ser::PrettyFormatter
+struct OptionsFormatter<'a> {
+    indent: &'a [u8],
+    newline: &'a [u8],
+    ensure_ascii: bool,
+    float: FloatFormat,
+    level: usize,
+    has_value: bool,
+}
+
+impl<'a> OptionsFormatter<'a> {
+    fn begin<W: ?Sized + io::Write>(&mut self, writer: &mut W, c: &[u8]) -> io::Result<()> {
+        self.level += 1;
+        self.has_value = false;
+        writer.write_all(c)
+    }
+
+    fn end<W: ?Sized + io::Write>(&mut self, writer: &mut W, c: &[u8]) -> io::Result<()> {
+        self.level -= 1;
+        if self.has_value && !self.indent.is_empty() {
+            self.line(writer)?;
+        }
+        writer.write_all(c)
+    }
+
+    fn item<W: ?Sized + io::Write>(&mut self, writer: &mut W, first: bool) -> io::Result<()> {
+        if !first {
+            writer.write_all(b",")?;
+        }
+        if !self.indent.is_empty() {
+            self.line(writer)?;
+        }
+        Ok(())
+    }
+
+    fn line<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.newline)?;
+        for _ in 0..self.level {
+            writer.write_all(self.indent)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Formatter for OptionsFormatter<'a> {
+    fn begin_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.begin(writer, b"[")
+    }
+
+    fn end_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.end(writer, b"]")
+    }
+
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.item(writer, first)
+    }
+
+    fn end_array_value<W: ?Sized + io::Write>(&mut self, _writer: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.begin(writer, b"{")
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.end(writer, b"}")
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.item(writer, first)
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        match self.indent.is_empty() {
+            true => writer.write_all(b":"),
+            false => writer.write_all(b": "),
+        }
+    }
+
+    fn end_object_value<W: ?Sized + io::Write>(&mut self, _writer: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+
+    fn write_f32<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        match self.float {
+            FloatFormat::Precision(n) => write!(writer, "{:.*}", n, value),
+            FloatFormat::Integer if value.fract() == 0.0 && value.abs() < 1e16 => {
+                write!(writer, "{}", value)
+            }
+            _ => CompactFormatter.write_f32(writer, value),
+        }
+    }
+
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        match self.float {
+            FloatFormat::Precision(n) => write!(writer, "{:.*}", n, value),
+            FloatFormat::Integer if value.fract() == 0.0 && value.abs() < 1e16 => {
+                write!(writer, "{}", value)
+            }
+            _ => CompactFormatter.write_f64(writer, value),
+        }
+    }
+
+    fn write_string_fragment<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        fragment: &str,
+    ) -> io::Result<()> {
+        if !self.ensure_ascii || fragment.is_ascii() {
+            return writer.write_all(fragment.as_bytes());
+        }
+        let mut units = [0u16; 2];
+        for c in fragment.chars() {
+            if c.is_ascii() {
+                writer.write_all(&[c as u8])?;
+            } else {
+                // characters beyond the BMP are escaped as surrogate pairs
+                for unit in c.encode_utf16(&mut units) {
+                    write!(writer, "\\u{:04x}", unit)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// indentation of one level, empty for concise output
+fn indent(options: &StringifyOptions) -> Vec<u8> {
+    let mut c = [0u8; 4];
+    let c = options.indent_char.encode_utf8(&mut c).as_bytes();
+    c.repeat(options.indent)
+}
+
+fn write<W: io::Write, T: Serialize + ?Sized>(
+    mut writer: W,
+    value: &T,
+    options: &StringifyOptions,
+    indent: &[u8],
+) -> Result<()> {
+    let formatter = OptionsFormatter {
+        indent,
+        newline: options.newline(),
+        ensure_ascii: options.ensure_ascii,
+        float: options.float,
+        level: 0,
+        has_value: false,
+    };
+    let mut ser = serde_json::Serializer::with_formatter(&mut writer, formatter);
+    match options.non_finite {
+        NonFinite::Null => value.serialize(&mut ser)?,
+        policy => nonfinite::serialize(value, policy, &mut ser)?,
+    }
+    if options.trailing_newline {
+        writer.write_all(options.newline())?;
+    }
+    Ok(())
+}
+
+fn serialize<W, T>(writer: W, value: &T, options: &StringifyOptions, indent: &[u8]) -> Result<()>
+where
+    W: io::Write,
+    T: Serialize + ?Sized,
+{
+    if options.sort_keys {
+        let value = nonfinite::serialize(value, options.non_finite, serde_json::value::Serializer)?;
+        return write(writer, &sort_value(value), options, indent);
+    }
+    write(writer, value, options, indent)
+}
+
+/// Serialize the value into the writer
+pub fn to_writer<W, T>(writer: W, value: &T, options: &StringifyOptions) -> Result<()>
+where
+    W: io::Write,
+    T: Serialize + ?Sized,
+{
+    serialize(writer, value, options, &indent(options))
+}
+
+/// Serialize the value into a string
+pub fn to_string<T: Serialize + ?Sized>(value: &T, options: &StringifyOptions) -> Result<String> {
+    let mut buf = Vec::new();
+    to_writer(&mut buf, value, options)?;
+    String::from_utf8(buf).map_err(Error::custom)
+}
+
+// appends to the string, serde_json writes the text in whole characters
+struct StringWriter<'a>(&'a mut String);
+
+impl io::Write for StringWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let s =
+            std::str::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.0.push_str(s);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reusable stringifier for hot loops, the options are prepared once and the output is appended to the
+/// caller's buffer
+///
+/// ```rust
+/// let s = Stringifier::pretty(2);
+/// let mut buf = String::with_capacity(4096);
+/// for event in events {
+///     buf.clear();
+///     s.stringify_into(&event, &mut buf)?;
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Stringifier {
+    options: StringifyOptions,
+    indent: Vec<u8>,
+}
+
+impl Default for Stringifier {
+    fn default() -> Self {
+        Self::compact()
+    }
+}
+
+impl Stringifier {
+    /// Stringifier of the options, e.g. `StringifyOptions::new().indent(2).sort_keys(true)`
+    pub fn new(options: StringifyOptions) -> Self {
+        let indent = indent(&options);
+        Self { options, indent }
+    }
+
+    /// Concise output
+    pub fn compact() -> Self {
+        Self::new(StringifyOptions::new())
+    }
+
+    /// Pretty output with N spaces indentation
+    pub fn pretty(indent: usize) -> Self {
+        Self::new(StringifyOptions::new().indent(indent))
+    }
+
+    /// Options of the output
+    pub fn options(&self) -> &StringifyOptions {
+        &self.options
+    }
+
+    /// Serialize the value into a new string
+    pub fn stringify<T: Serialize + ?Sized>(&self, value: &T) -> Result<String> {
+        let mut buf = String::new();
+        self.stringify_into(value, &mut buf)?;
+        Ok(buf)
+    }
+
+    /// Append the value to the string, the string is unchanged on errors
+    pub fn stringify_into<T: Serialize + ?Sized>(&self, value: &T, buf: &mut String) -> Result<()> {
+        let len = buf.len();
+        let result = serialize(StringWriter(buf), value, &self.options, &self.indent);
+        if result.is_err() {
+            buf.truncate(len);
+        }
+        result
+    }
+
+    /// Serialize the value into the writer
+    pub fn to_writer<W: io::Write, T: Serialize + ?Sized>(
+        &self,
+        writer: W,
+        value: &T,
+    ) -> Result<()> {
+        serialize(writer, value, &self.options, &self.indent)
+    }
+}
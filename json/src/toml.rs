@@ -0,0 +1,61 @@
+//! TOML interop
+//!
+//! The declared structs are loaded from and saved to TOML files, e.g. Cargo-style configs, the errors report the
+//! field path and the location:
+//!
+//! ```rust
+//! let config: Config = native_json::read_toml("config.toml")?;
+//! native_json::write_toml("config.toml", &config)?;
+//! ```
+//!
+//! TOML has no null and its top level must be a table, `None` in arrays and top-level arrays are not serializable.
+//! The datetimes of TOML are not strings, they are reported at the field.
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// line and column of the byte offset, starting from 1
+fn location(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+    (line, column)
+}
+
+/// Parse from TOML text
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T> {
+    let e = match serde_path_to_error::deserialize(::toml::Deserializer::new(s)) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+    let path = e.path().to_string();
+    let e = e.into_inner();
+    let mut msg = e.message().trim_end().to_owned();
+    if let Some(span) = e.span() {
+        let value = s.get(span.clone()).unwrap_or_default();
+        if msg.starts_with("invalid type: map") && value.parse::<::toml::value::Datetime>().is_ok()
+        {
+            msg = format!("TOML datetime `{}` is not a string, quote it", value);
+        }
+        let (line, column) = location(s, span.start);
+        msg = format!("{} at line {} column {}", msg, line, column);
+    }
+    Err(match path.as_str() {
+        "." => Error::Custom(msg),
+        path => Error::Custom(format!("{}: {}", path, msg)),
+    })
+}
+
+/// Serialize into TOML text
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    ::toml::to_string_pretty(value).map_err(|e| {
+        let msg = e.to_string();
+        Error::Custom(match msg.as_str() {
+            m if m.contains("None") => format!("{}, TOML has no null", m),
+            m if m.contains("unsupported rust type") => {
+                format!("{}, the top level of TOML must be a table", m)
+            }
+            m => m.to_owned(),
+        })
+    })
+}
@@ -0,0 +1,96 @@
+//! Change tracking of values
+//!
+//! [Tracked] keeps a snapshot of the serialized value, the fields whose keys differ from the snapshot are dirty. The
+//! struct is declared with the `reflect` option, which lists the fields:
+//!
+//! ```rust
+//! json!{
+//! #[json(reflect)]
+//! Config { name: String, port: u16 }
+//! }
+//!
+//! let mut config = Tracked::new(native_json::read::<Config, _>("config.json")?)?;
+//! config.port = 8080;
+//! assert_eq!(config.dirty_fields()?, ["port"]);
+//! let changes = config.serialize_dirty()?; // {"port":8080}
+//! config.commit()?;
+//! ```
+use crate::{Error, Reflect, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::ops::{Deref, DerefMut};
+
+// top-level keys and values
+fn snapshot<T: Serialize>(value: &T) -> Result<Map<String, Value>> {
+    match serde_json::to_value(value)? {
+        Value::Object(map) => Ok(map),
+        _ => Err(Error::custom("the tracked value is not a JSON object")),
+    }
+}
+
+/// A value with the snapshot since it was loaded or committed
+#[derive(Debug, Clone)]
+pub struct Tracked<T> {
+    value: T,
+    original: Map<String, Value>,
+}
+
+impl<T: Serialize + Reflect> Tracked<T> {
+    pub fn new(value: T) -> Result<Self> {
+        let original = snapshot(&value)?;
+        Ok(Self { value, original })
+    }
+
+    // JSON keys of the fields which are modified, added or removed in the current snapshot
+    fn dirty(&self, current: &Map<String, Value>) -> Vec<&'static str> {
+        T::fields()
+            .iter()
+            .map(|field| field.key)
+            .filter(|key| self.original.get(*key) != current.get(*key))
+            .collect()
+    }
+
+    /// JSON keys of the fields which are modified, added or removed since the snapshot, in the declaration order
+    pub fn dirty_fields(&self) -> Result<Vec<&'static str>> {
+        Ok(self.dirty(&snapshot(&self.value)?))
+    }
+
+    pub fn is_dirty(&self) -> Result<bool> {
+        Ok(self.original != snapshot(&self.value)?)
+    }
+
+    /// JSON object of the dirty keys, the removed keys are null
+    pub fn serialize_dirty(&self) -> Result<String> {
+        let mut current = snapshot(&self.value)?;
+        let mut dirty = Map::new();
+        for key in self.dirty(&current) {
+            let value = current.remove(key).unwrap_or(Value::Null);
+            dirty.insert(key.to_owned(), value);
+        }
+        Ok(serde_json::to_string(&dirty)?)
+    }
+
+    /// Take the current value as the snapshot, e.g. after it is saved
+    pub fn commit(&mut self) -> Result<()> {
+        self.original = snapshot(&self.value)?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.value
+    }
+}
@@ -0,0 +1,169 @@
+//! Runtime validation of declared structs
+//!
+//! Constraint annotations in declarations generate an implementation of [Validate]:
+//!
+//! ```rust
+//! json!{ User {
+//!     name: String @min_len(1) @max_len(32),
+//!     age: u16 @range(0, 150),
+//!     email: String @regex("^[^@]+@[^@]+$"), // requires the `regex` feature
+//!     tags: [String] @max_len(8),
+//!     score: f64 @min(0)
+//! }}
+//!
+//! let user = User::new();
+//! if let Err(errors) = user.validate() {
+//!     println!("{}", errors); // name: length must be at least 1; ...
+//! }
+//! ```
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+
+/// A violation of the constraint
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// Field path, e.g. `students[3].age`
+    pub path: String,
+    pub message: String,
+}
+
+/// All violations of a value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValidationErrors {
+    pub errors: Vec<ValidationError>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, path: &str, message: String) {
+        let path = path.to_owned();
+        self.errors.push(ValidationError { path, message });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Ok if there is no violation
+    pub fn into_result(self) -> Result<(), ValidationErrors> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let errors: Vec<_> = self
+            .errors
+            .iter()
+            .map(|e| format!("{}: {}", e.path, e.message))
+            .collect();
+        write!(f, "{}", errors.join("; "))
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Validate the constraints declared by annotations
+pub trait Validate {
+    /// Collect the violations, field paths are prefixed by `path`
+    fn validate_into(&self, path: &str, errors: &mut ValidationErrors);
+
+    /// Validate all fields, report all violations
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        self.validate_into("", &mut errors);
+        errors.into_result()
+    }
+}
+
+/// Path of a field, e.g. `students[3]` + `age` is `students[3].age`
+pub fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+/// Length of string (in characters) or collection
+pub trait Length {
+    fn length(&self) -> usize;
+}
+
+impl Length for String {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl Length for &str {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl<T> Length for Vec<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> Length for VecDeque<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T, S> Length for HashSet<T, S> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> Length for BTreeSet<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K, V, S> Length for HashMap<K, V, S> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K, V> Length for BTreeMap<K, V> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: Length> Length for Option<T> {
+    fn length(&self) -> usize {
+        self.as_ref().map(|x| x.length()).unwrap_or(0)
+    }
+}
+
+/// Check `@regex("...")`, the compiled patterns are cached
+#[cfg(feature = "regex")]
+pub fn is_match(value: &str, pattern: &str) -> bool {
+    use std::sync::{Mutex, OnceLock};
+    static CACHE: OnceLock<Mutex<HashMap<String, regex::Regex>>> = OnceLock::new();
+
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+    if !cache.contains_key(pattern) {
+        match regex::Regex::new(pattern) {
+            Ok(re) => cache.insert(pattern.to_owned(), re),
+            Err(_) => return false,
+        };
+    }
+    cache[pattern].is_match(value)
+}
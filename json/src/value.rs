@@ -0,0 +1,78 @@
+//! Dynamic JSON values
+//!
+//! [Value] is `serde_json::Value`, indexed natively by keys and positions, [ValueExt] accesses it by the dotted
+//! paths of [flatten](crate::flatten()):
+//!
+//! ```rust
+//! use native_json::{Value, ValueExt, FromJSON, JSON};
+//!
+//! let mut value = school.to_value()?;
+//! let size = value["style"]["size"].as_i64();
+//! let age = value.get_path("students[0].age")?.as_u64();
+//! value.set_path("students[0].age", 19.into())?;
+//! let school = School::from_value(value)?;
+//! ```
+use crate::flatten::{entry, segments, Segment};
+use crate::Result;
+pub use serde_json::Value;
+
+/// Access of the value by the dotted paths, e.g. `style.size` and `students[0].age`
+pub trait ValueExt {
+    /// Value at the path, None if it does not exist or the path is invalid
+    fn get_path(&self, path: &str) -> Option<&Value>;
+
+    /// Mutable value at the path, None if it does not exist or the path is invalid
+    fn get_path_mut(&mut self, path: &str) -> Option<&mut Value>;
+
+    /// Replace the value at the path, the missing objects and arrays are created, the missing array items are null
+    fn set_path(&mut self, path: &str, value: Value) -> Result<()>;
+
+    /// Remove the value at the path, the later items of an array are shifted
+    fn remove_path(&mut self, path: &str) -> Option<Value>;
+}
+
+impl ValueExt for Value {
+    fn get_path(&self, path: &str) -> Option<&Value> {
+        let mut target = self;
+        for segment in segments(path).ok()? {
+            target = match segment {
+                Segment::Key(key) => target.get(key)?,
+                Segment::Index(i) => target.get(i)?,
+            };
+        }
+        Some(target)
+    }
+
+    fn get_path_mut(&mut self, path: &str) -> Option<&mut Value> {
+        let mut target = self;
+        for segment in segments(path).ok()? {
+            target = match segment {
+                Segment::Key(key) => target.get_mut(key)?,
+                Segment::Index(i) => target.get_mut(i)?,
+            };
+        }
+        Some(target)
+    }
+
+    fn set_path(&mut self, path: &str, value: Value) -> Result<()> {
+        *entry(self, path)? = value;
+        Ok(())
+    }
+
+    fn remove_path(&mut self, path: &str) -> Option<Value> {
+        let mut segments = segments(path).ok()?;
+        let last = segments.pop()?;
+        let mut target = self;
+        for segment in segments {
+            target = match segment {
+                Segment::Key(key) => target.get_mut(key)?,
+                Segment::Index(i) => target.get_mut(i)?,
+            };
+        }
+        match (target, last) {
+            (Value::Object(object), Segment::Key(key)) => object.remove(&key),
+            (Value::Array(items), Segment::Index(i)) if i < items.len() => Some(items.remove(i)),
+            _ => None,
+        }
+    }
+}
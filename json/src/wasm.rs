@@ -0,0 +1,52 @@
+//! WebAssembly interop
+//!
+//! The declared structs cross the JS boundary as plain objects with the JSON keys, without stringifying:
+//!
+//! ```rust
+//! #[wasm_bindgen]
+//! pub fn rename(user: JsValue, name: String) -> Result<JsValue, JsValue> {
+//!     let mut user = User::from_js_value(user)?;
+//!     user.name = name;
+//!     Ok(user.to_js_value()?)
+//! }
+//! ```
+//!
+//! The values are JSON compatible, maps are objects and 64-bit integers are numbers if they are safe integers.
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use wasm_bindgen::{JsError, JsValue};
+
+impl From<serde_wasm_bindgen::Error> for Error {
+    fn from(e: serde_wasm_bindgen::Error) -> Self {
+        Error::Custom(e.to_string())
+    }
+}
+
+impl From<serde_path_to_error::Error<serde_wasm_bindgen::Error>> for Error {
+    fn from(e: serde_path_to_error::Error<serde_wasm_bindgen::Error>) -> Self {
+        match e.path().iter().next() {
+            Some(_) => Error::Custom(format!("{}: {}", e.path(), e.inner())),
+            None => Error::Custom(e.into_inner().to_string()),
+        }
+    }
+}
+
+/// JS `Error` of the message, so `?` works in the exported functions
+impl From<Error> for JsValue {
+    fn from(e: Error) -> Self {
+        JsError::new(&e.to_string()).into()
+    }
+}
+
+/// Convert the value into a JS value, structs are plain objects with the JSON keys
+pub fn to_js_value<T: Serialize + ?Sized>(value: &T) -> Result<JsValue> {
+    let serializer = serde_wasm_bindgen::Serializer::json_compatible();
+    Ok(value.serialize(&serializer)?)
+}
+
+/// Convert from a JS value, the errors report the field path
+pub fn from_js_value<T: DeserializeOwned>(value: JsValue) -> Result<T> {
+    let de = serde_wasm_bindgen::Deserializer::from(value);
+    Ok(serde_path_to_error::deserialize(de)?)
+}
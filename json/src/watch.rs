@@ -0,0 +1,186 @@
+//! Hot reload of files
+//!
+//! [watch] polls the file in a background thread, the file is reloaded when it stops changing for the debounce
+//! duration, the snapshots are shared by `Arc`:
+//!
+//! ```rust
+//! let config = native_json::watch::<Config, _>("config.json")?;
+//! config.on_change(|c| println!("port {}", c.port));
+//! let port = config.current().port;
+//!
+//! // reloads and parse errors, the old snapshot is kept on errors
+//! while let Some(result) = config.recv_timeout(Duration::from_secs(1)) {
+//!     if let Err(e) = result {
+//!         eprintln!("config.json: {}", e);
+//!     }
+//! }
+//! ```
+use crate::Result;
+use serde::de::DeserializeOwned;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+// shared, so the list is cloned and the callbacks run without the lock of the list
+type Callback<T> = Arc<Mutex<dyn FnMut(&Arc<T>) + Send>>;
+
+/// Options of [watch_with]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WatchOptions {
+    interval: Duration,
+    debounce: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WatchOptions {
+    /// Poll every 500ms, reload after the file is unchanged for 100ms
+    pub fn new() -> Self {
+        Self {
+            interval: Duration::from_millis(500),
+            debounce: Duration::from_millis(100),
+        }
+    }
+
+    /// Interval of polling the modification time and the size
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Wait until the file is unchanged for the duration, so a file written in steps is reloaded once
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+}
+
+/// Handle of a watched file, the background thread stops when it is dropped
+pub struct Watcher<T> {
+    path: PathBuf,
+    current: Arc<Mutex<Arc<T>>>,
+    callbacks: Arc<Mutex<Vec<Callback<T>>>>,
+    changes: Mutex<Receiver<Result<Arc<T>>>>,
+    stop: Option<Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl<T> Watcher<T> {
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Snapshot of the latest value which was parsed successfully
+    pub fn current(&self) -> Arc<T> {
+        self.current.lock().unwrap().clone()
+    }
+
+    /// Call the closure in the background thread after every successful reload
+    pub fn on_change<F>(&self, f: F)
+    where
+        F: FnMut(&Arc<T>) + Send + 'static,
+    {
+        self.callbacks.lock().unwrap().push(Arc::new(Mutex::new(f)));
+    }
+
+    /// Next reload or parse error, None if there is none yet
+    pub fn try_recv(&self) -> Option<Result<Arc<T>>> {
+        self.changes.lock().unwrap().try_recv().ok()
+    }
+
+    /// Wait for the next reload or parse error, None on timeout
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<Result<Arc<T>>> {
+        self.changes.lock().unwrap().recv_timeout(timeout).ok()
+    }
+}
+
+impl<T> Drop for Watcher<T> {
+    fn drop(&mut self) {
+        // disconnect, so the thread wakes up and stops
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+// modification time and size, None if the file does not exist
+fn stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Watch the file with the default options, it is parsed once before returning
+pub fn watch<T, P>(path: P) -> Result<Watcher<T>>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+    P: AsRef<Path>,
+{
+    watch_with(path, WatchOptions::new())
+}
+
+/// Watch the file, it is parsed once before returning
+pub fn watch_with<T, P>(path: P, options: WatchOptions) -> Result<Watcher<T>>
+where
+    T: DeserializeOwned + Send + Sync + 'static,
+    P: AsRef<Path>,
+{
+    let path = path.as_ref().to_path_buf();
+    let mut last = stamp(&path);
+    let current = Arc::new(Mutex::new(Arc::new(crate::read::<T, _>(&path)?)));
+    let callbacks: Arc<Mutex<Vec<Callback<T>>>> = Arc::default();
+    let (sender, changes) = mpsc::channel();
+    let (stop, stopped) = mpsc::channel::<()>();
+
+    let thread = {
+        let path = path.clone();
+        let current = current.clone();
+        let callbacks = callbacks.clone();
+        // false if the watcher is dropped
+        let sleep = move |d| matches!(stopped.recv_timeout(d), Err(RecvTimeoutError::Timeout));
+        std::thread::spawn(move || {
+            while sleep(options.interval) {
+                let mut now = stamp(&path);
+                if now == last {
+                    continue;
+                }
+                loop {
+                    if !sleep(options.debounce) {
+                        return;
+                    }
+                    match stamp(&path) {
+                        next if next == now => break,
+                        next => now = next,
+                    }
+                }
+                last = now;
+                let result = crate::read::<T, _>(&path).map(Arc::new);
+                if let Ok(value) = &result {
+                    *current.lock().unwrap() = value.clone();
+                    // a callback may register another one
+                    let list = callbacks.lock().unwrap().clone();
+                    for f in list {
+                        (f.lock().unwrap())(value);
+                    }
+                }
+                let _ = sender.send(result);
+            }
+        })
+    };
+
+    Ok(Watcher {
+        path,
+        current,
+        callbacks,
+        changes: Mutex::new(changes),
+        stop: Some(stop),
+        thread: Some(thread),
+    })
+}
@@ -0,0 +1,40 @@
+//! YAML interop
+//!
+//! The declared structs are loaded from and saved to YAML files, the errors report the field path and the location:
+//!
+//! ```rust
+//! let config: Config = native_json::read_yaml("config.yaml")?;
+//! native_json::write_yaml("config.yaml", &config)?;
+//! native_json::writer(&"config.yaml").format(Format::Yaml).atomic(true).write(&config)?;
+//! ```
+use crate::{Error, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io;
+
+// the message of serde_yaml has the field path and the location
+impl From<serde_yaml::Error> for Error {
+    fn from(e: serde_yaml::Error) -> Self {
+        Error::Custom(e.to_string())
+    }
+}
+
+/// Parse from YAML text
+pub fn from_str<T: DeserializeOwned>(s: &str) -> Result<T> {
+    Ok(serde_yaml::from_str(s)?)
+}
+
+/// Parse from the YAML reader
+pub fn from_reader<R: io::Read, T: DeserializeOwned>(reader: R) -> Result<T> {
+    Ok(serde_yaml::from_reader(reader)?)
+}
+
+/// Serialize into YAML text
+pub fn to_string<T: Serialize + ?Sized>(value: &T) -> Result<String> {
+    Ok(serde_yaml::to_string(value)?)
+}
+
+/// Serialize into the YAML writer
+pub fn to_writer<W: io::Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<()> {
+    Ok(serde_yaml::to_writer(writer, value)?)
+}
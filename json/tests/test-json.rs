@@ -1,8 +1,45 @@
+// the original tests of optional fields and keywords predate these lints of the toolchain
+#![allow(
+    unused_assignments,
+    clippy::bool_comparison,
+    clippy::useless_conversion
+)]
 use native_json::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 type Pod<T = (), E = anyhow::Error> = core::result::Result<T, E>;
 
+// directory of a test under the temp dir, removed when dropped
+struct TempDir(std::path::PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> std::io::Result<Self> {
+        let dir = std::env::temp_dir().join(format!("native-json-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self(dir))
+    }
+}
+
+impl std::ops::Deref for TempDir {
+    type Target = std::path::Path;
+
+    fn deref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+impl AsRef<std::path::Path> for TempDir {
+    fn as_ref(&self) -> &std::path::Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
 #[test]
 fn json_optional_field() -> Pod {
     // optional field: key: type?
@@ -81,100 +118,4170 @@ fn json_instance() {
 }
 
 #[test]
-fn json_declare() {
-    json! {
-    School {
-        name: String,
-        students:[{
-            name: String,
-            age: i32,
-            tutor: {
-                name: String,
-                course: String
-              }
-            },
-            ...
-        ],
-        nullable: Option<String>,
-        map: HashMap<String, i32>
-    }}
+fn json_pointer() -> Pod {
+    let json = json! { style: { size: 12, "font/family": "serif" }, array: [5, 4, 3] };
+    assert_eq!(json.pointer("/style/size"), Some(12.into()));
+    assert_eq!(json.pointer("/style/font~1family"), Some("serif".into()));
+    assert_eq!(json.pointer("/array/2"), Some(3.into()));
+    assert_eq!(json.pointer(""), Some(serde_json::to_value(&json)?));
+    assert!(json.pointer("/style/color").is_none());
 
-    let mut school = School::new();
-    school.name = "MIT".to_owned();
+    json! { Pointer { style: { size: u8 }, array: [u8] } }
+    let mut pointer = Pointer::new();
+    pointer.array.push(0);
+    pointer.set_pointer("/style/size", 14.into())?;
+    pointer.set_pointer("/array/0", 1.into())?;
+    assert_eq!((pointer.style.size, pointer.array[0]), (14, 1));
+    assert!(pointer.set_pointer("/array/1", 2.into()).is_err());
+    assert!(pointer.set_pointer("/style/size", "large".into()).is_err());
 
-    let mut newbie = School_students_item::new();
-    let tutor = School_students_item_tutor {
-        name: "Don Markuson".to_owned(),
-        course: "Math".to_owned(),
+    Ok(())
+}
+
+#[test]
+fn json_flatten() -> Pod {
+    use native_json::{flatten, unflatten};
+
+    let value = serde_json::json!({
+        "style": { "size": 14, "font.family": "serif" },
+        "students": [{ "age": 18 }, { "age": 19, "tags": [] }],
+        "empty": {},
+        "note": null
+    });
+    let map = flatten(&value);
+    let keys: Vec<_> = map.keys().map(String::as_str).collect();
+    assert_eq!(
+        keys,
+        [
+            "empty",
+            "note",
+            "students[0].age",
+            "students[1].age",
+            "students[1].tags",
+            "style.font\\.family",
+            "style.size"
+        ]
+    );
+    assert_eq!(map["students[1].age"], 19);
+    assert_eq!(unflatten(map)?, value);
+    assert_eq!(flatten(&1.into()).get(""), Some(&1.into()));
+    assert_eq!(unflatten([("", serde_json::Value::from(1))])?, 1);
+
+    // items are indexed in any order, the gaps are null
+    let value = unflatten([("a[10]", 1), ("a[2]", 2)].map(|(k, v)| (k, v.into())))?;
+    assert_eq!(value["a"].as_array().unwrap().len(), 11);
+    assert_eq!(
+        (value["a"][2].clone(), value["a"][3].clone()),
+        (2.into(), serde_json::Value::Null)
+    );
+
+    let e = unflatten([("a", 1), ("a.b", 2)].map(|(k, v)| (k, v.into()))).unwrap_err();
+    assert_eq!(e.to_string(), "conflicting path `a.b`");
+    let e = unflatten([("a[x]", serde_json::Value::Null)]).unwrap_err();
+    assert_eq!(e.to_string(), "invalid path `a[x]`");
+    Ok(())
+}
+
+#[test]
+fn json_value() -> Pod {
+    use native_json::{Value, ValueExt};
+
+    json! { Dynamic { style: { size: u8 }, students: [{ name: String, age: u16 }] } }
+    let mut school = Dynamic::new();
+    school.style.size = 12;
+    school.students.push(Dynamic_students_item::new());
+
+    let mut value: Value = school.to_value()?;
+    assert_eq!(value["style"]["size"].as_i64(), Some(12));
+    assert_eq!(value.get_path("students[0].age"), Some(&0.into()));
+    assert!(value.get_path("students[1].age").is_none());
+    assert!(value.get_path("students.age").is_none());
+
+    value.set_path("students[0].age", 19.into())?;
+    *value.get_path_mut("style.size").unwrap() = 14.into();
+    value["students"][0]["name"] = "Tom".into();
+    let school = Dynamic::from_value(value.clone())?;
+    assert_eq!((school.style.size, school.students[0].age), (14, 19));
+    assert_eq!(school.students[0].name, "Tom");
+
+    // the missing objects and arrays are created
+    value.set_path("extra.tags[1]", "b".into())?;
+    assert_eq!(value["extra"], serde_json::json!({ "tags": [null, "b"] }));
+    assert!(value.set_path("style.size.x", 1.into()).is_err());
+    assert_eq!(value.remove_path("extra.tags[0]"), Some(Value::Null));
+    assert_eq!(
+        value.remove_path("extra"),
+        Some(serde_json::json!({ "tags": ["b"] }))
+    );
+    assert!(value.remove_path("extra").is_none());
+
+    // the errors report the field path
+    value.set_path("students[0].age", "x".into())?;
+    let e = Dynamic::from_value(value).unwrap_err();
+    assert_eq!(e.path(), "students[0].age");
+    Ok(())
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn json_preserve_order() -> Pod {
+    use native_json::OrderedMap;
+
+    json! { Ordered { servers: OrderedMap<String, u16>, extra: serde_json::Value } }
+
+    let text = r#"{"servers":{"zeta":1,"alpha":2,"mid":3},"extra":{"b":1,"a":{"y":2,"x":3}}}"#;
+    let mut config: Ordered = native_json::parse(text)?;
+    let keys: Vec<_> = config.servers.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["zeta", "alpha", "mid"]);
+    assert_eq!(config.string()?, text);
+
+    // new keys are appended, rewriting keeps the order of the user
+    config.servers.insert("beta".to_owned(), 4);
+    config.merge_from_str(r#"{"extra":{"c":5}}"#)?;
+    assert_eq!(
+        config.string()?,
+        r#"{"servers":{"zeta":1,"alpha":2,"mid":3,"beta":4},"extra":{"b":1,"a":{"y":2,"x":3},"c":5}}"#
+    );
+    let sorted = config.stringify_with(&StringifyOptions::new().sort_keys(true))?;
+    assert!(sorted.starts_with(r#"{"extra":{"a":{"x":3,"y":2},"b":1,"c":5},"servers":{"alpha":2"#));
+    Ok(())
+}
+
+#[test]
+fn json_merge() -> Pod {
+    json! { Layered { name: String, note: String?, server: { host: String, port: u16 }, tags: [String] } }
+
+    let mut config = Layered::new();
+    config.name = "app".to_owned();
+    config.note = "default".to_owned();
+    config.server.host = "localhost".to_owned();
+    config.server.port = 80;
+    config.tags = vec!["a".to_owned(), "b".to_owned()];
+
+    config.merge_from_str("{\"note\":null,\"server\":{\"port\":8080},\"tags\":[\"c\"]}")?;
+    assert_eq!((config.name.as_str(), config.note.as_str()), ("app", ""));
+    assert_eq!(
+        (config.server.host.as_str(), config.server.port),
+        ("localhost", 8080)
+    );
+    assert_eq!(config.tags, ["c"]);
+
+    let mut other = config.clone();
+    other.name = "cli".to_owned();
+    other.server.port = 9090;
+    config.merge(&other)?;
+    assert_eq!((config.name.as_str(), config.server.port), ("cli", 9090));
+
+    assert!(config
+        .merge_from_str("{\"server\":{\"port\":\"x\"}}")
+        .is_err());
+
+    let mut value = json! { a: { b: 1, c: 2 }, d: [1] };
+    value.merge_from_str("{\"a\":{\"c\":3}}")?;
+    assert_eq!((value.a.b, value.a.c), (1, 3));
+
+    Ok(())
+}
+
+#[test]
+fn json_patch() -> Pod {
+    json! { Doc { title: String, note: String?, tags: [String], meta: { version: u32 } } }
+
+    let mut before = Doc::new();
+    before.title = "a".to_owned();
+    before.note = "x".to_owned();
+    before.tags = vec!["a".to_owned(), "b".to_owned(), "c".to_owned()];
+    let mut after = before.clone();
+    after.title = "b".to_owned();
+    after.note.clear();
+    after.tags = vec!["a".to_owned(), "d".to_owned()];
+    after.meta.version = 2;
+
+    let ops = native_json::patch::diff(&before, &after)?;
+    // keys are in the order of serde_json::Map
+    let mut sorted = ops.clone();
+    sorted.sort_by_key(|x| serde_json::to_value(x).unwrap()["path"].to_string());
+    assert_eq!(
+        serde_json::to_string(&sorted)?,
+        "[{\"op\":\"replace\",\"path\":\"/meta/version\",\"value\":2},\
+        {\"op\":\"remove\",\"path\":\"/note\"},\
+        {\"op\":\"replace\",\"path\":\"/tags/1\",\"value\":\"d\"},\
+        {\"op\":\"remove\",\"path\":\"/tags/2\"},\
+        {\"op\":\"replace\",\"path\":\"/title\",\"value\":\"b\"}]"
+    );
+    native_json::patch::apply(&mut before, &ops)?;
+    assert_eq!(before.string()?, after.string()?);
+    assert!(native_json::patch::diff(&before, &after)?.is_empty());
+
+    let ops: Vec<PatchOp> = native_json::parse(
+        "[{\"op\":\"test\",\"path\":\"/a/b\",\"value\":1},\
+        {\"op\":\"add\",\"path\":\"/c/0\",\"value\":0},\
+        {\"op\":\"move\",\"from\":\"/a/b\",\"path\":\"/d\"},\
+        {\"op\":\"copy\",\"from\":\"/d\",\"path\":\"/c/-\"}]",
+    )?;
+    let mut value: serde_json::Value = native_json::parse("{\"a\":{\"b\":1},\"c\":[2]}")?;
+    native_json::patch::apply_value(&mut value, &ops)?;
+    assert_eq!(value.string()?, "{\"a\":{},\"c\":[0,2,1],\"d\":1}");
+
+    // atomic
+    let ops = [
+        PatchOp::Remove {
+            path: "/d".to_owned(),
+        },
+        PatchOp::Remove {
+            path: "/e".to_owned(),
+        },
+    ];
+    assert!(native_json::patch::apply_value(&mut value, &ops).is_err());
+    assert_eq!(value["d"], 1);
+
+    Ok(())
+}
+
+#[test]
+fn json_stringify_buffer() -> Pod {
+    let json = json! { name: "a", list: [1, 2] };
+    let text = "{\"name\":\"a\",\"list\":[1,2]}";
+    assert_eq!(json.to_bytes()?, text.as_bytes());
+
+    let mut bytes = b"data: ".to_vec();
+    json.write_to(&mut bytes)?;
+    assert_eq!(bytes, format!("data: {text}").as_bytes());
+
+    let mut buf = String::with_capacity(64);
+    json.stringify_into(&mut buf)?;
+    json.stringify_into(&mut buf)?;
+    assert_eq!(buf, format!("{text}{text}"));
+    buf.clear();
+    json.stringify_into(&mut buf)?;
+    assert_eq!(buf, text);
+
+    // the buffer is kept if it fails
+    let mut map = HashMap::new();
+    map.insert(vec![1], 1);
+    assert!(map.stringify_into(&mut buf).is_err());
+    assert_eq!(buf, text);
+
+    Ok(())
+}
+
+#[test]
+fn json_stringifier() -> Pod {
+    let json = json! { name: "é", list: [1.5, 2.0] };
+
+    let s = Stringifier::pretty(2);
+    let mut buf = String::from("> ");
+    s.stringify_into(&json, &mut buf)?;
+    s.stringify_into(&json, &mut buf)?;
+    let text = json.stringify(2)?;
+    assert_eq!(buf, format!("> {text}{text}"));
+    assert_eq!(s.stringify(&json)?, text);
+
+    let options = StringifyOptions::new()
+        .indent(1)
+        .indent_char('\t')
+        .ensure_ascii(true)
+        .float_format(FloatFormat::Integer)
+        .sort_keys(true)
+        .trailing_newline(true);
+    let s = Stringifier::new(options.clone());
+    let mut bytes = Vec::new();
+    s.to_writer(&mut bytes, &json)?;
+    assert_eq!(bytes, json.stringify_with(&options)?.as_bytes());
+    assert_eq!(
+        s.stringify(&json)?,
+        "{\n\t\"list\": [\n\t\t1.5,\n\t\t2\n\t],\n\t\"name\": \"\\u00e9\"\n}\n"
+    );
+    assert_eq!(Stringifier::default().stringify(&json)?, json.string()?);
+
+    // the buffer is kept if it fails
+    let mut map = HashMap::new();
+    map.insert(vec![1], 1);
+    buf.clear();
+    assert!(s.stringify_into(&map, &mut buf).is_err());
+    assert!(buf.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn json_from_json() -> Pod {
+    json! { Server { host: String, port: u16, tls: { cert: String?, enabled: bool } } }
+
+    let text = "{\"host\":\"a\",\"port\":80,\"tls\":{\"cert\":\"x.pem\",\"enabled\":true}}";
+    let server = Server::from_json(text)?;
+    assert_eq!((server.host.as_str(), server.port), ("a", 80));
+    let server = Server::from_slice(text.as_bytes())?;
+    assert_eq!(server.tls.cert, "x.pem");
+    let mut server = Server::from_reader(std::io::Cursor::new(text))?;
+    assert!(server.tls.enabled);
+
+    server.update_from_str("{\"port\":8080,\"tls\":{\"enabled\":false}}")?;
+    assert_eq!((server.host.as_str(), server.port), ("a", 8080));
+    assert_eq!(
+        (server.tls.cert.as_str(), server.tls.enabled),
+        ("x.pem", false)
+    );
+
+    // typed errors, the value is unchanged
+    let err: native_json::Error = server.update_from_str("{\"port\":\"x\"}").unwrap_err();
+    assert!(matches!(err, native_json::Error::Type { .. }));
+    let err = Server::from_json("{").unwrap_err();
+    assert!(matches!(err, native_json::Error::Syntax { .. }));
+    assert_eq!(server.port, 8080);
+
+    Ok(())
+}
+
+#[test]
+fn json_error_path() -> Pod {
+    json! { Class { id: u8 } }
+    json! { Classes { students: [{ name: String, age: u16 }], map: HashMap<String, Class> } }
+
+    let text = "{\n  \"students\": [\n    { \"name\": \"a\", \"age\": 1 },\n    { \"name\": \"b\", \"age\": \"x\" }\n  ],\n  \"map\": {}\n}";
+    let err = native_json::parse::<Classes>(text).unwrap_err();
+    assert_eq!(err.path(), "students[1].age");
+    assert_eq!((err.line(), err.column()), (4, 29));
+    assert_eq!(
+        err.to_string(),
+        "students[1].age: invalid type: string \"x\", expected u16 at line 4 column 29"
+    );
+
+    let err = Classes::from_json("{\"students\":[],\"map\":{\"k\":{\"id\":300}}}").unwrap_err();
+    assert_eq!(err.path(), "map.k.id");
+    let err = Classes::from_json("{\"students\":[],\"map\":{}} x").unwrap_err();
+    assert!(matches!(
+        err,
+        native_json::Error::Syntax {
+            line: 1,
+            column: 26,
+            ..
+        }
+    ));
+
+    let mut classes = Classes::new();
+    let err = classes
+        .set_pointer("/students", serde_json::json!([{ "name": 1 }]))
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .starts_with("students[0].name: invalid type"));
+
+    Ok(())
+}
+
+#[test]
+fn json_error_kind() -> Pod {
+    json! { Kind { age: u16 @max(150) } }
+
+    let err = native_json::read::<Kind, _>("/nonexistent/kind.json").unwrap_err();
+    match err {
+        native_json::Error::Io(e) => assert_eq!(e.kind(), std::io::ErrorKind::NotFound),
+        _ => panic!("expected io error"),
+    }
+
+    let mut kind = Kind::new();
+    kind.age = 200;
+    let err: native_json::Error = kind.validate().unwrap_err().into();
+    assert!(matches!(err, native_json::Error::Validation(ref e) if e.errors.len() == 1));
+
+    let err = kind.set_pointer("/name", 1.into()).unwrap_err();
+    assert_eq!(err.to_string(), "no value at pointer `/name`");
+    assert!(matches!(err, native_json::Error::Custom(_)));
+
+    // anyhow interop
+    let err: anyhow::Error = Kind::from_json("[]").unwrap_err().into();
+    assert!(err.downcast_ref::<native_json::Error>().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn json_write_atomic() -> Pod {
+    let dir = TempDir::new("atomic")?;
+    let path = dir.join("state.json");
+
+    let state = json! { name: "state", items: [1, 2, 3] };
+    native_json::write_atomic(&path, &state)?;
+    assert_eq!(std::fs::read_to_string(&path)?, state.stringify(2)?);
+
+    native_json::writer(&path)
+        .indent(0)
+        .atomic(true)
+        .write(&state)?;
+    assert_eq!(std::fs::read_to_string(&path)?, state.string()?);
+
+    // the target is intact if it fails, and the temporary file is removed
+    let mut bad = HashMap::new();
+    bad.insert(vec![1], 1);
+    assert!(native_json::write_atomic(&path, &bad).is_err());
+    assert!(native_json::writer(&path).atomic(true).write(&bad).is_err());
+    assert_eq!(std::fs::read_to_string(&path)?, state.string()?);
+    assert_eq!(std::fs::read_dir(&dir)?.count(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn json_write_backup() -> Pod {
+    let dir = TempDir::new("backup")?;
+    let path = dir.join("config.json");
+    let backup = |n: usize| dir.join(format!("config.json.{n}"));
+
+    for version in 1..=4 {
+        let config = json! { version: version };
+        native_json::writer(&path)
+            .indent(0)
+            .backup(2)
+            .write(&config)?;
+    }
+    assert_eq!(std::fs::read_to_string(&path)?, "{\"version\":4}");
+    assert_eq!(std::fs::read_to_string(backup(1))?, "{\"version\":3}");
+    assert_eq!(std::fs::read_to_string(backup(2))?, "{\"version\":2}");
+    assert!(!backup(3).exists());
+
+    // the latest valid backup is restored
+    std::fs::write(&path, "{\"version\":")?;
+    std::fs::write(backup(1), "corrupt")?;
+    json! { Versioned { version: u32 } }
+    let config: Versioned =
+        native_json::read(&path).or_else(|_| native_json::restore_latest(&path))?;
+    assert_eq!(config.version, 2);
+    assert_eq!(std::fs::read_to_string(&path)?, "{\"version\":2}");
+
+    assert!(native_json::restore_latest::<Versioned, _>(dir.join("missing.json")).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn json_writer_create_dirs() -> Pod {
+    json! { Settings { token: String } }
+
+    let dir = TempDir::new("dirs")?;
+    let path = dir.join("app").join("settings.json");
+    let settings = Settings {
+        token: "secret".to_owned(),
     };
-    newbie.name = "John".to_owned();
-    newbie.age = 17;
-    newbie.tutor = tutor;
-    school.students.push(newbie);
+    assert!(native_json::writer(&path).write(&settings).is_err());
+
+    let writer = native_json::writer(&path).create_dirs(true);
+    #[cfg(unix)]
+    let writer = writer.mode(0o600);
+    writer.write(&settings)?;
+    let read: Settings = native_json::read(&path)?;
+    assert_eq!(read.token, "secret");
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = |path: &std::path::Path| -> std::io::Result<u32> {
+            Ok(std::fs::metadata(path)?.permissions().mode() & 0o777)
+        };
+        assert_eq!(mode(&path)?, 0o600);
+
+        // the mode also applies to existing files and atomic writes
+        native_json::writer(&path).mode(0o640).write(&settings)?;
+        assert_eq!(mode(&path)?, 0o640);
+        native_json::writer(&path)
+            .atomic(true)
+            .mode(0o600)
+            .write(&settings)?;
+        assert_eq!(mode(&path)?, 0o600);
+    }
+
+    Ok(())
 }
 
 #[test]
-fn json_serialize() -> Pod {
-    let mut json = json! {
-        name: "native json",
-        point: { x: 10, y: 20},
-        array: [1,2,3,4,5],
-        vector: vec![1,2,3,4,5,6]
+fn json_writer_append() -> Pod {
+    json! { Event { id: u32, tags: [String] } }
+
+    let dir = TempDir::new("append")?;
+    let path = dir.join("events.jsonl");
+    let event = |id| Event {
+        id,
+        tags: vec!["a".to_owned(), "b".to_owned()],
     };
 
-    // stringify
-    let s = json.stringify(4)?;
-    json.name = "";
-    json = native_json::parse(&s)?;
-    assert_eq!(json.name, "native json");
+    native_json::writer(&path).compact().write(&event(0))?;
+    assert_eq!(
+        std::fs::read_to_string(&path)?,
+        r#"{"id":0,"tags":["a","b"]}"#
+    );
 
-    // concise string
-    let s2 = json.string()?;
-    json.name = "modified";
-    json = native_json::parse(&s2)?;
-    assert_eq!(json.name, "native json");
+    // one concise line per document, regardless of the indentation
+    std::fs::remove_file(&path)?;
+    for id in 1..=3 {
+        native_json::writer(&path)
+            .indent(4)
+            .append(true)
+            .write(&event(id))?;
+    }
+    let text = std::fs::read_to_string(&path)?;
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[2], r#"{"id":3,"tags":["a","b"]}"#);
+    assert!(text.ends_with('\n'));
 
     Ok(())
 }
 
 #[test]
-fn json_test_array_with_custom_type() -> Result<(), std::io::Error> {
-    json! {
-        Student { name: String, age: u32}
+fn json_progress() -> Pod {
+    use std::sync::Mutex;
+
+    json! { State { items: [{ id: u32, name: String }] } }
+
+    let dir = TempDir::new("progress")?;
+    let path = dir.join("state.json");
+    let mut state = State::new();
+    for id in 0..10000 {
+        let name = format!("item {}", id);
+        state.items.push(State_items_item { id, name });
     }
 
-    json! {
-        Class {
-            name: String,
-            students: [Student]
-        }
+    // the total is unknown while writing, the last call is the file size
+    let calls = Mutex::new(Vec::new());
+    native_json::writer(&path)
+        .atomic(true)
+        .on_progress(|done, total| calls.lock().unwrap().push((done, total)))
+        .write(&state)?;
+    let size = std::fs::metadata(&path)?.len();
+    let calls = calls.into_inner().unwrap();
+    assert!(calls.len() > 2);
+    assert!(calls.windows(2).all(|x| x[0].0 < x[1].0));
+    assert_eq!(calls.last(), Some(&(size, None)));
+
+    let calls = Mutex::new(Vec::new());
+    let read: State = native_json::reader(&path)
+        .on_progress(|done, total| calls.lock().unwrap().push((done, total)))
+        .read()?;
+    assert_eq!(read.items.len(), 10000);
+    let calls = calls.into_inner().unwrap();
+    assert!(calls.len() > 2);
+    assert!(calls.iter().all(|x| x.1 == Some(size)));
+    assert_eq!(calls.last(), Some(&(size, Some(size))));
+
+    // the line is the total of appending
+    let log = dir.join("events.jsonl");
+    let last = Mutex::new((0, None));
+    native_json::writer(&log)
+        .append(true)
+        .on_progress(|done, total| *last.lock().unwrap() = (done, total))
+        .write(&state.items[0])?;
+    let size = std::fs::metadata(&log)?.len();
+    assert_eq!(last.into_inner().unwrap(), (size, Some(size)));
+
+    Ok(())
+}
+
+#[test]
+fn json_read_or_default() -> Pod {
+    json! { Config { name: String, port: u16 } }
+
+    let dir = TempDir::new("default")?;
+    let path = dir.join("app").join("config.json");
+
+    let config: Config = native_json::read_or_default(&path)?;
+    assert_eq!(config.port, 0);
+    let config: Config = native_json::read_or_else(&path, || Config {
+        name: "app".to_owned(),
+        port: 8080,
+    })?;
+    assert_eq!(config.port, 8080);
+    assert!(!path.exists());
+
+    let config: Config = native_json::read_or_create(&path, || Config {
+        name: "app".to_owned(),
+        port: 80,
+    })?;
+    assert_eq!(config.port, 80);
+    let config: Config = native_json::read_or_create(&path, Config::new)?;
+    assert_eq!(config.port, 80);
+
+    // malformed files are not replaced by the default
+    std::fs::write(&path, "{")?;
+    assert!(native_json::read_or_default::<Config, _>(&path).is_err());
+
+    Ok(())
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn json_read_mmap() -> Pod {
+    use native_json::mmap::MappedFile;
+
+    json! { Index { name: String, entries: [{ key: String, offset: u64 }] } }
+
+    #[derive(Deserialize)]
+    struct Borrowed<'a> {
+        name: &'a str,
     }
 
-    let mut c = Class::new();
-    c.name = "High School".into();
-    let mut student = Student::new();
-    student.name = "Tom Jackson".into();
-    student.age = 25;
-    c.students.push(student);
+    let dir = TempDir::new("mmap")?;
+    let path = dir.join("mmap.json");
+    std::fs::write(
+        &path,
+        r#"{"name": "index", "entries": [{"key": "a", "offset": 42}]}"#,
+    )?;
+    // SAFETY: the file is not modified while it is mapped
+    let index: Index = unsafe { native_json::read_mmap(&path)? };
+    assert_eq!(index.entries[0].offset, 42);
+
+    // the borrowed fields point into the mapping
+    let file = unsafe { MappedFile::open(&path)? };
+    let borrowed: Borrowed = file.parse()?;
+    assert_eq!(borrowed.name, "index");
+    assert!(file
+        .bytes()
+        .as_ptr_range()
+        .contains(&borrowed.name.as_ptr()));
+    drop(file);
+
+    // the errors report the path
+    std::fs::write(&path, "{\n  \"name\": 1\n}")?;
+    let e = unsafe { native_json::read_mmap::<Index, _>(&path) }.unwrap_err();
+    assert_eq!(e.path(), "name");
+    std::fs::write(&path, "")?;
+    let e = unsafe { native_json::read_mmap::<Index, _>(&path) }.unwrap_err();
+    assert!(matches!(e, Error::Syntax { .. }));
 
     Ok(())
 }
 
+#[cfg(feature = "jsonschema")]
 #[test]
-fn json_test_inline_comment() {
-    println!("should compile");
+fn json_read_validated() -> Pod {
     json! {
-    AggTrage {
-        e: String,   // Event type: aggTrade
-        E: i64,      // Event time
-        s: String,   // Symbol
-        a: i64,      // Aggregate trade ID
-        p: String,   // Price
-        q: String,   // Quantity
-        f: i64,      // First trade ID
-        l: i64,      // Last trade ID
-        T: i64,      // Trade time
-        m: bool,     // Is the buyer the market maker?
-        c: char,     // test only
+    #[json(schema)]
+    Roster { name: String, students: [{ name: String, age: u16 }] }}
+
+    let dir = TempDir::new("schema")?;
+    let path = dir.join("schema.json");
+    std::fs::write(
+        &path,
+        r#"{"name": "a", "students": [{"name": "b", "age": 10}]}"#,
+    )?;
+    let roster: Roster = native_json::read_validated(&path, &Roster::schema())?;
+    assert_eq!(roster.students[0].age, 10);
+
+    // all the violations are reported with the paths
+    std::fs::write(
+        &path,
+        r#"{"name": 1, "students": [{"name": "b", "age": 10}, {"name": "c", "age": "x"}]}"#,
+    )?;
+    let e = native_json::read_validated::<Roster, _>(&path, &Roster::schema()).unwrap_err();
+    let Error::Validation(errors) = e else {
+        panic!("{}", e);
+    };
+    let paths: Vec<&str> = errors.errors.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, ["name", "students[1].age"]);
+
+    let e = native_json::schema::validate(&serde_json::json!(1), &serde_json::json!({"type": 1}))
+        .unwrap_err();
+    assert!(matches!(e, Error::Custom(_)), "{}", e);
+    Ok(())
+}
+
+#[test]
+fn json_update() -> Pod {
+    json! { Counter { count: u32, names: [String] } }
+
+    let dir = TempDir::new("update")?;
+    let path = dir.join("counter.json");
+
+    let counter = native_json::update(&path, |c: &mut Counter| c.names.push("a".to_owned()))?;
+    assert_eq!(counter.names, ["a"]);
+
+    // no update is lost under the lock
+    let threads: Vec<_> = (0..8)
+        .map(|_| {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                for _ in 0..10 {
+                    native_json::update_locked(&path, |c: &mut Counter| c.count += 1)?;
+                }
+                Ok::<_, native_json::Error>(())
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap()?;
+    }
+    let counter: Counter = native_json::read(&path)?;
+    assert_eq!(counter.count, 80);
+    assert_eq!(counter.names, ["a"]);
+
+    Ok(())
+}
+
+#[test]
+fn json_watch() -> Pod {
+    use std::sync::atomic::{AtomicU16, Ordering};
+    use std::time::Duration;
+
+    json! { Config { port: u16 } }
+
+    let dir = TempDir::new("watch")?;
+    let path = dir.join("config.json");
+    std::fs::write(&path, r#"{"port": 80}"#)?;
+
+    let options = native_json::WatchOptions::new()
+        .interval(Duration::from_millis(10))
+        .debounce(Duration::from_millis(20));
+    let config = native_json::watch_with::<Config, _>(&path, options)?;
+    assert_eq!(config.current().port, 80);
+    let port = std::sync::Arc::new(AtomicU16::new(0));
+    let seen = port.clone();
+    config.on_change(move |c| seen.store(c.port, Ordering::SeqCst));
+
+    let timeout = Duration::from_secs(5);
+    std::fs::write(&path, r#"{"port": 8080}"#)?;
+    let reloaded = config.recv_timeout(timeout).unwrap()?;
+    assert_eq!(reloaded.port, 8080);
+    assert_eq!(config.current().port, 8080);
+    assert_eq!(port.load(Ordering::SeqCst), 8080);
+
+    // parse errors are reported, the last snapshot is kept
+    std::fs::write(&path, r#"{"port": "#)?;
+    assert!(config.recv_timeout(timeout).unwrap().is_err());
+    assert_eq!(config.current().port, 8080);
+    assert!(config.try_recv().is_none());
+
+    // a callback registers another one on the same handle
+    let config = std::sync::Arc::new(config);
+    let handle = std::sync::Arc::downgrade(&config);
+    let seen = port.clone();
+    config.on_change(move |_| {
+        if let Some(config) = handle.upgrade() {
+            let seen = seen.clone();
+            config.on_change(move |c| seen.store(c.port + 1, Ordering::SeqCst));
+        }
+    });
+    std::fs::write(&path, r#"{"port": 81}"#)?;
+    assert_eq!(config.recv_timeout(timeout).unwrap()?.port, 81);
+    std::fs::write(&path, r#"{"port": 8081}"#)?;
+    assert_eq!(config.recv_timeout(timeout).unwrap()?.port, 8081);
+    assert_eq!(port.load(Ordering::SeqCst), 8082);
+
+    drop(config);
+    Ok(())
+}
+
+#[test]
+fn json_config_layers() -> Pod {
+    use native_json::config::{Loader, Source};
+
+    json! { LayeredConfig {
+        name: String,
+        password: String,
+        style: { size: u32, color: String, bold: bool },
+        hosts: [String],
     }}
+
+    let dir = TempDir::new("config")?;
+    let path = dir.join("config.json");
+    std::fs::write(
+        &path,
+        r#"{"name": "app", "style": {"size": 12, "color": "red"}}"#,
+    )?;
+    std::env::set_var("NJ_LAYERED__STYLE__SIZE", "14");
+    std::env::set_var("NJ_LAYERED__PASSWORD", "1234");
+
+    let config = Loader::new()
+        .file(&path)
+        .optional_file(dir.join("local.json"))
+        .env("NJ_LAYERED")
+        .set("style.bold", true)
+        .overrides([("hosts", vec!["a", "b"])])
+        .load::<LayeredConfig>()?;
+    assert_eq!(config.name, "app");
+    assert_eq!(config.password, "1234");
+    assert_eq!(config.style.size, 14);
+    assert_eq!(config.style.color, "red");
+    assert!(config.style.bold);
+    assert_eq!(config.hosts, ["a", "b"]);
+
+    assert_eq!(config.source("name"), Some(&Source::File(path.clone())));
+    assert_eq!(
+        config.source("style.color"),
+        Some(&Source::File(path.clone()))
+    );
+    let env = Source::Env("NJ_LAYERED__STYLE__SIZE".to_owned());
+    assert_eq!(config.source("style.size"), Some(&env));
+    assert_eq!(config.source("style.bold"), Some(&Source::Override));
+    assert_eq!(config.source("hosts"), Some(&Source::Override));
+    assert_eq!(
+        config.source("password").unwrap().to_string(),
+        "env NJ_LAYERED__PASSWORD"
+    );
+    assert_eq!(config.sources().len(), 6);
+
+    // the required file must exist, the type errors report the path
+    assert!(Loader::new()
+        .file(dir.join("missing.json"))
+        .load::<LayeredConfig>()
+        .is_err());
+    let e = Loader::new()
+        .set("style.size", "x")
+        .load::<LayeredConfig>()
+        .unwrap_err();
+    assert_eq!(e.path(), "style.size");
+
+    std::env::remove_var("NJ_LAYERED__STYLE__SIZE");
+    std::env::remove_var("NJ_LAYERED__PASSWORD");
+    Ok(())
+}
+
+#[test]
+fn json_config_args() -> Pod {
+    use native_json::config::{Loader, Source};
+
+    json! { CliConfig {
+        name: String,
+        offset: i32,
+        style: { size: u32, bold: bool },
+    }}
+
+    let config = Loader::new()
+        .set("name", "app")
+        .args([
+            "--style.size",
+            "14",
+            "--name=1234",
+            "--style.bold",
+            "--offset",
+            "-5",
+        ])
+        .load::<CliConfig>()?;
+    assert_eq!(config.name, "1234");
+    assert_eq!((config.offset, config.style.size), (-5, 14));
+    assert!(config.style.bold);
+    let arg = Source::Arg("--style.size".to_owned());
+    assert_eq!(config.source("style.size"), Some(&arg));
+    assert_eq!(config.source("name").unwrap().to_string(), "arg --name");
+
+    // typos and the type errors
+    let e = Loader::new()
+        .args(["--style.sise", "1"])
+        .load::<CliConfig>();
+    assert_eq!(e.unwrap_err().to_string(), "unknown option `--style.sise`");
+    let e = Loader::new().args(["14"]).load::<CliConfig>().unwrap_err();
+    assert_eq!(e.to_string(), "unexpected argument `14`");
+    let e = Loader::new()
+        .args(["--offset"])
+        .load::<CliConfig>()
+        .unwrap_err();
+    assert_eq!(e.path(), "offset");
+
+    let mut config = CliConfig::new();
+    config.style.size = 12;
+    config.apply_cli_overrides(["--style.bold=true", "--name", "x"])?;
+    assert_eq!((config.style.size, config.style.bold), (12, true));
+    assert_eq!(config.name, "x");
+    assert!(config.apply_cli_overrides(["--size", "1"]).is_err());
+    Ok(())
+}
+
+#[cfg(feature = "clap")]
+#[test]
+fn json_config_clap() -> Pod {
+    use native_json::config::{augment, command, Loader};
+
+    json! { ClapConfig { name: String, style: { size: u32, bold: bool } } }
+
+    let matches = command::<ClapConfig>("app")?.try_get_matches_from([
+        "app",
+        "--style.size",
+        "14",
+        "--style.bold",
+    ])?;
+    let config = Loader::new()
+        .set("name", "app")
+        .matches(&matches)
+        .load::<ClapConfig>()?;
+    assert_eq!(config.name, "app");
+    assert_eq!(config.style.size, 14);
+    assert!(config.style.bold);
+
+    // the other arguments of the command are ignored
+    let app = clap::Command::new("app").arg(clap::Arg::new("config").long("config"));
+    let mut app = augment::<ClapConfig>(app)?;
+    let help = app.render_help().to_string();
+    assert!(help.contains("--style.size <NUMBER>"), "{}", help);
+    let matches = app.try_get_matches_from(["app", "--config", "a.json", "--name", "x"])?;
+    let config = Loader::new().matches(&matches).load::<ClapConfig>()?;
+    assert_eq!(config.name, "x");
+    assert!(command::<ClapConfig>("app")?
+        .try_get_matches_from(["app", "--style.sise", "1"])
+        .is_err());
+    Ok(())
+}
+
+#[test]
+fn json_interpolate() -> Pod {
+    use native_json::interpolate::expand_with;
+
+    json! { Database { url: String, hosts: [String], port: u16 } }
+
+    let dir = TempDir::new("env")?;
+    let path = dir.join("database.json");
+    std::fs::write(
+        &path,
+        r#"{"url": "postgres://${NJ_DB_USER}@${NJ_DB_HOST:-localhost}/app", "hosts": ["${NJ_DB_USER}", "$$HOME"], "port": 5432}"#,
+    )?;
+    std::env::set_var("NJ_DB_USER", "admin");
+
+    let db: Database = native_json::reader(&path).interpolate(true).read()?;
+    assert_eq!(db.url, "postgres://admin@localhost/app");
+    assert_eq!(db.hosts, ["admin", "$HOME"]);
+    let db: Database = native_json::reader(&path).read()?;
+    assert_eq!(db.hosts[0], "${NJ_DB_USER}");
+
+    let lookup = |name: &str| (name == "EMPTY").then(String::new);
+    assert_eq!(expand_with("${EMPTY:-x} $5 $${A}", lookup)?, "x $5 ${A}");
+    assert!(expand_with("${MISSING}", lookup).is_err());
+    assert!(expand_with("${EMPTY", lookup).is_err());
+
+    std::env::remove_var("NJ_DB_USER");
+    Ok(())
+}
+
+#[test]
+fn json_read_comments() -> Pod {
+    use native_json::ReadOptions;
+
+    json! { Editor { font: String, size: u32, rulers: [u32] } }
+
+    let text = r#"{
+    // the font, "//" is not a comment in strings
+    "font": "Fira // Code /* mono */",
+    /* size
+       in points */ "size": 14,
+    "rulers": [80, 120,],
+}"#;
+    assert!(native_json::parse::<Editor>(text).is_err());
+    let options = ReadOptions::new().allow_comments(true);
+    let e = native_json::parse_with::<Editor>(text, &options).unwrap_err();
+    assert_eq!((e.line(), e.column()), (6, 24));
+
+    let options = options.allow_trailing_commas(true);
+    let editor: Editor = native_json::parse_with(text, &options)?;
+    assert_eq!(editor.font, "Fira // Code /* mono */");
+    assert_eq!(editor.size, 14);
+    assert_eq!(editor.rulers, [80, 120]);
+
+    // an unterminated comment is a syntax error
+    let e = native_json::parse_with::<Editor>(
+        "{\"font\": \"a\", \"size\": 1, \"rulers\": []}\n/* oops",
+        &options,
+    )
+    .unwrap_err();
+    assert!(matches!(e, Error::Syntax { .. }), "{}", e);
+    assert_eq!((e.line(), e.column()), (2, 1));
+
+    let dir = TempDir::new("jsonc")?;
+    let path = dir.join("settings.json");
+    std::fs::write(&path, text)?;
+    let editor: Editor = native_json::read_with(&path, &options)?;
+    assert_eq!(editor.size, 14);
+    let editor: Editor = native_json::reader(&path)
+        .allow_comments(true)
+        .allow_trailing_commas(true)
+        .read()?;
+    assert_eq!(editor.rulers, [80, 120]);
+
+    Ok(())
+}
+
+#[test]
+fn json_read_limits() -> Pod {
+    json! { Request { name: String, tags: [[String]] } }
+
+    let text = r#"{"name": "[[[{{{", "tags": [["a"], []]}"#;
+    let options = ReadOptions::new().max_bytes(text.len()).max_depth(3);
+    let request: Request = native_json::parse_with(text, &options)?;
+    assert_eq!(request.tags[0], ["a"]);
+
+    let e = native_json::parse_with::<Request>(text, &options.clone().max_bytes(10)).unwrap_err();
+    assert!(matches!(
+        e,
+        Error::Limit {
+            limit: "max_bytes",
+            max: 10
+        }
+    ));
+    assert_eq!(e.to_string(), "the input exceeds max_bytes of 10");
+    let e = native_json::parse_with::<Request>(text, &options.clone().max_depth(2)).unwrap_err();
+    assert!(matches!(
+        e,
+        Error::Limit {
+            limit: "max_depth",
+            max: 2
+        }
+    ));
+
+    // the brackets of the comments are not counted
+    let lenient = ReadOptions::new().allow_comments(true).max_depth(3);
+    let text = "{\"name\": \"a\", \"tags\": [] /* [[[ */}";
+    let request: Request = native_json::parse_with(text, &lenient)?;
+    assert!(request.tags.is_empty());
+
+    // the file is not read beyond the limit
+    let dir = TempDir::new("limits")?;
+    let path = dir.join("limits.json");
+    let tags = vec![vec!["x".repeat(100)]; 1000];
+    native_json::write(
+        &path,
+        &Request {
+            name: "a".to_owned(),
+            tags,
+        },
+    )?;
+    let e = native_json::read_with::<Request, _>(&path, &ReadOptions::new().max_bytes(1024))
+        .unwrap_err();
+    assert!(matches!(
+        e,
+        Error::Limit {
+            limit: "max_bytes",
+            ..
+        }
+    ));
+    let e = native_json::reader(&path)
+        .options(ReadOptions::new().max_depth(2))
+        .read::<Request>()
+        .unwrap_err();
+    assert!(matches!(
+        e,
+        Error::Limit {
+            limit: "max_depth",
+            ..
+        }
+    ));
+    let request: Request = native_json::read_with(&path, &ReadOptions::new().max_depth(3))?;
+    assert_eq!(request.tags.len(), 1000);
+
+    Ok(())
+}
+
+#[test]
+fn json_duplicate_keys() -> Pod {
+    json! { Cluster { servers: [{ name: String, port: u16 }], labels: {String: String} } }
+
+    let text = r#"{"servers": [], "labels": {"env": "dev", "env": "prod"}}"#;
+    let cluster: Cluster = native_json::parse_with(text, &ReadOptions::new())?;
+    assert_eq!(cluster.labels["env"], "prod");
+
+    let strict = ReadOptions::new().deny_duplicate_keys(true);
+    let e = native_json::parse_with::<Cluster>(text, &strict).unwrap_err();
+    assert!(matches!(e, Error::Type { .. }), "{}", e);
+    assert_eq!(e.path(), "labels.env");
+    assert!(e.to_string().contains("duplicate key"), "{}", e);
+
+    // the nested objects are checked, the same keys of the different objects are allowed
+    let text = r#"{"servers": [{"name": "a", "port": 80, "name": "b"}], "labels": {}}"#;
+    let e = native_json::parse_with::<Cluster>(text, &strict).unwrap_err();
+    assert_eq!(e.path(), "servers[0].name");
+    let text =
+        r#"{"servers": [{"name": "a", "port": 80}, {"name": "b", "port": 80}], "labels": {}}"#;
+    let cluster: Cluster = native_json::parse_with(text, &strict)?;
+    assert_eq!(cluster.servers.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn json_document_comments() -> Pod {
+    use native_json::Document;
+
+    json! { DocSettings {
+        theme: String,
+        size: u32,
+        rulers: [u32],
+        font: { family: String, bold: bool },
+        tabs: Option<u32>,
+        wrap: Option<bool>,
+    }}
+
+    let text = r#"// user settings
+{
+    // the color theme
+    "theme": "dark",
+
+    "size": 12, // points
+    "rulers": [80, 120],
+    "font": {
+        "family": "Fira Code", /* monospace */
+        "bold": false
+    },
+    "tabs": 4
+}
+"#;
+    let mut doc = Document::parse(text)?;
+    let mut settings: DocSettings = doc.get()?;
+    assert_eq!(settings.tabs, Some(4));
+
+    // unchanged values keep the text
+    doc.set(&settings)?;
+    assert_eq!(doc.as_str(), text);
+
+    settings.size = 14;
+    settings.rulers = vec![100];
+    settings.font.bold = true;
+    settings.tabs = None;
+    settings.wrap = Some(true);
+    doc.set(&settings)?;
+    let expected = r#"// user settings
+{
+    // the color theme
+    "theme": "dark",
+
+    "size": 14, // points
+    "rulers": [100],
+    "font": {
+        "family": "Fira Code", /* monospace */
+        "bold": true
+    },
+    "tabs": null,
+    "wrap": true
+}
+"#;
+    assert_eq!(doc.to_string(), expected);
+    let read: DocSettings = doc.get()?;
+    assert_eq!(read.wrap, Some(true));
+    assert_eq!(read.tabs, None);
+
+    // the new keys of inline objects stay inline
+    let mut doc = Document::parse(r#"{"a": 1, "b": {"c": 2}}"#)?;
+    doc.set(&serde_json::json!({"a": 1, "b": {"c": 2, "d": [1, 2]}}))?;
+    assert_eq!(doc.as_str(), r#"{"a": 1, "b": {"c": 2, "d": [1,2]}}"#);
+    doc.set(&serde_json::json!({"b": {"d": []}}))?;
+    assert_eq!(doc.as_str(), r#"{"b": {"d": []}}"#);
+
+    // removed members take their line and comma
+    let text = "{\n  \"a\": 1,\n  \"b\": 2, // two\n  \"c\": 3\n}";
+    let mut doc = Document::parse(text)?;
+    doc.set(&serde_json::json!({"a": 1, "c": 3}))?;
+    assert_eq!(doc.as_str(), "{\n  \"a\": 1,\n  \"c\": 3\n}");
+    let mut doc = Document::parse(text)?;
+    doc.set(&serde_json::json!({"a": 1, "b": 2}))?;
+    assert_eq!(doc.as_str(), "{\n  \"a\": 1,\n  \"b\": 2 // two\n}");
+
+    Ok(())
+}
+
+#[cfg(feature = "serde_yaml")]
+#[test]
+fn json_yaml() -> Pod {
+    use native_json::Format;
+
+    json! { Deployment { name: String, replicas: u32, ports: [{ port: u16, public: bool }] } }
+
+    let dir = TempDir::new("yaml")?;
+    let path = dir.join("deployment.yaml");
+    std::fs::write(
+        &path,
+        "name: web\nreplicas: 3\nports:\n  - port: 80\n    public: true\n  - port: 9090\n    public: false\n",
+    )?;
+
+    let mut deployment: Deployment = native_json::read_yaml(&path)?;
+    assert_eq!(deployment.replicas, 3);
+    assert_eq!(deployment.ports[1].port, 9090);
+
+    deployment.replicas = 5;
+    native_json::write_yaml(&path, &deployment)?;
+    let text = std::fs::read_to_string(&path)?;
+    assert!(text.starts_with("name: web\nreplicas: 5\n"));
+    native_json::writer(&path)
+        .format(Format::Yaml)
+        .atomic(true)
+        .write(&deployment)?;
+    let read: Deployment = native_json::reader(&path).format(Format::Yaml).read()?;
+    assert_eq!(read.ports.len(), 2);
+
+    std::fs::write(&path, "name: web\nreplicas: 3\nports:\n  - port: x\n")?;
+    let e = native_json::read_yaml::<Deployment, _>(&path).unwrap_err();
+    assert!(
+        e.to_string().starts_with("ports[0].port: invalid type"),
+        "{}",
+        e
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn json_toml() -> Pod {
+    use native_json::Format;
+
+    json! { Manifest { package: { name: String, version: String }, features: [String], released: String } }
+
+    let dir = TempDir::new("toml")?;
+    let path = dir.join("manifest.toml");
+    let text = "features = [\"a\"]\nreleased = \"2024-01-01\"\n\n[package]\nname = \"app\"\nversion = \"1.0.0\"\n";
+    std::fs::write(&path, text)?;
+
+    let mut manifest: Manifest = native_json::read_toml(&path)?;
+    assert_eq!(manifest.package.name, "app");
+    assert_eq!(manifest.features, ["a"]);
+
+    manifest.package.version = "1.1.0".to_owned();
+    native_json::write_toml(&path, &manifest)?;
+    assert!(std::fs::read_to_string(&path)?.contains("version = \"1.1.0\""));
+    let read: Manifest = native_json::reader(&path).format(Format::Toml).read()?;
+    assert_eq!(read.package.version, "1.1.0");
+
+    // errors of the TOML features which do not map to the declaration
+    let e = native_json::toml::from_str::<Manifest>("released = 1979-05-27\n").unwrap_err();
+    assert_eq!(
+        e.to_string(),
+        "released: TOML datetime `1979-05-27` is not a string, quote it at line 1 column 12"
+    );
+    let e = native_json::toml::from_str::<Manifest>("features = [1]\n").unwrap_err();
+    assert!(
+        e.to_string()
+            .starts_with("features[0]: invalid type: integer"),
+        "{}",
+        e
+    );
+    let e = native_json::toml::to_string(&vec![Some(1), None]).unwrap_err();
+    assert!(e.to_string().contains("TOML"), "{}", e);
+
+    Ok(())
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn json_msgpack() -> Pod {
+    use native_json::FromJSON;
+
+    json! { Packet { id: u32, name: String, payload: [u8], tags: [String], extra: Option<f64> } }
+
+    let packet = Packet {
+        id: 7,
+        name: "ping".to_owned(),
+        payload: vec![1, 2, 3],
+        tags: vec!["a".to_owned()],
+        extra: None,
+    };
+    let bytes = packet.to_msgpack()?;
+    assert!(bytes.len() < packet.to_bytes()?.len());
+    let decoded = Packet::from_msgpack(&bytes)?;
+    assert_eq!(decoded.string()?, packet.string()?);
+
+    // the JSON keys, so generic values decode too
+    let value = serde_json::Value::from_msgpack(&bytes)?;
+    assert_eq!(value["name"], "ping");
+
+    let wrong = serde_json::json!({"id": "x"}).to_msgpack()?;
+    let e = Packet::from_msgpack(&wrong).unwrap_err();
+    assert!(e.to_string().starts_with("id: "), "{}", e);
+    Ok(())
+}
+
+#[cfg(feature = "cbor")]
+#[test]
+fn json_cbor() -> Pod {
+    use native_json::FromJSON;
+
+    json! { Reading { sensor: String, values: [f64], battery: u8, calibrated: bool } }
+
+    let reading = Reading {
+        sensor: "t1".to_owned(),
+        values: vec![21.5, 21.75],
+        battery: 97,
+        calibrated: true,
+    };
+    let bytes = reading.to_cbor()?;
+    let decoded = Reading::from_cbor(&bytes)?;
+    assert_eq!(decoded.string()?, reading.string()?);
+    let value = serde_json::Value::from_cbor(&bytes)?;
+    assert_eq!(value["battery"], 97);
+
+    let wrong = serde_json::json!({"sensor": "t1", "values": [1.0, "x"]}).to_cbor()?;
+    let e = Reading::from_cbor(&wrong).unwrap_err();
+    assert!(e.to_string().starts_with("values[1]: "), "{}", e);
+    assert!(Reading::from_cbor(&bytes[..3]).is_err());
+    Ok(())
+}
+
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+#[test]
+fn json_compressed() -> Pod {
+    use native_json::Compression;
+
+    json! { Snapshot { id: u32, samples: [f64] } }
+
+    let dir = TempDir::new("compress")?;
+    let snapshot = Snapshot {
+        id: 1,
+        samples: vec![0.5; 1000],
+    };
+    let plain = dir.join("snapshot.json");
+    native_json::write(&plain, &snapshot)?;
+    let size = std::fs::metadata(&plain)?.len();
+
+    let names = [
+        #[cfg(feature = "gzip")]
+        "snapshot.json.gz",
+        #[cfg(feature = "zstd")]
+        "snapshot.json.zst",
+    ];
+    for name in names {
+        let path = dir.join(name);
+        // by the extension
+        native_json::write(&path, &snapshot)?;
+        assert!(std::fs::metadata(&path)?.len() < size / 10);
+        let read: Snapshot = native_json::read(&path)?;
+        assert_eq!(read.samples.len(), 1000);
+
+        // update writes back by the extension
+        native_json::update(&path, |s: &mut Snapshot| s.id = 2)?;
+        assert!(std::fs::metadata(&path)?.len() < size / 10);
+        let read: Snapshot = native_json::read(&path)?;
+        assert_eq!(read.id, 2);
+
+        // by the magic bytes, appended lines are concatenated members or frames
+        let log = dir.join(format!("{}.log", name));
+        for id in 0..3 {
+            let compression = Compression::from_path(&path);
+            let writer = native_json::writer(&log).compress(compression).append(true);
+            writer.write(&Snapshot {
+                id,
+                samples: vec![],
+            })?;
+        }
+        let mut text = String::new();
+        let file = std::fs::File::open(&log)?;
+        std::io::Read::read_to_string(&mut native_json::compress::decompress(file)?, &mut text)?;
+        assert_eq!(text.lines().count(), 3);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn json_lines() -> Pod {
+    use native_json::lines::{Flush, LinesReader, LinesWriter};
+    use std::io::Write;
+
+    json! { LogEvent { seq: u32, message: String } }
+
+    let dir = TempDir::new("lines")?;
+    let path = dir.join("events.jsonl");
+
+    let mut log = LinesWriter::create(&path)?.flush_policy(Flush::EveryLine);
+    for seq in 0..3 {
+        let message = format!("event\n{}", seq);
+        log.write(&LogEvent { seq, message })?;
+        // visible before the writer is dropped
+        assert_eq!(
+            std::fs::read_to_string(&path)?.lines().count(),
+            seq as usize + 1
+        );
+    }
+    drop(log);
+
+    // a partial line of a crash and a blank line
+    let mut file = std::fs::OpenOptions::new().append(true).open(&path)?;
+    file.write_all(b"\n{\"seq\": 3, \"mess\n")?;
+    let mut log = LinesWriter::append(&path)?;
+    log.write(&LogEvent {
+        seq: 4,
+        message: "last".to_owned(),
+    })?;
+    drop(log);
+
+    let events: Vec<LogEvent> = LinesReader::open(&path)?
+        .take(3)
+        .collect::<Result<_, _>>()?;
+    assert_eq!(events[2].message, "event\n2");
+    let e = LinesReader::<LogEvent>::open(&path)?
+        .nth(3)
+        .unwrap()
+        .unwrap_err();
+    assert!(matches!(e, Error::Syntax { line: 5, .. }), "{}", e);
+    assert!(e.to_string().contains("at line 5 column"), "{}", e);
+
+    let mut reader = LinesReader::<LogEvent>::open(&path)?.skip_corrupt(true);
+    let seqs: Vec<_> = reader
+        .by_ref()
+        .map(|e| e.map(|e| e.seq))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(seqs, [0, 1, 2, 4]);
+    assert_eq!(reader.skipped(), 1);
+
+    Ok(())
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn json_lines_parallel() -> Pod {
+    use native_json::lines::{self, LinesWriter, ParLines};
+
+    json! { LogEvent { seq: u32, message: String } }
+
+    let dir = TempDir::new("par")?;
+    let path = dir.join("events.jsonl");
+    let mut log = LinesWriter::create(&path)?;
+    for seq in 0..1000 {
+        let message = format!("event {}", seq);
+        log.write(&LogEvent { seq, message })?;
+    }
+    drop(log);
+
+    // the order of the lines is kept across the chunks
+    let events: Vec<LogEvent> = lines::par_read(&path)?.collect::<Result<_, _>>()?;
+    assert_eq!(events.len(), 1000);
+    assert!(events.iter().enumerate().all(|(i, e)| e.seq == i as u32));
+    let reader = ParLines::<LogEvent, _>::new(std::io::BufReader::new(std::fs::File::open(&path)?));
+    let seqs: Vec<u32> = reader
+        .chunk_size(100)
+        .map(|e| e.map(|e| e.seq))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(seqs, (0..1000).collect::<Vec<_>>());
+
+    // the errors report the line numbers, blank lines are skipped
+    let text = "{\"seq\": 1, \"message\": \"a\"}\n\n{\"seq\": \"x\"}\n";
+    let results: Vec<_> = ParLines::<LogEvent, _>::new(text.as_bytes()).collect();
+    assert_eq!(results.len(), 2);
+    let e = results[1].as_ref().unwrap_err();
+    assert!(matches!(e, Error::Type { line: 3, .. }), "{}", e);
+    assert_eq!(e.path(), "seq");
+
+    Ok(())
+}
+
+#[test]
+fn json_stream_array() -> Pod {
+    use native_json::ArrayReader;
+
+    json! { Item { id: u32, name: String, tags: [String] } }
+
+    let dir = TempDir::new("stream")?;
+    let path = dir.join("items.json");
+    std::fs::write(
+        &path,
+        r#" [ {"id": 1, "name": "a]}\"", "tags": ["x", "[y"]},
+            {"id": 2, "name": "b", "tags": []} ,{"id":3,"name":"c","tags":["z"]} ] "#,
+    )?;
+    let items: Vec<Item> = native_json::stream_array(&path)?.collect::<Result<_, _>>()?;
+    assert_eq!(items.len(), 3);
+    assert_eq!(items[0].name, "a]}\"");
+    assert_eq!(items[0].tags, ["x", "[y"]);
+    assert_eq!(items[2].id, 3);
+
+    let ids: Vec<u32> = ArrayReader::new(&b"[1, 2 ,3]"[..]).collect::<Result<_, _>>()?;
+    assert_eq!(ids, [1, 2, 3]);
+    assert_eq!(ArrayReader::<u32, _>::new(&b" [ ] "[..]).count(), 0);
+
+    // a mismatched element is reported with its index, the next ones are parsed
+    let reader = ArrayReader::<Item, _>::new(&br#"[{"id": 1, "name": "a", "tags": []}, {"id": "x"}, {"id": 3, "name": "c", "tags": []}]"#[..]);
+    let results: Vec<_> = reader.collect();
+    assert_eq!(results.len(), 3);
+    assert!(results[1]
+        .as_ref()
+        .unwrap_err()
+        .to_string()
+        .starts_with("[1].id: "));
+    assert_eq!(results[2].as_ref().unwrap().id, 3);
+
+    // malformed JSON ends the iteration
+    let results: Vec<_> = ArrayReader::<u32, _>::new(&b"[1, 2"[..]).collect();
+    assert_eq!(results.len(), 2);
+    assert!(results[1].is_err());
+    assert!(ArrayReader::<u32, _>::new(&b"{}"[..])
+        .next()
+        .unwrap()
+        .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn json_array_writer() -> Pod {
+    use native_json::ArrayWriter;
+
+    json! { Row { id: u32, tags: [String] } }
+
+    let rows = (0..3).map(|id| Row {
+        id,
+        tags: vec![format!("t{}", id)],
+    });
+    let mut writer = ArrayWriter::new(Vec::new());
+    writer.write_all(rows.clone())?;
+    assert_eq!(writer.count(), 3);
+    let bytes = writer.finish()?;
+    let parsed: Vec<Row> = native_json::parse(std::str::from_utf8(&bytes)?)?;
+    assert_eq!(parsed.len(), 3);
+    assert_eq!(
+        bytes,
+        serde_json::to_vec(&rows.clone().collect::<Vec<_>>())?
+    );
+
+    let mut writer = ArrayWriter::<Row, _>::new(Vec::new()).pretty(true);
+    let all: Vec<Row> = rows.clone().collect();
+    writer.write_all(all.iter())?;
+    let text = String::from_utf8(writer.finish()?)?;
+    assert!(text.starts_with("[\n  {\n    \"id\": 0,"), "{}", text);
+    assert!(text.ends_with("  }\n]\n"), "{}", text);
+    let parsed: Vec<Row> = native_json::parse(&text)?;
+    assert_eq!(parsed[2].tags, ["t2"]);
+
+    let empty = ArrayWriter::<Row, _>::new(Vec::new()).finish()?;
+    assert_eq!(empty, b"[]");
+
+    // the array is closed on drop, it streams back element by element
+    let dir = TempDir::new("array")?;
+    let path = dir.join("rows.json");
+    {
+        let mut writer = ArrayWriter::create(&path)?.pretty(true);
+        for row in rows {
+            writer.write(&row)?;
+        }
+    }
+    let ids: Vec<u32> = native_json::stream_array::<Row, _>(&path)?
+        .map(|row| row.map(|row| row.id))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(ids, [0, 1, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn json_parse_many() -> Pod {
+    json! { Event { kind: String, seq: u32 } }
+
+    let text = r#"{"kind": "added", "seq": 1}{"kind": "modified", "seq": 2}
+  {"kind": "deleted", "seq": 3}
+"#;
+    let events: Vec<(usize, Event)> = parse_many(text).collect::<Result<_, _>>()?;
+    let offsets: Vec<usize> = events.iter().map(|(offset, _)| *offset).collect();
+    assert_eq!(offsets, [0, 27, 60]);
+    assert!(text[27..].starts_with(r#"{"kind": "modified""#));
+    assert!(text[60..].starts_with(r#"{"kind": "deleted""#));
+    assert_eq!(events[2].1.seq, 3);
+    assert_eq!(parse_many::<u32>("1 2\n3").count(), 3);
+    assert_eq!(parse_many::<Event>(" \n ").count(), 0);
+
+    // the errors report the position in the whole buffer
+    let results: Vec<_> =
+        parse_many::<Event>("{\"kind\": \"a\", \"seq\": 1}\n{\"kind\": \"b\", \"seq\": \"x\"}\n{}")
+            .collect();
+    assert_eq!(results.len(), 2);
+    let e = results[1].as_ref().unwrap_err();
+    assert_eq!(e.path(), "seq");
+    assert_eq!(e.line(), 2);
+    let results: Vec<_> = parse_many::<u32>("1 [").collect();
+    assert!(matches!(results[1], Err(Error::Syntax { .. })));
+
+    let mut stream = native_json::stream::from_reader::<_, u32>(&b"1 22 333"[..]);
+    assert_eq!(stream.next().transpose()?, Some(1));
+    assert_eq!(stream.next().transpose()?, Some(22));
+    assert_eq!(stream.byte_offset(), 4);
+
+    // parse is strict, parse_prefix returns the remaining text
+    let text = "{\"kind\": \"added\", \"seq\": 1}\n--\nbody";
+    let e = native_json::parse::<Event>(text).unwrap_err();
+    assert!(matches!(e, Error::Syntax { .. }), "{}", e);
+    assert_eq!(e.line(), 2);
+    let (event, rest): (Event, &str) = native_json::parse_prefix(text)?;
+    assert_eq!(event.seq, 1);
+    assert_eq!(rest, "\n--\nbody");
+    let (n, rest): (u32, &str) = native_json::parse_prefix(" 12 ")?;
+    assert_eq!((n, rest), (12, " "));
+    let e =
+        native_json::parse_prefix::<Event>("{\"kind\": \"a\", \"seq\": \"x\"} tail").unwrap_err();
+    assert_eq!(e.path(), "seq");
+    assert!(native_json::parse_prefix::<u32>("").is_err());
+    Ok(())
+}
+
+#[cfg(feature = "simd")]
+#[test]
+fn json_simd() -> Pod {
+    json! { Course { name: String, tags: [String], students: [{ name: String, age: u16 }] } }
+
+    let text =
+        r#"{"name": "math \"101\"", "tags": ["a", "b"], "students": [{"name": "Tom", "age": 18}]}"#;
+    let course: Course = native_json::simd::parse(text.to_owned())?;
+    assert_eq!(course.name, "math \"101\"");
+    assert_eq!(course.students[0].age, 18);
+
+    let mut bytes = text.as_bytes().to_vec();
+    let value: Value = native_json::simd::from_slice(&mut bytes)?;
+    assert_eq!(value["tags"][1], "b");
+
+    // the errors report the path
+    let text = r#"{"name": "x", "tags": [], "students": [{"name": "Tom", "age": "x"}]}"#;
+    let e = native_json::simd::parse::<Course>(text.to_owned()).unwrap_err();
+    assert!(matches!(e, Error::Type { .. }));
+    assert_eq!(e.path(), "students[0].age");
+    let e = native_json::simd::parse::<Course>("{\"name\": ".to_owned()).unwrap_err();
+    assert!(matches!(e, Error::Syntax { .. }));
+
+    // serde_json reports the line and column of the file
+    let dir = TempDir::new("simd")?;
+    let path = dir.join("simd.json");
+    std::fs::write(&path, "{\n  \"name\": 1\n}")?;
+    let e = native_json::read::<Course, _>(&path).unwrap_err();
+    assert_eq!(e.path(), "name");
+    assert!(e.line() > 0);
+    native_json::write(&path, &course)?;
+    let read: Course = native_json::read(&path)?;
+    assert_eq!(read.tags, ["a", "b"]);
+    Ok(())
+}
+
+#[test]
+fn json_lines_tail() -> Pod {
+    use native_json::lines::tail;
+    use std::io::Write;
+
+    json! { Record { seq: u32 } }
+
+    let dir = TempDir::new("tail")?;
+    let path = dir.join("records.jsonl");
+    let append = |text: &str| -> std::io::Result<()> {
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        file.write_all(text.as_bytes())
+    };
+
+    // the file may not exist yet, the existing records are skipped
+    let mut records = tail::<Record, _>(&path).interval(std::time::Duration::from_millis(10));
+    assert!(records.poll().is_none());
+    append("{\"seq\": 0}\n")?;
+    assert!(records.poll().is_none());
+    append("{\"seq\": 1}\n\n{\"seq\": 2}\n{\"se")?;
+    assert_eq!(records.next().transpose()?.map(|r| r.seq), Some(1));
+    assert_eq!(records.poll().transpose()?.map(|r| r.seq), Some(2));
+    assert!(records.poll().is_none());
+    append("q\": 3}\n")?;
+    assert_eq!(records.poll().transpose()?.map(|r| r.seq), Some(3));
+
+    // truncated
+    std::fs::write(&path, "{\"seq\": 4}\n")?;
+    assert_eq!(records.poll().transpose()?.map(|r| r.seq), Some(4));
+    assert!(records.poll().is_none());
+
+    // rotated
+    std::fs::rename(&path, dir.join("records.jsonl.1"))?;
+    assert!(records.poll().is_none());
+    append("{\"seq\": 5}\n")?;
+    assert_eq!(records.poll().transpose()?.map(|r| r.seq), Some(5));
+    append("oops\n")?;
+    assert!(records.poll().unwrap().is_err());
+
+    let seqs: Vec<u32> = tail::<Record, _>(&path)
+        .from_start(true)
+        .take(1)
+        .map(|r| r.map(|r| r.seq))
+        .collect::<Result<_, _>>()?;
+    assert_eq!(seqs, [5]);
+
+    Ok(())
+}
+
+// a server of canned responses, `/echo` returns the request body
+#[cfg(feature = "http")]
+fn serve(requests: usize) -> std::io::Result<String> {
+    use std::io::{BufRead, BufReader, Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}", listener.local_addr()?);
+    std::thread::spawn(move || {
+        for stream in listener.incoming().take(requests) {
+            let mut stream = BufReader::new(stream.unwrap());
+            let (mut line, mut length, mut auth) = (String::new(), 0, false);
+            stream.read_line(&mut line).unwrap();
+            let path = line.split(' ').nth(1).unwrap_or_default().to_owned();
+            loop {
+                line.clear();
+                stream.read_line(&mut line).unwrap();
+                let lower = line.to_ascii_lowercase();
+                if let Some(n) = lower.strip_prefix("content-length:") {
+                    length = n.trim().parse().unwrap();
+                }
+                auth |= lower.starts_with("authorization: token");
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut body = vec![0; length];
+            stream.read_exact(&mut body).unwrap();
+            let (status, body) = match path.as_str() {
+                "/user" => ("200 OK", r#"{"id": 7, "name": "ann"}"#.to_owned()),
+                "/echo" => ("200 OK", String::from_utf8(body).unwrap()),
+                "/empty" => ("204 No Content", String::new()),
+                "/private" if auth => ("200 OK", r#"{"id": 1, "name": "root"}"#.to_owned()),
+                "/private" => ("401 Unauthorized", r#"{"error": "token"}"#.to_owned()),
+                _ => ("404 Not Found", "no such user".to_owned()),
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.get_mut().write_all(response.as_bytes()).unwrap();
+        }
+    });
+    Ok(url)
+}
+
+#[cfg(feature = "http")]
+#[test]
+fn json_http() -> Pod {
+    json! { User { id: u32, name: String } }
+
+    let url = serve(7)?;
+    let user: User = native_json::get(&format!("{}/user", url))?;
+    assert_eq!((user.id, user.name.as_str()), (7, "ann"));
+    let echoed: User = native_json::post(&format!("{}/echo", url), &user)?;
+    assert_eq!(echoed.name, "ann");
+    native_json::get::<()>(&format!("{}/empty", url))?;
+
+    match native_json::get::<User>(&format!("{}/missing", url)) {
+        Err(Error::Status { status, body }) => {
+            assert_eq!((status, body.as_str()), (404, "no such user"))
+        }
+        other => panic!("{:?}", other.map(|u| u.id)),
+    }
+    let e = native_json::post::<User, _>(&format!("{}/echo", url), &json! { id: 1, name: 2 })
+        .unwrap_err();
+    assert!(e.to_string().starts_with("name: invalid type"), "{}", e);
+
+    let options = HttpOptions::new()
+        .header("Authorization", "token")
+        .error_body(3);
+    let root: User = native_json::http::get_with(&format!("{}/private", url), &options)?;
+    assert_eq!(root.name, "root");
+    let e = native_json::http::get_with::<User>(
+        &format!("{}/private", url),
+        &HttpOptions::new().error_body(3),
+    )
+    .unwrap_err();
+    assert_eq!(e.to_string(), "HTTP status 401: {\"e");
+    Ok(())
+}
+
+#[cfg(all(feature = "http", feature = "tokio"))]
+#[tokio::test]
+async fn json_http_async() -> Pod {
+    json! { User { id: u32, name: String } }
+
+    let url = serve(2)?;
+    let user: User = native_json::http::get_async(&format!("{}/user", url)).await?;
+    let echoed: User = native_json::http::post_async(&format!("{}/echo", url), &user).await?;
+    assert_eq!(echoed.id, 7);
+    Ok(())
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn json_axum() -> Pod {
+    use ::axum::body::{to_bytes, Body};
+    use ::axum::extract::FromRequest;
+    use ::axum::http::{Request, StatusCode};
+    use ::axum::response::IntoResponse;
+    use native_json::axum::Json;
+
+    json! { School { name: String, students: [{ name: String, age: u16 }] } }
+
+    let post = |content_type: &str, body: &'static str| {
+        Request::builder()
+            .header("content-type", content_type)
+            .body(Body::from(body))
+            .unwrap()
+    };
+    let reject = |rejection: native_json::axum::Rejection| async move {
+        let response = rejection.into_response();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (
+            status,
+            serde_json::from_slice::<serde_json::Value>(&body).unwrap(),
+        )
+    };
+
+    let body = r#"{"name": "MIT", "students": [{"name": "ann", "age": 18}]}"#;
+    let Json(school) = Json::<School>::from_request(post("application/json", body), &()).await?;
+    assert_eq!(school.students[0].age, 18);
+
+    // the response
+    let response = Json(school).into_response();
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body = to_bytes(response.into_body(), usize::MAX).await?;
+    assert_eq!(
+        body,
+        r#"{"name":"MIT","students":[{"name":"ann","age":18}]}"#
+    );
+
+    // field path and expected type
+    let body = r#"{"name": "MIT", "students": [{"name": "ann", "age": "x"}]}"#;
+    let request = post("application/json; charset=utf-8", body);
+    let e = Json::<School>::from_request(request, &())
+        .await
+        .unwrap_err();
+    let (status, body) = reject(e).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["path"], "students[0].age");
+    assert_eq!(body["expected"], "u16");
+    assert_eq!(body["line"], 1);
+
+    let request = Request::builder().body(Body::from("{ bad")).unwrap();
+    let e = Json::<School>::from_request(request, &())
+        .await
+        .unwrap_err();
+    assert_eq!(reject(e).await.0, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let e = Json::<School>::from_request(post("application/problem+json", "{ bad"), &())
+        .await
+        .unwrap_err();
+    let (status, body) = reject(e).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.get("path").is_none());
+
+    // the server errors are not disclosed
+    let e = native_json::read::<School, _>("/nonexistent/school.json").unwrap_err();
+    let (status, body) = reject(e.into()).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(body, serde_json::json!({"error": "Internal Server Error"}));
+    Ok(())
+}
+
+// the expirations of the keys in milliseconds
+#[cfg(feature = "redis")]
+type Expires = std::sync::Arc<std::sync::Mutex<HashMap<String, u64>>>;
+
+// a RESP server of SET and GET, the expirations are recorded
+#[cfg(feature = "redis")]
+fn serve_redis() -> std::io::Result<(String, Expires)> {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::sync::{Arc, Mutex};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("redis://{}/", listener.local_addr()?);
+    let expires = Arc::new(Mutex::new(HashMap::new()));
+    let ttl = expires.clone();
+    std::thread::spawn(move || {
+        let mut values: HashMap<Vec<u8>, Vec<u8>> = HashMap::new();
+        let stream = listener.incoming().next().unwrap().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        let mut line = String::new();
+        while reader.read_line(&mut line).unwrap_or(0) > 0 {
+            let n: usize = line.trim()[1..].parse().unwrap();
+            let mut args = Vec::new();
+            for _ in 0..n {
+                line.clear();
+                reader.read_line(&mut line).unwrap();
+                let mut arg = vec![0; line.trim()[1..].parse::<usize>().unwrap() + 2];
+                reader.read_exact(&mut arg).unwrap();
+                arg.truncate(arg.len() - 2);
+                args.push(arg);
+            }
+            line.clear();
+            let reply = match args[0].as_slice() {
+                b"SET" => {
+                    let key = String::from_utf8_lossy(&args[1]).into_owned();
+                    if args.len() == 5 {
+                        let ms = String::from_utf8_lossy(&args[4]).parse().unwrap();
+                        ttl.lock().unwrap().insert(key, ms);
+                    }
+                    values.insert(args[1].clone(), args[2].clone());
+                    b"+OK\r\n".to_vec()
+                }
+                b"GET" => match values.get(&args[1]) {
+                    Some(value) => {
+                        let mut reply = format!("${}\r\n", value.len()).into_bytes();
+                        reply.extend_from_slice(value);
+                        reply.extend_from_slice(b"\r\n");
+                        reply
+                    }
+                    None => b"$-1\r\n".to_vec(),
+                },
+                _ => b"+OK\r\n".to_vec(),
+            };
+            writer.write_all(&reply).unwrap();
+        }
+    });
+    Ok((url, expires))
+}
+
+#[cfg(feature = "redis")]
+#[test]
+fn json_redis() -> Pod {
+    use native_json::redis::RedisJson;
+    use std::time::Duration;
+
+    json! { CachedUser { id: u32, name: String, tags: [String] } }
+
+    let (url, expires) = serve_redis()?;
+    let mut cache = ::redis::Client::open(url)?.get_connection()?;
+    let mut user = CachedUser::new();
+    user.id = 7;
+    user.name = "ann".to_owned();
+    cache.set_json("user:7", &user, Some(Duration::from_secs(60)))?;
+    let cached: Option<CachedUser> = cache.get_json("user:7")?;
+    assert_eq!(cached.unwrap().name, "ann");
+    assert_eq!(expires.lock().unwrap()["user:7"], 60000);
+    assert!(cache.get_json::<CachedUser>("user:8")?.is_none());
+
+    // the stored JSON, the type errors report the path
+    cache.set_json("user:9", &serde_json::json!({"id": "x"}), None)?;
+    let e = cache.get_json::<CachedUser>("user:9").unwrap_err();
+    assert_eq!(e.path(), "id");
+    assert!(!expires.lock().unwrap().contains_key("user:9"));
+
+    #[cfg(feature = "zstd")]
+    {
+        user.tags = vec!["admin".to_owned(); 1000];
+        cache.set_json_compressed("user:7", &user, None, Compression::Zstd(3))?;
+        let cached: CachedUser = cache.get_json("user:7")?.unwrap();
+        assert_eq!(cached.tags.len(), 1000);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "sqlx")]
+#[test]
+fn json_sqlx() -> Pod {
+    use ::sqlx::postgres::{PgArgumentBuffer, Postgres};
+    use ::sqlx::{Encode, Type, TypeInfo};
+    use native_json::sqlx::Json;
+
+    json! { Row { id: u32, tags: [String] } }
+
+    assert_eq!(<Json<Row> as Type<Postgres>>::type_info().name(), "JSONB");
+    let mut row = Json(Row::new());
+    row.tags.push("a".to_owned());
+    let mut buf = PgArgumentBuffer::default();
+    let null = Encode::<Postgres>::encode_by_ref(&row, &mut buf).map_err(|e| anyhow::anyhow!(e))?;
+    assert!(!null.is_null());
+    assert_eq!(&buf[..], b"\x01{\"id\":0,\"tags\":[\"a\"]}");
+    Ok(())
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn json_csv() -> Pod {
+    use native_json::csv::{self, CsvOptions};
+
+    json! { Row { name: String, age: u16, score: f64?, tutor: Option<String>, active: bool } }
+
+    let mut rows = vec![Row::new(), Row::new()];
+    rows[0].name = "Tom, Jr.".to_owned();
+    rows[0].age = 18;
+    rows[1].name = "Jack".to_owned();
+    rows[1].score = 3.5;
+    rows[1].tutor = Some("Ann".to_owned());
+    rows[1].active = true;
+
+    let mut buffer = Vec::new();
+    csv::to_csv(&mut buffer, &rows)?;
+    let text = String::from_utf8(buffer)?;
+    assert_eq!(
+        text,
+        "name,age,tutor,active,score\n\"Tom, Jr.\",18,,false,\nJack,0,Ann,true,3.5\n"
+    );
+    let back: Vec<Row> = csv::from_csv(text.as_bytes())?;
+    assert_eq!(back.string()?, rows.string()?);
+
+    // one level of nesting is flattened into dotted columns
+    json! { Phone { number: String } }
+    json! { Person { name: String, address: { city: String, zip: u32? }, phone: Option<Phone> } }
+    let mut person = Person::new();
+    person.name = "Tom".to_owned();
+    person.address.city = "Boston".to_owned();
+    let options = CsvOptions::new().flatten(true).delimiter(b';');
+    let mut buffer = Vec::new();
+    csv::to_csv_with(&mut buffer, std::slice::from_ref(&person), &options)?;
+    let text = String::from_utf8(buffer)?;
+    assert_eq!(text, "name;address.city;phone\nTom;Boston;\n");
+    let back: Vec<Person> = csv::from_csv_with(text.as_bytes(), &options)?;
+    assert_eq!(back[0].string()?, person.string()?);
+    let back: Vec<Person> = csv::from_csv_with(
+        "name;address.city;phone.number\nTom;NY;\nJack;LA;555\n".as_bytes(),
+        &options,
+    )?;
+    assert!(back[0].phone.is_none());
+    assert_eq!(back[1].phone.as_ref().unwrap().number, "555");
+
+    // the errors report the field path and the line
+    let e = csv::from_csv::<_, Row>("name,age,active\nTom,18,true\nJack,x,false\n".as_bytes())
+        .unwrap_err();
+    assert_eq!((e.path(), e.line()), ("age", 3));
+    assert_eq!(
+        e.to_string(),
+        "age: invalid value: string \"x\", expected u16"
+    );
+    let e = csv::to_csv(Vec::new(), &[person]).unwrap_err();
+    assert_eq!(e.to_string(), "[0].address: expected a scalar value");
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn json_arrow() -> Pod {
+    use ::arrow::datatypes::DataType;
+    use native_json::arrow;
+
+    json! { Student { name: String, age: u16, score: Option<f64>, tags: [String], address: { city: String } } }
+
+    let schema = arrow::schema::<Student>()?;
+    let columns: Vec<_> = schema.fields().iter().map(|x| x.name().as_str()).collect();
+    assert_eq!(columns, ["name", "age", "score", "tags", "address"]);
+    assert_eq!(schema.field(1).data_type(), &DataType::UInt16);
+    assert!(schema.field(2).is_nullable());
+    assert!(matches!(
+        schema.field(3).data_type(),
+        DataType::LargeList(_)
+    ));
+    assert!(matches!(schema.field(4).data_type(), DataType::Struct(_)));
+
+    let mut students = Vec::new();
+    for (name, age) in [("Tom", 18), ("Jack", 21)] {
+        let mut student = Student::new();
+        student.name = name.to_owned();
+        student.age = age;
+        student.address.city = "Boston".to_owned();
+        students.push(student);
+    }
+    students[1].score = Some(3.5);
+    students[1].tags.push("honor".to_owned());
+
+    let batch = arrow::to_record_batch(&students)?;
+    assert_eq!((batch.num_rows(), batch.num_columns()), (2, 5));
+    let rows: Vec<Student> = arrow::from_record_batch(&batch)?;
+    assert_eq!(rows.string()?, students.string()?);
+
+    #[cfg(feature = "parquet")]
+    {
+        let dir = TempDir::new("arrow")?;
+        let path = dir.join("students.parquet");
+        arrow::write_parquet(std::fs::File::create(&path)?, &students)?;
+        let rows: Vec<Student> = arrow::read_parquet(std::fs::File::open(&path)?)?;
+        assert_eq!(rows.string()?, students.string()?);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "python")]
+#[test]
+fn json_python() -> Pod {
+    use native_json::python::PyJson;
+    use pyo3::prelude::*;
+    use pyo3::types::PyDict;
+
+    json! { PySchool { name: String, rank: u64, score: f64?, students: [{ name: String, age: u16 }] } }
+
+    pyo3::prepare_freethreaded_python();
+    Python::with_gil(|py| -> Pod {
+        let mut school = PySchool::new();
+        school.name = "MIT".to_owned();
+        school.rank = u64::MAX;
+        let mut student = PySchool_students_item::new();
+        student.age = 18;
+        school.students.push(student);
+
+        let object = school.to_py(py)?;
+        let dict = object.downcast::<PyDict>().unwrap();
+        assert_eq!(dict.get_item("name")?.unwrap().extract::<String>()?, "MIT");
+        assert!(dict.get_item("score")?.is_none());
+        let students = dict.get_item("students")?.unwrap();
+        assert_eq!(students.get_item(0)?.get_item("age")?.extract::<u16>()?, 18);
+
+        // back from Python, tuples are arrays
+        students.get_item(0)?.set_item("age", 19)?;
+        dict.set_item("score", 1.5)?;
+        let school = PySchool::from_py(&object)?;
+        assert_eq!((school.rank, school.score), (u64::MAX, 1.5));
+        assert_eq!(school.students[0].age, 19);
+        let PyJson(pair): PyJson<(bool, Option<i32>)> =
+            (true, py.None()).into_pyobject(py)?.extract()?;
+        assert_eq!(pair, (true, None));
+        let object = PyJson(&school).into_pyobject(py)?;
+        assert_eq!(object.get_item("rank")?.extract::<u64>()?, u64::MAX);
+
+        // the errors report the path
+        students.get_item(0)?.set_item("age", "x")?;
+        let e = PySchool::from_py(dict.as_any()).unwrap_err();
+        assert_eq!(e.path(), "students[0].age");
+        dict.set_item("name", pyo3::types::PySet::empty(py)?)?;
+        let e = PySchool::from_py(dict.as_any()).unwrap_err();
+        assert!(e.to_string().starts_with("name: unsupported type"), "{}", e);
+        let e: PyErr = e.into();
+        assert!(e.is_instance_of::<pyo3::exceptions::PyValueError>(py));
+        Ok(())
+    })
+}
+
+#[test]
+fn json_rpc() -> Pod {
+    use native_json::rpc::{self, Method};
+
+    json! { rpc subtract { minuend: i32, subtrahend: i32 } -> i32 }
+    json! { rpc get_user { id: u32 } -> { id: u32, name: String, roles: [String] } }
+    json! { rpc list_names -> [String] }
+    json! { rpc log { message: String } }
+
+    assert_eq!(Subtract::NAME, "subtract");
+    assert_eq!(GetUser::NAME, "get_user");
+
+    let router = rpc::Router::new()
+        .method::<Subtract, _>(|p| Ok(p.minuend - p.subtrahend))
+        .method::<GetUser, _>(|p| match p.id {
+            1 => Ok(GetUserResult {
+                id: 1,
+                name: "ann".to_owned(),
+                roles: vec!["admin".to_owned()],
+            }),
+            id => Err(rpc::Error::new(404, "no such user").data(&id)),
+        })
+        .method::<ListNames, _>(|()| Ok(vec!["ann".to_owned()]))
+        .method::<Log, _>(|_| Ok(()));
+
+    let client = rpc::Client::new();
+    let request = client.request::<Subtract>(SubtractParams {
+        minuend: 42,
+        subtrahend: 23,
+    });
+    let text = request.to_string()?;
+    assert_eq!(
+        text,
+        r#"{"jsonrpc":"2.0","method":"subtract","params":{"minuend":42,"subtrahend":23},"id":1}"#
+    );
+    let response = router.handle(&text).unwrap();
+    assert_eq!(response, r#"{"jsonrpc":"2.0","result":19,"id":1}"#);
+    assert_eq!(rpc::Client::response::<Subtract>(&response)?, 19);
+
+    // no params, and the errors of the handler
+    let text = client.request::<ListNames>(()).to_string()?;
+    assert_eq!(text, r#"{"jsonrpc":"2.0","method":"list_names","id":2}"#);
+    assert_eq!(
+        rpc::Client::response::<ListNames>(&router.handle(&text).unwrap())?,
+        ["ann"]
+    );
+    let response = router.handle(
+        &client
+            .request::<GetUser>(GetUserParams { id: 2 })
+            .to_string()?,
+    );
+    let response: rpc::Response<GetUserResult> = native_json::parse(&response.unwrap())?;
+    let e = response.into_result().unwrap_err();
+    assert_eq!((e.code, e.data), (404, Some(serde_json::json!(2))));
+
+    // batch, the notification has no response
+    let mut batch = client.batch();
+    let user = batch.request::<GetUser>(&GetUserParams { id: 1 })?;
+    let difference = batch.request::<Subtract>(&SubtractParams {
+        minuend: 1,
+        subtrahend: 2,
+    })?;
+    batch.notification::<Log>(&LogParams {
+        message: "hi".to_owned(),
+    })?;
+    assert_eq!(batch.requests().len(), 3);
+    let mut responses = rpc::Responses::parse(&router.handle(&batch.to_string()?).unwrap())?;
+    assert_eq!(responses.take::<Subtract>(&difference)?, -1);
+    assert_eq!(responses.take::<GetUser>(&user)?.roles, ["admin"]);
+    assert!(responses.take::<GetUser>(&user).is_err());
+    let log = client.notification::<Log>(LogParams {
+        message: "x".to_owned(),
+    });
+    assert!(log.is_notification());
+    assert_eq!(router.handle(&log.to_string()?), None);
+
+    // protocol errors
+    let error = |text: &str| -> Pod<i64> {
+        let response: rpc::Response = native_json::parse(&router.handle(text).unwrap())?;
+        Ok(response.into_result().unwrap_err().code)
+    };
+    assert_eq!(error("{")?, rpc::Error::PARSE_ERROR);
+    assert_eq!(error("[]")?, rpc::Error::INVALID_REQUEST);
+    assert_eq!(
+        error(r#"{"jsonrpc": "1.0", "method": "x", "id": 1}"#)?,
+        rpc::Error::INVALID_REQUEST
+    );
+    assert_eq!(
+        error(r#"{"jsonrpc": "2.0", "method": "x", "id": 1}"#)?,
+        rpc::Error::METHOD_NOT_FOUND
+    );
+    let text = r#"{"jsonrpc": "2.0", "method": "subtract", "params": {"minuend": "x"}, "id": "a"}"#;
+    let response: rpc::Response = native_json::parse(&router.handle(text).unwrap())?;
+    assert_eq!(response.id, Some(rpc::Id::from("a")));
+    let e = response.into_result().unwrap_err();
+    assert_eq!(e.code, rpc::Error::INVALID_PARAMS);
+    assert!(e.message.starts_with("minuend: "), "{}", e.message);
+    Ok(())
+}
+
+#[test]
+fn json_ws() -> Pod {
+    json! { ws Message {
+        Ping { ts: i64 },
+        Pong,
+        Trade { price: f64, qty: f64, side: { buy: bool } },
+        book_ticker { bid: f64, ask: f64 },
+    }}
+    json! { ws Event @tag("e") { Kline { k: [f64] } } }
+
+    let ping = Message::parse_message(r#"{"type": "Ping", "ts": 7}"#)?;
+    assert!(matches!(ping, Message::Ping(MessagePing { ts: 7 })));
+    assert_eq!(ping.kind(), "Ping");
+
+    let mut trade = MessageTrade::new();
+    trade.price = 1.5;
+    trade.side.buy = true;
+    let trade = Message::from(trade);
+    let text = trade.encode_message()?;
+    assert_eq!(
+        text,
+        r#"{"type":"Trade","price":1.5,"qty":0.0,"side":{"buy":true}}"#
+    );
+    assert!(matches!(Message::parse_message(&text)?, Message::Trade(t) if t.side.buy));
+
+    // unit variants and the renamed tags
+    assert_eq!(Message::Pong.encode_message()?, r#"{"type":"Pong"}"#);
+    let ticker = Message::parse_message(r#"{"type": "book_ticker", "bid": 1, "ask": 2}"#)?;
+    assert_eq!(ticker.kind(), "book_ticker");
+    assert!(matches!(ticker, Message::BookTicker(MessageBookTicker { ask, .. }) if ask == 2.0));
+
+    let event = Event::parse_message(r#"{"e": "Kline", "k": [1, 2]}"#)?;
+    assert_eq!(event.encode_message()?, r#"{"e":"Kline","k":[1.0,2.0]}"#);
+
+    // unknown tags and the fields of the variants
+    assert!(Message::parse_message(r#"{"type": "Order"}"#).is_err());
+    let e = Message::parse_message(r#"{"type": "Ping", "ts": "x"}"#).unwrap_err();
+    assert!(matches!(e, Error::Type { .. }), "{}", e);
+    Ok(())
+}
+
+#[test]
+fn json_locked() -> Pod {
+    json! { Shared { writer: u32, items: [u32] } }
+
+    let dir = TempDir::new("locked")?;
+    let path = dir.join("shared.json");
+    native_json::write_locked(&path, &Shared::new())?;
+
+    // readers never see a partial file
+    let threads: Vec<_> = (0..8)
+        .map(|i| {
+            let path = path.clone();
+            std::thread::spawn(move || -> Result<(), native_json::Error> {
+                for _ in 0..20 {
+                    if i % 2 == 0 {
+                        let shared = Shared {
+                            writer: i,
+                            items: (0..2000).collect(),
+                        };
+                        match i {
+                            0 => native_json::writer(&path).exclusive(true).write(&shared)?,
+                            _ => native_json::write_locked(&path, &shared)?,
+                        }
+                    } else {
+                        let _: Shared = native_json::read_locked(&path)?;
+                    }
+                }
+                Ok(())
+            })
+        })
+        .collect();
+    for thread in threads {
+        thread.join().unwrap()?;
+    }
+    let shared: Shared = native_json::read_locked(&path)?;
+    assert_eq!(shared.items.len(), 2000);
+
+    Ok(())
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn json_async_io() -> Pod {
+    json! { Stored { name: String, items: [u32] } }
+
+    let dir = TempDir::new("async")?;
+    let path = dir.join("stored.json");
+
+    let mut stored = Stored::new();
+    stored.name = "state".to_owned();
+    stored.items = (0..1000).collect();
+    native_json::write_async(&path, &stored).await?;
+    let read: Stored = native_json::read_async(&path).await?;
+    assert_eq!(read.items.len(), 1000);
+    assert_eq!(std::fs::read_to_string(&path)?, stored.stringify(2)?);
+
+    let shared = std::sync::Arc::new(stored);
+    native_json::write_async_blocking(&path, shared.clone()).await?;
+    let read: Stored = native_json::read_async_blocking(&path).await?;
+    assert_eq!(read.name, "state");
+
+    native_json::writer(&path)
+        .indent(0)
+        .write_async(&*shared)
+        .await?;
+    assert_eq!(std::fs::read_to_string(&path)?, shared.string()?);
+
+    // the future is Send, the progress is observed
+    let done = std::sync::Arc::new(std::sync::atomic::AtomicU64::new(0));
+    let task = tokio::spawn({
+        let (path, done) = (path.clone(), done.clone());
+        async move {
+            native_json::writer(&path)
+                .atomic(true)
+                .on_progress(move |n, _| done.store(n, std::sync::atomic::Ordering::Relaxed))
+                .write_async(&*shared)
+                .await
+        }
+    });
+    task.await.unwrap()?;
+    let size = std::fs::metadata(&path)?.len();
+    assert_eq!(done.load(std::sync::atomic::Ordering::Relaxed), size);
+
+    let err = native_json::read_async::<Stored, _>(dir.join("missing.json")).await;
+    assert!(matches!(err, Err(native_json::Error::Io(_))));
+
+    Ok(())
+}
+
+#[test]
+fn json_stringify_sort_keys() -> Pod {
+    json! { Sorted { name: String, map: HashMap<String, u8>, list: [{ z: u8, a: u8 }] } }
+
+    let mut sorted = Sorted::new();
+    for (i, key) in ["d", "b", "e", "a", "c"].iter().enumerate() {
+        sorted.map.insert(key.to_string(), i as u8);
+    }
+    sorted.list.push(Sorted_list_item::new());
+
+    // declaration order
+    let options = StringifyOptions::new();
+    assert!(sorted
+        .stringify_with(&options)?
+        .starts_with("{\"name\":\"\",\"map\":"));
+
+    let options = StringifyOptions::new().sort_keys(true);
+    assert_eq!(
+        sorted.stringify_with(&options)?,
+        "{\"list\":[{\"a\":0,\"z\":0}],\"map\":{\"a\":3,\"b\":1,\"c\":4,\"d\":0,\"e\":2},\"name\":\"\"}"
+    );
+    let pretty = sorted.stringify_with(&options.indent(2))?;
+    assert!(pretty.starts_with("{\n  \"list\": [\n    {\n      \"a\": 0,"));
+
+    Ok(())
+}
+
+#[test]
+fn json_stringify_options() -> Pod {
+    let json = json! { name: "caf\u{e9} \u{1f600}", list: [1, 2], empty: [], object: {} };
+
+    // same output as serde_json
+    let pretty = json.stringify(2)?;
+    assert_eq!(pretty, serde_json::to_string_pretty(&json)?);
+    assert_eq!(json.string()?, serde_json::to_string(&json)?);
+
+    let options = StringifyOptions::new()
+        .indent(1)
+        .indent_char('\t')
+        .crlf(true)
+        .trailing_newline(true);
+    assert_eq!(
+        json.stringify_with(&options)?,
+        "{\r\n\t\"name\": \"caf\u{e9} \u{1f600}\",\r\n\t\"list\": [\r\n\t\t1,\r\n\t\t2\r\n\t],\r\n\t\"empty\": [],\r\n\t\"object\": {}\r\n}\r\n"
+    );
+
+    let options = StringifyOptions::new().ensure_ascii(true);
+    let ascii = json.stringify_with(&options)?;
+    assert_eq!(
+        ascii,
+        "{\"name\":\"caf\\u00e9 \\ud83d\\ude00\",\"list\":[1,2],\"empty\":[],\"object\":{}}"
+    );
+    let value: serde_json::Value = native_json::parse(&ascii)?;
+    assert_eq!(value["name"], "caf\u{e9} \u{1f600}");
+
+    let options = StringifyOptions::new().trailing_newline(true);
+    assert_eq!(json! { a: 1 }.stringify_with(&options)?, "{\"a\":1}\n");
+
+    Ok(())
+}
+
+#[test]
+fn json_stringify_float() -> Pod {
+    let json = json! { a: 0.1 + 0.2, b: 1.0, c: -2.5f32, d: 1e20, e: 3 };
+
+    let shortest = json.string()?;
+    assert_eq!(
+        shortest,
+        "{\"a\":0.30000000000000004,\"b\":1.0,\"c\":-2.5,\"d\":1e+20,\"e\":3}"
+    );
+
+    let options = StringifyOptions::new().float_precision(4);
+    assert_eq!(
+        json.stringify_with(&options)?,
+        "{\"a\":0.3000,\"b\":1.0000,\"c\":-2.5000,\"d\":100000000000000000000.0000,\"e\":3}"
+    );
+
+    let options = StringifyOptions::new().float_format(FloatFormat::Integer);
+    assert_eq!(
+        json.stringify_with(&options)?,
+        "{\"a\":0.30000000000000004,\"b\":1,\"c\":-2.5,\"d\":1e+20,\"e\":3}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn json_stringify_non_finite() -> Pod {
+    json! { Measure { name: String, ratio: f64, samples: [{ value: f32 }], extra: HashMap<String, f64> } }
+
+    let mut measure = Measure::new();
+    measure.ratio = f64::NAN;
+    measure.samples.push(Measure_samples_item { value: 1.5 });
+    measure.samples.push(Measure_samples_item {
+        value: f32::NEG_INFINITY,
+    });
+    assert_eq!(
+        measure.string()?,
+        "{\"name\":\"\",\"ratio\":null,\"samples\":[{\"value\":1.5},{\"value\":null}],\"extra\":{}}"
+    );
+
+    let options = StringifyOptions::new().non_finite(NonFinite::String);
+    assert_eq!(
+        measure.stringify_with(&options)?,
+        "{\"name\":\"\",\"ratio\":\"NaN\",\"samples\":[{\"value\":1.5},{\"value\":\"-Infinity\"}],\"extra\":{}}"
+    );
+    let sorted = options.clone().sort_keys(true);
+    assert!(measure
+        .stringify_with(&sorted)?
+        .contains("\"ratio\":\"NaN\""));
+
+    let options = StringifyOptions::new().non_finite(NonFinite::Error);
+    let err = measure.stringify_with(&options).unwrap_err().to_string();
+    assert_eq!(err, "non-finite float at `ratio`");
+    measure.ratio = 0.5;
+    let err = measure.stringify_with(&options).unwrap_err().to_string();
+    assert_eq!(err, "non-finite float at `samples[1].value`");
+    measure.samples.pop();
+    measure.extra.insert("max".to_owned(), f64::INFINITY);
+    let err = measure.stringify_with(&options).unwrap_err().to_string();
+    assert_eq!(err, "non-finite float at `extra.max`");
+    measure.extra.clear();
+    assert!(measure.stringify_with(&options.indent(2)).is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_secret() -> Pod {
+    json! { Secrets {
+        user: String,
+        token: String @secret,
+        pin: u32? @secret,
+        keys: [{ id: u8, key: String @secret }]
+    }}
+
+    let mut secrets = Secrets::new();
+    secrets.user = "admin".to_owned();
+    secrets.token = "abc".to_owned();
+    secrets.keys.push(Secrets_keys_item {
+        id: 1,
+        key: "xyz".to_owned(),
+    });
+
+    let text = "{\"user\":\"admin\",\"token\":\"abc\",\"keys\":[{\"id\":1,\"key\":\"xyz\"}]}";
+    assert_eq!(secrets.string()?, text);
+    assert_eq!(
+        secrets.stringify_redacted(0)?,
+        "{\"user\":\"admin\",\"token\":\"****\",\"keys\":[{\"id\":1,\"key\":\"****\"}]}"
+    );
+    assert_eq!(secrets.string()?, text);
+
+    // deserialize as usual
+    let parsed: Secrets = native_json::parse(text)?;
+    assert_eq!(parsed.token, "abc");
+
+    Ok(())
+}
+
+#[cfg(any(feature = "sha2", feature = "blake3"))]
+#[test]
+fn json_digest() -> Pod {
+    let a = json! { b: 1.0, a: "x" };
+    let b = json! { b: 1, a: "x" };
+    let c = json! { b: 2, a: "x" };
+
+    #[cfg(feature = "sha2")]
+    {
+        // sha256 of {"a":"x","b":1}
+        let hash = a.digest(Digest::Sha256)?;
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, b.digest(Digest::Sha256)?);
+        assert_ne!(hash, c.digest(Digest::Sha256)?);
+        let value: serde_json::Value = native_json::parse("{}")?;
+        assert_eq!(
+            value.digest(Digest::Sha256)?,
+            "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a"
+        );
+    }
+    #[cfg(feature = "blake3")]
+    {
+        let hash = a.digest(Digest::Blake3)?;
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, b.digest(Digest::Blake3)?);
+        assert_ne!(hash, c.digest(Digest::Blake3)?);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn json_canonicalize() -> Pod {
+    let json = json! { b: [1.0, -0.0, 1e21, 1.5e-7, 0.000001, 123456789012.5, 4.5e300], a: null };
+    assert_eq!(
+        json.canonicalize()?,
+        "{\"a\":null,\"b\":[1,0,1e+21,1.5e-7,0.000001,123456789012.5,4.5e+300]}"
+    );
+
+    // keys are sorted by UTF-16 code units
+    let value: serde_json::Value = native_json::parse(
+        "{ \"\u{fb01}\": \"\u{1f600}\\n/\", \"\u{1f600}\": 1, \"\\r\": true, \"1\": 9007199254740993 }",
+    )?;
+    assert_eq!(
+        value.canonicalize()?,
+        "{\"\\r\":true,\"1\":9007199254740992,\"\u{1f600}\":1,\"\u{fb01}\":\"\u{1f600}\\n/\"}"
+    );
+    Ok(())
+}
+
+#[test]
+fn json_instance_empty() -> Pod {
+    let mut json = json! {
+        tags: [],
+        meta: {},
+        names: [] as Vec<String>,
+        labels: {} as HashMap<String, String>,
+        nested: { items: [], options: {} }
+    };
+    json.names.push("a".to_owned());
+    json.labels.insert("k".to_owned(), "v".to_owned());
+    assert_eq!(
+        json.string()?,
+        "{\"tags\":[],\"meta\":{},\"names\":[\"a\"],\"labels\":{\"k\":\"v\"},\"nested\":{\"items\":[],\"options\":{}}}"
+    );
+    Ok(())
+}
+
+#[test]
+fn json_instance_typed_null() -> Pod {
+    let mut json = json! {
+        name: null,
+        range: null as Option<i32>,
+        ratio: None as Option<f64>,
+        extra: null as serde_json::Value
+    };
+    assert_eq!(
+        json.string()?,
+        "{\"name\":null,\"range\":null,\"ratio\":null,\"extra\":null}"
+    );
+    json.range = Some(10);
+    json.ratio = Some(0.5);
+    assert_eq!(
+        json.string()?,
+        "{\"name\":null,\"range\":10,\"ratio\":0.5,\"extra\":null}"
+    );
+    Ok(())
+}
+
+#[test]
+fn json_instance_spread() -> Pod {
+    let defaults = json! {
+        host: "localhost",
+        port: 80,
+        style: { color: "red", size: 12 }
+    };
+    let base = defaults.clone();
+
+    // fields of base are overridden
+    let config = json! { ..defaults, port: 8080 };
+    assert_eq!(
+        config.string()?,
+        "{\"host\":\"localhost\",\"port\":8080,\"style\":{\"color\":\"red\",\"size\":12}}"
+    );
+
+    // nested
+    let config = json! { ..base.clone(), style: { ..base.style, size: 14 } };
+    assert_eq!(config.style.size, 14);
+    assert_eq!(config.style.color, "red");
+
+    // declared struct
+    json! { Server { host: String, port: u16 } }
+    let server = Server {
+        host: "example.com".to_owned(),
+        port: 443,
+    };
+    let local = json! { ..server, host: "localhost".to_owned() };
+    assert_eq!((local.host.as_str(), local.port), ("localhost", 443));
+
+    // Rust struct update of a typed object
+    let proxy = json! { server: Server { port: 3128, ..Default::default() } };
+    assert_eq!((proxy.server.host.as_str(), proxy.server.port), ("", 3128));
+    let proxy = json! { server: Server { ..local.clone() } };
+    assert_eq!(proxy.server.host, "localhost");
+
+    Ok(())
+}
+
+#[test]
+fn json_instance_shorthand() -> Pod {
+    let name = "Tom";
+    let age = 18;
+    let active = true;
+    let r#type = "admin";
+
+    let user = json! { name, age, active };
+    assert_eq!(
+        user.string()?,
+        "{\"name\":\"Tom\",\"age\":18,\"active\":true}"
+    );
+
+    let user = json! { name, r#type, profile: { age, active }, score: 9.5 };
+    assert_eq!(
+        user.string()?,
+        "{\"name\":\"Tom\",\"type\":\"admin\",\"profile\":{\"age\":18,\"active\":true},\"score\":9.5}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn json_instance_typed() -> Pod {
+    json! { School {
+        name: String,
+        students: [{ name: String, age: u8, tutor: { name: String } }],
+        address: Option<String>
+    }}
+
+    // nested objects are named after the declared type
+    let school = json! { school: School {
+        name: "MIT",
+        students: [
+            { name: "Tom", age: 20, tutor: { name: "Don" } },
+            { name: "Ann", age: 19, tutor: { name: "" } }
+        ],
+        address: null
+    }};
+    assert_eq!(school.school.students[1].name, "Ann");
+    assert_eq!(school.school.address, None);
+
+    let student =
+        json! { student: School_students_item { name: "Tom", age: 20, tutor: { name: "Don" } } };
+    assert_eq!(student.student.tutor.name, "Don");
+
+    let list =
+        json! { students: [School_students_item; { name: "A", age: 1, tutor: { name: "B" } }] };
+    let students: Vec<School_students_item> = list.students;
+    assert_eq!(students[0].age, 1);
+
+    // not a typed array, the Rust repeat expression
+    let n = 7;
+    let zeros = json! { zeros: [n; 3] };
+    assert_eq!(zeros.string()?, "{\"zeros\":[7,7,7]}");
+
+    Ok(())
+}
+
+#[test]
+fn json_instance_conditional() -> Pod {
+    for verbose in [false, true] {
+        let json = json! {
+            name: "x",
+            debug: { level: 3, trace: [1, 2] } @if(verbose),
+            count: 5 @if(!verbose)
+        };
+        if verbose {
+            assert_eq!(json.debug.as_ref().map(|x| x.level), Some(3));
+            assert_eq!(
+                json.string()?,
+                "{\"name\":\"x\",\"debug\":{\"level\":3,\"trace\":[1,2]}}"
+            );
+        } else {
+            assert_eq!(json.string()?, "{\"name\":\"x\",\"count\":5}");
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn json_declare() {
+    json! {
+    School {
+        name: String,
+        students:[{
+            name: String,
+            age: i32,
+            tutor: {
+                name: String,
+                course: String
+              }
+            },
+            ...
+        ],
+        nullable: Option<String>,
+        map: HashMap<String, i32>
+    }}
+
+    let mut school = School::new();
+    school.name = "MIT".to_owned();
+
+    let mut newbie = School_students_item::new();
+    let tutor = School_students_item_tutor {
+        name: "Don Markuson".to_owned(),
+        course: "Math".to_owned(),
+    };
+    newbie.name = "John".to_owned();
+    newbie.age = 17;
+    newbie.tutor = tutor;
+    school.students.push(newbie);
+}
+
+#[test]
+fn json_serialize() -> Pod {
+    let mut json = json! {
+        name: "native json",
+        point: { x: 10, y: 20},
+        array: [1,2,3,4,5],
+        vector: vec![1,2,3,4,5,6]
+    };
+
+    // stringify
+    let s = json.stringify(4)?;
+    json.name = "";
+    json = native_json::parse(&s)?;
+    assert_eq!(json.name, "native json");
+
+    // concise string
+    let s2 = json.string()?;
+    json.name = "modified";
+    json = native_json::parse(&s2)?;
+    assert_eq!(json.name, "native json");
+
+    Ok(())
+}
+
+#[test]
+fn json_test_array_with_custom_type() -> Result<(), std::io::Error> {
+    json! {
+        Student { name: String, age: u32}
+    }
+
+    json! {
+        Class {
+            name: String,
+            students: [Student]
+        }
+    }
+
+    let mut c = Class::new();
+    c.name = "High School".into();
+    let mut student = Student::new();
+    student.name = "Tom Jackson".into();
+    student.age = 25;
+    c.students.push(student);
+
+    Ok(())
+}
+
+#[test]
+fn json_test_inline_comment() {
+    println!("should compile");
+    json! {
+    AggTrage {
+        e: String,   // Event type: aggTrade
+        E: i64,      // Event time
+        s: String,   // Symbol
+        a: i64,      // Aggregate trade ID
+        p: String,   // Price
+        q: String,   // Quantity
+        f: i64,      // First trade ID
+        l: i64,      // Last trade ID
+        T: i64,      // Trade time
+        m: bool,     // Is the buyer the market maker?
+        c: char,     // test only
+    }}
+}
+
+#[test]
+fn json_datetime_initializer() -> Pod {
+    use chrono::{DateTime, NaiveDate, Utc};
+
+    json! {
+    Event {
+        name: String,
+        created_at: DateTime<Utc>,
+        day: NaiveDate,
+        expires_at: time::OffsetDateTime
+    }}
+
+    let event = Event::new();
+    assert_eq!(event.created_at.timestamp(), 0);
+    assert_eq!(event.expires_at.unix_timestamp(), 0);
+
+    let s = event.string()?;
+    let event: Event = native_json::parse(&s)?;
+    assert_eq!(event.day, NaiveDate::default());
+
+    Ok(())
+}
+
+#[cfg(all(feature = "chrono", feature = "time"))]
+#[test]
+fn json_datetime_format() -> Pod {
+    use chrono::{DateTime, TimeZone, Utc};
+    use time::OffsetDateTime;
+
+    json! {
+    Event {
+        a: DateTime<Utc> @format(rfc3339),
+        b: DateTime<Utc> @format(unix),
+        c: DateTime<Utc> @format(unix_ms),
+        d: OffsetDateTime @format(unix)
+    }}
+
+    let mut event = Event::new();
+    event.a = Utc.timestamp_opt(1_700_000_000, 0).unwrap();
+    event.b = event.a;
+    event.c = Utc.timestamp_millis_opt(1_700_000_000_123).unwrap();
+    event.d = OffsetDateTime::from_unix_timestamp(1_700_000_000)?;
+
+    let s = event.string()?;
+    assert_eq!(
+        s,
+        "{\"a\":\"2023-11-14T22:13:20Z\",\"b\":1700000000,\"c\":1700000000123,\"d\":1700000000}"
+    );
+
+    let parsed: Event = native_json::parse(&s)?;
+    assert_eq!(parsed.c, event.c);
+    assert_eq!(parsed.d, event.d);
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_default() -> Pod {
+    json! {
+    Style {
+        color: String,
+        size: u32,
+        font: { name: String, bold: bool }
+    }}
+
+    #[derive(Deserialize)]
+    struct Page {
+        #[serde(default)]
+        style: Style,
+    }
+
+    let style = Style::default();
+    assert_eq!(style.size, 0);
+    assert!(!style.font.bold);
+    assert!(Style_font::default().name.is_empty());
+
+    // `#[serde(default)]` requires `Default`
+    let page: Page = native_json::parse("{}")?;
+    assert_eq!(page.style.color, "");
+
+    let styles: Vec<Style> = Vec::new();
+    assert_eq!(styles.into_iter().next().unwrap_or_default().size, 0);
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_map() -> Pod {
+    json! {
+    #[json(schema)]
+    Inventory {
+        attributes: {String: i32},
+        points: {String: { x: f64, y: f64 }},
+        levels: [{u8: String}]
+    }}
+
+    let mut inventory = Inventory::new();
+    assert!(inventory.attributes.is_empty());
+    inventory.attributes.insert("size".to_owned(), 3);
+    inventory.points.insert(
+        "origin".to_owned(),
+        Inventory_points_value { x: 1.0, y: 2.0 },
+    );
+    let text = inventory.string()?;
+    assert_eq!(
+        text,
+        "{\"attributes\":{\"size\":3},\"points\":{\"origin\":{\"x\":1.0,\"y\":2.0}},\"levels\":[]}"
+    );
+
+    let text = "{\"attributes\":{},\"points\":{},\"levels\":[{\"1\":\"low\",\"2\":\"high\"}]}";
+    let parsed: Inventory = native_json::parse(text)?;
+    let levels: &HashMap<u8, String> = &parsed.levels[0];
+    assert_eq!(levels[&2], "high");
+
+    let schema = Inventory::schema();
+    let points = &schema["properties"]["points"];
+    assert_eq!(points["type"], "object");
+    assert_eq!(
+        points["additionalProperties"]["$ref"],
+        "#/$defs/Inventory_points_value"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_raw() -> Pod {
+    json! {
+    #[json(typescript)]
+    Envelope {
+        id: u32,
+        payload: raw,
+        extra: raw?
+    }}
+
+    // null by default
+    let envelope = Envelope::new();
+    assert_eq!(envelope.payload.get(), "null");
+    assert!(envelope.extra.is_none());
+    assert_eq!(envelope.string()?, "{\"id\":0,\"payload\":null}");
+
+    // the text is kept verbatim
+    let text = r#"{"id":1,"payload":{"b": [1, 2.50],"a":"x"},"extra":[ ]}"#;
+    let envelope: Envelope = native_json::parse(text)?;
+    assert_eq!(envelope.payload.get(), r#"{"b": [1, 2.50],"a":"x"}"#);
+    assert_eq!(envelope.extra.as_ref().map(|x| x.get()), Some("[ ]"));
+    assert_eq!(envelope.string()?, text);
+
+    // parsed on demand
+    let payload: HashMap<String, Value> = native_json::parse(envelope.payload.get())?;
+    assert_eq!(payload["a"], "x");
+    assert!(Envelope::typescript().contains("payload: unknown;"));
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_builder() -> Pod {
+    json! {
+    #[json(builder)]
+    School {
+        name: String,
+        rank: u32?,
+        students: [{ name: String, age: u16 }],
+        address: { city: String }
+    }}
+
+    let john = School_students_item::builder()
+        .with_name("John".into())
+        .with_age(18)
+        .build();
+    let school = School::builder()
+        .with_name("MIT".into())
+        .with_students(vec![john])
+        .with_address(
+            School_address::builder()
+                .with_city("Cambridge".into())
+                .build(),
+        )
+        .build();
+
+    assert_eq!(school.rank, 0);
+    assert_eq!(school.students[0].age, 18);
+    assert_eq!(
+        school.string()?,
+        "{\"name\":\"MIT\",\"students\":[{\"name\":\"John\",\"age\":18}],\"address\":{\"city\":\"Cambridge\"}}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_deny_unknown_fields() -> Pod {
+    json! {
+    #[json(deny_unknown_fields)]
+    Config {
+        host: String,
+        server: { port: u16 }
+    }}
+
+    let s = "{\"host\":\"localhost\",\"server\":{\"port\":80}}";
+    let config: Config = native_json::parse(s)?;
+    assert_eq!(config.server.port, 80);
+
+    // unknown key of root
+    let s = "{\"host\":\"localhost\",\"hots\":\"\",\"server\":{\"port\":80}}";
+    let error = native_json::parse::<Config>(s).unwrap_err();
+    assert!(error.to_string().contains("unknown field `hots`"));
+
+    // unknown key of nested object
+    let s = "{\"host\":\"localhost\",\"server\":{\"port\":80,\"prot\":81}}";
+    assert!(native_json::parse::<Config>(s).is_err());
+
+    // `...rest` conflicts with deny_unknown_fields, the unknown keys land in the rest
+    json! { Named { name: String, ...: HashMap<String, serde_json::Value> } }
+    let named: Named = native_json::parse("{\"name\":\"a\",\"zz\":3}")?;
+    assert_eq!(named.extra["zz"], 3);
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_with() -> Pod {
+    mod hex {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+            let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+            s.serialize_str(&hex)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+            let hex = String::deserialize(d)?;
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(serde::de::Error::custom))
+                .collect()
+        }
+    }
+
+    fn number_from_string<'de, D: serde::Deserializer<'de>>(d: D) -> Result<u32, D::Error> {
+        let s = String::deserialize(d)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+
+    json! { Packet {
+        payload: Vec<u8> @with(hex),
+        size: u32 @deserialize_with("number_from_string"),
+        checksum: Vec<u8> @serialize_with(hex::serialize) @deserialize_with(hex::deserialize)
+    }}
+
+    let s = "{\"payload\":\"00ff\",\"size\":\"2\",\"checksum\":\"0a\"}";
+    let packet: Packet = native_json::parse(s)?;
+    assert_eq!(packet.payload, [0, 255]);
+    assert_eq!(packet.size, 2);
+    assert_eq!(packet.checksum, [10]);
+    assert_eq!(
+        packet.string()?,
+        "{\"payload\":\"00ff\",\"size\":2,\"checksum\":\"0a\"}"
+    );
+
+    Ok(())
+}
+
+#[cfg(feature = "base64")]
+#[test]
+fn json_declare_bytes() -> Pod {
+    json! {
+    #[json(typescript)]
+    Blob {
+        data: bytes,
+        key: bytes @base64(url_safe_no_pad),
+        raw: Vec<u8> @base64,
+        thumbnail: bytes?
+    }}
+
+    let mut blob = Blob::new();
+    assert!(blob.data.is_empty());
+    blob.data = b"hello".to_vec();
+    blob.key = vec![0xfb, 0xff];
+    blob.raw = vec![1, 2, 3];
+    let s = blob.string()?;
+    assert_eq!(
+        s,
+        "{\"data\":\"aGVsbG8=\",\"key\":\"-_8\",\"raw\":\"AQID\"}"
+    );
+
+    let blob: Blob = native_json::parse(&s)?;
+    assert_eq!(blob.key, [0xfb, 0xff]);
+    assert!(blob.thumbnail.is_empty());
+    assert!(Blob::typescript().contains("  thumbnail?: string;"));
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_number_string() -> Pod {
+    json! {
+    #[json(typescript)]
+    Transfer {
+        amount: u128 @string,
+        fee: i64 @string,
+        nonce: u64? @string
+    }}
+
+    let mut transfer = Transfer::new();
+    transfer.amount = u128::MAX;
+    transfer.fee = -1;
+    let s = transfer.string()?;
+    assert_eq!(
+        s,
+        "{\"amount\":\"340282366920938463463374607431768211455\",\"fee\":\"-1\"}"
+    );
+    assert_eq!(native_json::parse::<Transfer>(&s)?.amount, u128::MAX);
+
+    // plain numbers are accepted
+    let transfer: Transfer = native_json::parse("{\"amount\":5,\"fee\":\"2\",\"nonce\":3}")?;
+    assert_eq!((transfer.amount, transfer.fee, transfer.nonce), (5, 2, 3));
+    assert!(native_json::parse::<Transfer>("{\"amount\":\"x\",\"fee\":1}").is_err());
+    assert!(Transfer::typescript().contains("  amount: string;"));
+
+    Ok(())
+}
+
+#[cfg(all(feature = "rust_decimal", feature = "bigdecimal"))]
+#[test]
+fn json_declare_decimal() -> Pod {
+    use std::str::FromStr;
+
+    json! { Invoice {
+        total: Decimal,
+        tax: Decimal?,
+        rate: BigDecimal,
+        fee: Decimal @string
+    }}
+
+    let mut invoice = Invoice::new();
+    assert_eq!(invoice.total, Decimal::ZERO);
+    invoice.total = Decimal::from_str("0.1")? + Decimal::from_str("0.2")?;
+    invoice.rate = BigDecimal::from_str("1.000000000000000000000000000001")?;
+    let s = invoice.string()?;
+    assert_eq!(
+        s,
+        "{\"total\":\"0.3\",\"rate\":\"1.000000000000000000000000000001\",\"fee\":\"0\"}"
+    );
+    let parsed: Invoice = native_json::parse(&s)?;
+    assert_eq!(parsed.rate, invoice.rate);
+
+    // JSON numbers are accepted
+    let parsed: Invoice =
+        native_json::parse("{\"total\": 1.25, \"tax\": 2, \"rate\": 0.5, \"fee\": 1}")?;
+    assert_eq!(parsed.total, Decimal::from_str("1.25")?);
+    assert_eq!(parsed.tax, Decimal::TWO);
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_net_path() -> Pod {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+    use std::path::PathBuf;
+
+    json! { Server {
+        ip: IpAddr,
+        v4: Ipv4Addr,
+        v6: std::net::Ipv6Addr,
+        listen: SocketAddr,
+        root: PathBuf
+    }}
+
+    let server = Server::new();
+    assert_eq!(server.ip, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    assert_eq!(server.v6, Ipv6Addr::UNSPECIFIED);
+    assert_eq!(server.listen.to_string(), "0.0.0.0:0");
+    assert_eq!(server.root, PathBuf::new());
+
+    let s = "{\"ip\":\"::1\",\"v4\":\"127.0.0.1\",\"v6\":\"::\",\"listen\":\"127.0.0.1:80\",\"root\":\"/srv\"}";
+    let server: Server = native_json::parse(s)?;
+    assert_eq!(server.listen.port(), 80);
+    assert_eq!(server.root, PathBuf::from("/srv"));
+
+    Ok(())
+}
+
+#[cfg(feature = "uuid")]
+#[test]
+fn json_declare_uuid() -> Pod {
+    json! { Session { id: Uuid, parent: Uuid? } }
+
+    let mut session = Session::new();
+    assert!(session.id.is_nil());
+    session.id = Uuid::from_u128(1);
+    let s = session.string()?;
+    assert_eq!(s, "{\"id\":\"00000000-0000-0000-0000-000000000001\"}");
+    assert_eq!(native_json::parse::<Session>(&s)?.id, session.id);
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_cfg() -> Pod {
+    json! {
+    #[json(builder)]
+    Config {
+        name: String,
+        #[cfg(unix)]
+        socket: String @min_len(1),
+        #[cfg(not(unix))]
+        pipe: String,
+        #[cfg(test)]
+        #[cfg(debug_assertions)]
+        trace: bool?
+    }}
+
+    let config = Config::builder().with_name("x".to_owned()).build();
+    #[cfg(unix)]
+    {
+        assert!(config.validate().is_err());
+        let config = Config::builder().with_socket("/tmp/s".to_owned()).build();
+        assert_eq!(config.string()?, "{\"name\":\"\",\"socket\":\"/tmp/s\"}");
+    }
+    #[cfg(not(unix))]
+    assert_eq!(config.string()?, "{\"name\":\"x\",\"pipe\":\"\"}");
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_extends() -> Pod {
+    json! { User { id: u64, name: String } }
+    json! {
+    #[json(typescript)]
+    Admin extends User {
+        permissions: [String]
+    }}
+
+    let mut admin = Admin::new();
+    admin.id = 1;
+    admin.name = "root".to_owned();
+    admin.permissions.push("all".to_owned());
+    let s = admin.string()?;
+    assert_eq!(s, "{\"id\":1,\"name\":\"root\",\"permissions\":[\"all\"]}");
+
+    let admin: Admin = native_json::parse(&s)?;
+    let user: &User = &admin;
+    assert_eq!(user.name, "root");
+    assert!(Admin::typescript().starts_with("export interface Admin extends User {"));
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_extends_options() -> Pod {
+    json! {
+    #[json(schema, avro, patch, reflect)]
+    Person {
+        id: u64,
+        name: String @min_len(1),
+        address: { city: String }
+    }}
+    json! {
+    #[json(schema, avro, patch, reflect)]
+    Staff extends Person {
+        role: String @min_len(1),
+        email: Option<String>
+    }}
+
+    // the base is validated at the same path
+    let mut staff = Staff::new();
+    let errors = staff.validate().unwrap_err();
+    let paths: Vec<_> = errors.errors.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, ["name", "role"]);
+
+    // the base is patched by PersonPatch
+    staff.email = Some("a@b.c".to_owned());
+    let patch: StaffPatch = native_json::parse(
+        "{\"name\":\"Ann\",\"address\":{\"city\":\"Oslo\"},\"role\":\"dev\",\"email\":null}",
+    )?;
+    staff.apply(patch);
+    assert_eq!(staff.name, "Ann");
+    assert_eq!(staff.address.city, "Oslo");
+    assert_eq!(staff.role, "dev");
+    assert_eq!(staff.email, None);
+    assert!(staff.validate().is_ok());
+
+    let keys: Vec<_> = Staff::fields().iter().map(|x| x.key).collect();
+    assert_eq!(keys, ["id", "name", "address", "role", "email"]);
+
+    let schema = Staff::schema();
+    assert_eq!(schema["title"], "Staff");
+    assert_eq!(
+        schema["required"],
+        serde_json::json!(["id", "name", "address", "role"])
+    );
+    assert_eq!(
+        schema["properties"]["address"]["$ref"],
+        "#/$defs/Person_address"
+    );
+    assert_eq!(
+        schema["$defs"]["Person_address"]["required"],
+        serde_json::json!(["city"])
+    );
+    assert!(schema["properties"]["email"].is_object());
+
+    let avro = Staff::avro_schema();
+    let names: Vec<_> = avro["fields"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|x| &x["name"])
+        .collect();
+    assert_eq!(names, ["id", "name", "address", "role", "email"]);
+    assert_eq!(avro["fields"][2]["type"]["name"], "Person_address");
+
+    // only the derived struct has rules
+    json! { Member { id: u64 } }
+    json! { Moderator extends Member { level: u8 @range(0, 10) } }
+    let mut moderator = Moderator::new();
+    assert!(moderator.validate().is_ok());
+    moderator.level = 11;
+    let errors = moderator.validate().unwrap_err();
+    let paths: Vec<_> = errors.errors.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, ["level"]);
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_reuse() -> Pod {
+    json! { School { students: [{ name: String, tutor: { name: String, course: String } }] } }
+    // reuse the nested type of another declaration
+    json! { Course { title: String, tutor: School_students_item_tutor, assistants: [School_students_item_tutor] } }
+
+    let mut course = Course::new();
+    course.tutor.name = "Don".to_owned();
+    course.assistants.push(School_students_item_tutor::new());
+    let s = course.string()?;
+    assert_eq!(
+        s,
+        "{\"title\":\"\",\"tutor\":{\"name\":\"Don\",\"course\":\"\"},\"assistants\":[{\"name\":\"\",\"course\":\"\"}]}"
+    );
+    Ok(())
+}
+
+#[test]
+fn json_declare_methods() {
+    json! { School {
+        name: String,
+        students: [{
+            name: String,
+            age: u8,
+            impl {
+                fn is_adult(&self) -> bool {
+                    self.age >= 18
+                }
+            }
+        }],
+        impl {
+            pub fn adults(&self) -> usize {
+                self.students.iter().filter(|x| x.is_adult()).count()
+            }
+        }
+    }}
+
+    let mut school = School::new();
+    let mut student = School_students_item::new();
+    student.age = 20;
+    school.students.push(student);
+    school.students.push(School_students_item::new());
+    assert_eq!(school.adults(), 1);
+}
+
+#[test]
+fn json_declare_module() -> Pod {
+    // helper structs are kept in the module, the root is exported
+    json! { mod school {
+        #[json(builder)]
+        School { name: String, students: [{ name: String, tutor: { name: String } }] }
+    }}
+
+    let mut school = School::builder().with_name("MIT".to_owned()).build();
+    school.students.push(school::School_students_item::new());
+    assert_eq!(
+        school.string()?,
+        "{\"name\":\"MIT\",\"students\":[{\"name\":\"\",\"tutor\":{\"name\":\"\"}}]}"
+    );
+    Ok(())
+}
+
+mod campus {
+    use super::*;
+
+    json! { pub(in crate::campus) mod hall {
+        Hall { name: String }
+    }}
+
+    pub fn hall() -> String {
+        hall::Hall::new().name
+    }
+}
+
+#[test]
+fn json_declare_module_restricted() {
+    assert_eq!(campus::hall(), "");
+}
+
+#[test]
+fn json_declare_patch() -> Pod {
+    // optional number as string
+    mod text {
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(n: &Option<u8>, s: S) -> Result<S::Ok, S::Error> {
+            match n {
+                Some(n) => s.collect_str(n),
+                None => s.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u8>, D::Error> {
+            let s = Option::<String>::deserialize(d)?;
+            s.map(|x| x.parse().map_err(serde::de::Error::custom))
+                .transpose()
+        }
+    }
+
+    json! {
+    #[json(patch)]
+    Config {
+        name: String,
+        "log-level": u8,
+        server: { host: String, port: u16 },
+        tags: [String],
+        note: String?,
+        proxy: Option<String>,
+        limit: u128 @string,
+        retries: Option<u8> @with(text)
+    }}
+
+    let mut config = Config::new();
+    config.name = "app".to_owned();
+    config.server.host = "localhost".to_owned();
+    config.server.port = 80;
+
+    let patch: ConfigPatch =
+        native_json::parse("{\"log-level\":3,\"server\":{\"port\":8080},\"tags\":[\"a\"]}")?;
+    config.apply(patch);
+    assert_eq!(config.name, "app");
+    assert_eq!(config.log_level, 3);
+    assert_eq!(config.server.host, "localhost");
+    assert_eq!(config.server.port, 8080);
+    assert_eq!(config.tags, ["a"]);
+
+    let patch = ConfigPatch {
+        note: Some("x".to_owned()),
+        ..Default::default()
+    };
+    assert_eq!(patch.string()?, "{\"note\":\"x\"}");
+
+    // `null` clears the Option, the custom serialization is kept
+    config.proxy = Some("http://proxy".to_owned());
+    let patch: ConfigPatch =
+        native_json::parse("{\"proxy\":null,\"limit\":\"7\",\"retries\":\"2\"}")?;
+    assert_eq!(
+        patch.string()?,
+        "{\"proxy\":null,\"limit\":\"7\",\"retries\":\"2\"}"
+    );
+    config.apply(patch);
+    assert_eq!(config.proxy, None);
+    assert_eq!(config.limit, 7);
+    assert_eq!(config.retries, Some(2));
+
+    let patch: ConfigPatch = native_json::parse("{\"retries\":null}")?;
+    config.apply(patch);
+    assert_eq!(config.retries, None);
+
+    // absent keys are untouched
+    config.proxy = Some("http://proxy".to_owned());
+    config.apply(native_json::parse("{}")?);
+    assert_eq!(config.proxy.as_deref(), Some("http://proxy"));
+
+    // write-only
+    #[derive(Serialize, Debug, Clone, Default)]
+    struct Elapsed(u64);
+
+    json! {
+    #[json(patch, serialize_only)]
+    Status {
+        elapsed: Elapsed,
+        note: Option<String>
+    }}
+
+    let patch = StatusPatch {
+        elapsed: Some(Elapsed(3)),
+        note: Some(None),
+    };
+    assert_eq!(patch.string()?, "{\"elapsed\":3,\"note\":null}");
+
+    Ok(())
+}
+
+#[cfg(feature = "base64")]
+#[test]
+fn json_declare_patch_bytes() -> Pod {
+    json! {
+    #[json(patch)]
+    Key {
+        data: bytes,
+        salt: bytes @base64(url_safe_no_pad)
+    }}
+
+    let mut key = Key::new();
+    let patch: KeyPatch = native_json::parse("{\"data\":\"AQI=\",\"salt\":\"_w\"}")?;
+    assert_eq!(patch.string()?, "{\"data\":\"AQI=\",\"salt\":\"_w\"}");
+    key.apply(patch);
+    assert_eq!(key.data, [1, 2]);
+    assert_eq!(key.salt, [255]);
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_reflect() -> Pod {
+    json! {
+    #[json(reflect)]
+    Reflected {
+        name: String,
+        type_: String,
+        note: String?,
+        students: [{ name: String, age: u8 }]
+    }}
+
+    let keys: Vec<_> = Reflected::fields().iter().map(|x| x.key).collect();
+    assert_eq!(keys, ["name", "type", "note", "students"]);
+    let note = Reflected::field("note").unwrap();
+    assert_eq!(
+        (note.name, note.ty, note.optional),
+        ("note", "String", true)
+    );
+    assert_eq!(Reflected_students_item::fields()[1].ty, "u8");
+
+    let mut school = Reflected::new();
+    school.students.push(Reflected_students_item::new());
+    school.set_path("students.0.age", 19.into())?;
+    school.set_path("type", "college".into())?;
+    assert_eq!(school.students[0].age, 19);
+    assert_eq!(school.type_, "college");
+    assert_eq!(school.get_path("students.0.age"), Some(19.into()));
+    assert!(school.get_path("students.1").is_none());
+    assert!(school.set_path("students.1.age", 20.into()).is_err());
+    assert!(school.set_path("name", 1.into()).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn json_tracked() -> Pod {
+    json! {
+    #[json(reflect)]
+    Config { name: String, port: u16, tags: [String], note: String? }}
+
+    let config: Config =
+        native_json::parse("{\"name\":\"app\",\"port\":80,\"tags\":[],\"note\":\"x\"}")?;
+    let mut config = Tracked::new(config)?;
+    assert!(!config.is_dirty()?);
+
+    config.port = 8080;
+    config.tags.push("a".to_owned());
+    config.note.clear();
+    let dirty: Vec<&'static str> = config.dirty_fields()?;
+    assert_eq!(dirty, ["port", "tags", "note"]);
+    let dirty: serde_json::Value = native_json::parse(&config.serialize_dirty()?)?;
+    let expected: serde_json::Value =
+        native_json::parse("{\"port\":8080,\"tags\":[\"a\"],\"note\":null}")?;
+    assert_eq!(dirty, expected);
+
+    config.commit()?;
+    assert!(config.dirty_fields()?.is_empty());
+    assert_eq!(config.into_inner().port, 8080);
+
+    // a serialization error is not a change of every key
+    json! {
+    #[json(reflect)]
+    Labels { labels: HashMap<Vec<u8>, String> }}
+
+    let mut labels = Tracked::new(Labels::new())?;
+    labels.labels.insert(vec![1], "a".to_owned());
+    assert!(labels.dirty_fields().is_err());
+    assert!(labels.serialize_dirty().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_collections() -> Pod {
+    use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+
+    json! { Inventory {
+        items: [{ name: String }],
+        tags: BTreeSet<String>,
+        ids: HashSet<u64>,
+        queue: VecDeque<u8>,
+        prices: BTreeMap<String, f64>,
+        groups: HashMap<String, Vec<Inventory_items_item>>,
+        flags: Vec<Option<bool>>,
+        history: Option<Vec<u32>>,
+        ordered: std::collections::BTreeMap<u8, Vec<String>>,
+        fast: HashMap<String, u8, std::collections::hash_map::RandomState>
+    }}
+
+    let mut inventory = Inventory::new();
+    assert!(inventory.tags.is_empty() && inventory.ids.is_empty());
+    assert!(inventory.queue.is_empty() && inventory.prices.is_empty());
+    assert!(inventory.groups.is_empty() && inventory.flags.is_empty());
+    assert_eq!(inventory.history, None);
+    assert!(inventory.ordered.is_empty() && inventory.fast.is_empty());
+
+    inventory.flags.push(None);
+    inventory
+        .groups
+        .insert("a".to_owned(), vec![Inventory_items_item::new()]);
+    let s = inventory.string()?;
+    let inventory: Inventory = native_json::parse(&s)?;
+    assert_eq!(inventory.flags, [None]);
+    assert_eq!(inventory.groups["a"].len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_any_type() -> Pod {
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+    enum Level {
+        #[default]
+        Info,
+        Warn,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+    struct Home(std::path::PathBuf);
+
+    // unknown types are initialized by Default::default(), or @init(expr)
+    json! { Logger {
+        level: Level,
+        home: Home,
+        threshold: Level @init(Level::Warn),
+        path: std::path::PathBuf @init("/var/log".into())
+    }}
+
+    let logger = Logger::new();
+    assert_eq!(logger.level, Level::Info);
+    assert_eq!(logger.home, Home::default());
+    assert_eq!(logger.threshold, Level::Warn);
+    assert_eq!(logger.path, std::path::PathBuf::from("/var/log"));
+
+    let s = "{\"level\":\"Warn\",\"home\":\"/home\",\"threshold\":\"Info\",\"path\":\"/\"}";
+    let logger: Logger = native_json::parse(s)?;
+    assert_eq!(logger.level, Level::Warn);
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_serialize_only() -> Pod {
+    // write-only, not deserializable
+    #[derive(Serialize, Debug, Clone, Default)]
+    struct Elapsed(u64);
+
+    json! {
+    #[json(serialize_only)]
+    Status {
+        name: String,
+        elapsed: Elapsed
+    }}
+
+    let mut status = Status::new();
+    status.name = "job".to_owned();
+    status.elapsed = Elapsed(3);
+    assert_eq!(status.string()?, "{\"name\":\"job\",\"elapsed\":3}");
+
+    // read-only
+    json! {
+    #[json(deserialize_only)]
+    Request {
+        id: u32,
+        query: { text: String? }
+    }}
+
+    let request: Request = native_json::parse("{\"id\":7,\"query\":{}}")?;
+    assert_eq!(request.id, 7);
+    assert_eq!(request.query.text, "");
+
+    Ok(())
+}
+
+#[test]
+fn json_declare_rest_fields() -> Pod {
+    json! {
+    Profile {
+        name: String,
+        settings: {
+            theme: String,
+            ...others: HashMap<String, serde_json::Value>
+        },
+        ...: HashMap<String, serde_json::Value>
+    }}
+
+    let s = "{\"name\":\"Tom\",\"settings\":{\"theme\":\"dark\",\"font\":12},\"age\":20,\"tags\":[\"a\"]}";
+    let profile: Profile = native_json::parse(s)?;
+    assert_eq!(profile.extra["age"], 20);
+    assert_eq!(profile.settings.others["font"], 12);
+
+    // unknown keys survive the round-trip
+    let value: serde_json::Value = native_json::parse(&profile.string()?)?;
+    let expected: serde_json::Value = native_json::parse(s)?;
+    assert_eq!(value, expected);
+
+    assert!(Profile::new().extra.is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn json_keyword_keys() -> Pod {
+    // keywords are raw identifiers, `self`, `super`, `crate` and `Self` get an underscore suffix
+    json! {
+    #[json(builder)]
+    Rule {
+        match: String,
+        type: { ref: String, async: bool },
+        fn: [{ move: i32 }],
+        self: u8
+    }}
+
+    let mut rule = Rule::new();
+    rule.r#match = "*.rs".into();
+    rule.r#type.r#ref = "HEAD".into();
+    rule.r#fn.push(Rule_fn_item { r#move: 1 });
+    rule.self_ = 2;
+    let s = rule.string()?;
+    assert_eq!(
+        s,
+        "{\"match\":\"*.rs\",\"type\":{\"ref\":\"HEAD\",\"async\":false},\"fn\":[{\"move\":1}],\"self\":2}"
+    );
+    let rule: Rule = native_json::parse(&s)?;
+    assert_eq!(rule.r#type.r#ref, "HEAD");
+    assert_eq!(Rule::builder().with_match("a".into()).build().r#match, "a");
+
+    // instance
+    let json = json! {
+        type: "Action",
+        loop: { while: true }
+    };
+    assert_eq!(
+        json.string()?,
+        "{\"type\":\"Action\",\"loop\":{\"while\":true}}"
+    );
+    assert!(json.r#loop.r#while);
+
+    Ok(())
+}
+
+#[test]
+fn json_string_keys() -> Pod {
+    json! {
+    Document {
+        "@timestamp": i64,
+        "content-type": String,
+        "user.agent": { "os name": String },
+        "type": String
+    }}
+
+    let mut doc = Document::new();
+    doc._timestamp = 1;
+    doc.content_type = "text/plain".into();
+    doc.user_agent.os_name = "linux".into();
+    doc.r#type = "log".into();
+    let s = doc.string()?;
+    assert_eq!(
+        s,
+        "{\"@timestamp\":1,\"content-type\":\"text/plain\",\"user.agent\":{\"os name\":\"linux\"},\"type\":\"log\"}"
+    );
+    let doc: Document = native_json::parse(&s)?;
+    assert_eq!(doc.user_agent.os_name, "linux");
+    let _ = Document_user_agent::new();
+
+    // instance
+    let json = json! { "x-request-id": 7 };
+    assert_eq!(json.x_request_id, 7);
+    assert_eq!(json.string()?, "{\"x-request-id\":7}");
+
+    Ok(())
+}
+
+#[test]
+fn json_digit_keys() -> Pod {
+    json! {
+    Kline {
+        1m: f64,
+        24h: { 5xx: u32, 200: u32 }
+    }}
+
+    let s = "{\"1m\":1.5,\"24h\":{\"5xx\":3,\"200\":97}}";
+    let kline: Kline = native_json::parse(s)?;
+    assert_eq!(kline._1m, 1.5);
+    assert_eq!(kline._24h._5xx, 3);
+    assert_eq!(kline._24h._200, 97);
+    assert_eq!(kline.string()?, s);
+    assert_eq!(Kline__24h::new()._5xx, 0);
+
+    // instance
+    let json = json! { 1h: 60, 1d: 1440 };
+    assert_eq!(json.string()?, "{\"1h\":60,\"1d\":1440}");
+
+    Ok(())
+}
+
+#[test]
+fn json_include_sample() -> Pod {
+    include_json!("tests/fixtures/response.json" as ApiResponse);
+
+    let s = std::fs::read_to_string("tests/fixtures/response.json")?;
+    let response: ApiResponse = native_json::parse(&s)?;
+    assert_eq!(response.code, 0);
+    assert_eq!(response.r#type, "order");
+    assert_eq!(response.content_type, "application/json");
+
+    let orders = &response.data.orders;
+    assert_eq!(orders[1].price, 11.0);
+    assert_eq!(orders[0].note, None);
+    assert_eq!(orders[1].note.as_deref(), Some("partial"));
+    assert!(orders[0].tags.is_empty());
+    assert!(response.data.empty.is_empty());
+    assert!(response.data.cursor.is_null());
+
+    let mut order = ApiResponse_data_orders_item::new();
+    order.id = 3;
+    let s = order.string()?;
+    assert_eq!(
+        s,
+        "{\"id\":3,\"price\":0.0,\"note\":null,\"filled\":false,\"tags\":[]}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn json_openapi_components() -> Pod {
+    openapi_json!("tests/fixtures/api.yaml", components = ["Order", "Trade"]);
+
+    let s = r#"{
+        "id": 7,
+        "side": "buy",
+        "price": 10.5,
+        "customer": { "name": "Tom", "email": null },
+        "items": [{ "sku": "A1", "type": "book" }, { "sku": "B2" }],
+        "labels": { "desk": "fx" },
+        "extra": [1, 2]
+    }"#;
+    let order: Order = native_json::parse(s)?;
+    let side: Side = order.side.clone();
+    assert_eq!(side, "buy");
+    assert_eq!(order.id, 7);
+    assert_eq!(order.quantity, 0i32);
+    assert_eq!(order.customer.email, None);
+    assert!(order.referrer.is_none());
+    assert_eq!(order.items[0].r#type, "book");
+    assert_eq!(order.items[1].r#type, "");
+    assert_eq!(order.labels["desk"], "fx");
+    assert_eq!(order.extra, serde_json::json!([1, 2]));
+
+    let mut trade = Trade::new();
+    trade.order = order;
+    trade.fee = Some(0.25);
+    let trade: Trade = native_json::parse(&trade.string()?)?;
+    assert_eq!(trade.order.items[0].sku, "A1");
+    assert_eq!(trade.fee, Some(0.25));
+
+    let mut item = Order_items_item::new();
+    item.sku = "C3".to_owned();
+    assert_eq!(item.string()?, "{\"sku\":\"C3\"}");
+
+    let e = native_json::parse::<Order>("{\"id\": 1}").unwrap_err();
+    assert!(e.to_string().contains("missing field `side`"), "{e}");
+    Ok(())
+}
+
+#[test]
+fn json_type_from_example() -> Pod {
+    json_type! {
+    Config = {
+        host: "localhost",
+        port: 8080 as u16,
+        ratio: 0.5,
+        debug: true,
+        retries: -1i8,
+        proxy: null,
+        style: { color: "red", size: 12 },
+        servers: [{ name: "a", weight: 1 }, { name: "b", weight: 2 }]
+    }}
+
+    let config = Config::example();
+    assert_eq!(config.port, 8080);
+    assert_eq!(config.servers[1].weight, 2);
+    assert_eq!(
+        config.string()?,
+        "{\"host\":\"localhost\",\"port\":8080,\"ratio\":0.5,\"debug\":true,\"retries\":-1,\"proxy\":null,\
+        \"style\":{\"color\":\"red\",\"size\":12},\"servers\":[{\"name\":\"a\",\"weight\":1},{\"name\":\"b\",\"weight\":2}]}"
+    );
+
+    // a reusable named type
+    let mut config = Config::new();
+    config.style = Config_style {
+        color: "blue".into(),
+        size: 14,
+    };
+    config.servers.push(Config_servers_item::new());
+    assert_eq!(config.host, "");
+
+    Ok(())
+}
+
+#[test]
+fn json_type_literals() -> Pod {
+    json_type! {
+    Profile = {
+        name: "known as Bob",
+        mask: 0x1f32,
+        scale: -1.5f32,
+        tags: [] as Vec<String>,
+        rank: null as Option<u8>,
+        alias: "b" as String
+    }}
+
+    let profile = Profile::example();
+    let mask: i64 = profile.mask;
+    let scale: f32 = profile.scale;
+    assert_eq!((mask, scale), (0x1f32, -1.5));
+    assert!(profile.tags.is_empty() && profile.rank.is_none());
+    assert_eq!(
+        profile.string()?,
+        "{\"name\":\"known as Bob\",\"mask\":7986,\"scale\":-1.5,\"tags\":[],\"rank\":null,\"alias\":\"b\"}"
+    );
+    Ok(())
+}
+
+#[test]
+fn json_declare_typescript() {
+    json! {
+    #[json(typescript)]
+    School {
+        name: String,
+        rank: u32?,
+        "content-type": String,
+        students: [{ name: String, age: u16, tags: Vec<String> }],
+        tutor: Option<String>,
+        scores: HashMap<String, f64>,
+        ...: HashMap<String, serde_json::Value>
+    }}
+
+    let expected = "export interface School {
+  name: string;
+  rank?: number;
+  \"content-type\": string;
+  students: School_students_item[];
+  tutor: string | null;
+  scores: Record<string, number>;
+  [key: string]: unknown;
+}
+
+export interface School_students_item {
+  name: string;
+  age: number;
+  tags: string[];
+}
+";
+    assert_eq!(School::typescript(), expected);
+}
+
+#[test]
+fn json_declare_schema() {
+    json! {
+    #[json(schema)]
+    School {
+        name: String,
+        rank: u32?,
+        students: [{ name: String, age: i16 }],
+        tutor: Option<String>,
+        scores: HashMap<String, f64>
+    }}
+
+    let expected = serde_json::json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "School",
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "rank": { "type": "integer", "minimum": 0 },
+            "students": { "type": "array", "items": { "$ref": "#/$defs/School_students_item" } },
+            "tutor": { "anyOf": [{ "type": "string" }, { "type": "null" }] },
+            "scores": { "type": "object", "additionalProperties": { "type": "number" } }
+        },
+        "required": ["name", "students", "scores"],
+        "$defs": {
+            "School_students_item": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "age": { "type": "integer" }
+                },
+                "required": ["name", "age"]
+            }
+        }
+    });
+    assert_eq!(School::schema(), expected);
+
+    // the keys of #[serde(default)] are not required
+    json! {
+    #[json(schema)]
+    Scope {
+        name: String,
+        self: u8,
+        level: u8 @default
+    }}
+
+    let schema = Scope::schema();
+    assert_eq!(schema["required"], serde_json::json!(["name"]));
+    assert!(schema["properties"]["self"].is_object());
+    let scope: Scope = native_json::parse("{\"name\":\"a\"}").unwrap();
+    assert_eq!((scope.self_, scope.level), (0, 0));
+}
+
+#[test]
+fn json_declare_avro() {
+    json! {
+    #[json(avro)]
+    School {
+        name: String,
+        rank: u32?,
+        students: [{ name: String, age: i16, mentor: Option<Box<School_students_item>> }],
+        tutor: Option<String>,
+        scores: HashMap<String, f64>,
+        ratio: f32,
+    }}
+
+    let expected = serde_json::json!({
+        "type": "record",
+        "name": "School",
+        "fields": [
+            { "name": "name", "type": "string" },
+            { "name": "rank", "type": ["null", "long"], "default": null },
+            {
+                "name": "students",
+                "type": {
+                    "type": "array",
+                    "items": {
+                        "type": "record",
+                        "name": "School_students_item",
+                        "fields": [
+                            { "name": "name", "type": "string" },
+                            { "name": "age", "type": "int" },
+                            { "name": "mentor", "type": ["null", "School_students_item"], "default": null }
+                        ]
+                    }
+                }
+            },
+            { "name": "tutor", "type": ["null", "string"], "default": null },
+            { "name": "scores", "type": { "type": "map", "values": "double" } },
+            { "name": "ratio", "type": "float" }
+        ]
+    });
+    assert_eq!(School::avro_schema(), expected);
+}
+
+#[test]
+fn json_declare_utoipa() -> Pod {
+    use utoipa::{OpenApi, PartialSchema, ToSchema};
+
+    json! {
+    #[json(utoipa)]
+    Pet {
+        name: String,
+        age: u32?,
+        "pet-type": String,
+        type_: String,
+        owner: { name: String },
+    }}
+
+    let schema = serde_json::to_value(Pet::schema())?;
+    assert_eq!(
+        schema["required"],
+        serde_json::json!(["name", "pet-type", "owner"])
+    );
+    let properties = schema["properties"].as_object().unwrap();
+    let keys: Vec<_> = properties.keys().collect();
+    assert_eq!(keys, ["age", "name", "owner", "pet-type", "type"]);
+    assert_eq!(
+        properties["owner"]["$ref"],
+        "#/components/schemas/Pet_owner"
+    );
+    assert_eq!(Pet::name(), "Pet");
+
+    #[derive(OpenApi)]
+    #[openapi(components(schemas(Pet)))]
+    struct Api;
+    let api = serde_json::to_value(Api::openapi())?;
+    assert!(api["components"]["schemas"]["Pet_owner"].is_object());
+    Ok(())
+}
+
+#[test]
+fn json_declare_validate() {
+    json! { Club {
+        name: String @min_len(1) @max_len(8),
+        rank: u32? @max(10),
+        members: [{ name: String @min_len(1), age: u16 @range(6, 150) }] @max_len(3)
+    }}
+
+    let mut club = Club::new();
+    club.name = "Chess".to_owned();
+    club.members = vec![Club_members_item {
+        name: "Tom".to_owned(),
+        age: 12,
+    }];
+    assert!(club.validate().is_ok());
+
+    club.name = "".to_owned();
+    club.rank = 11;
+    club.members[0].age = 200;
+    let errors = club.validate().unwrap_err();
+    let paths: Vec<_> = errors.errors.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, ["name", "rank", "members[0].age"]);
+    assert_eq!(errors.errors[2].message, "must be in range 6..=150");
+    assert_eq!(
+        errors.to_string(),
+        "name: length must be at least 1; rank: must be at most 10; members[0].age: must be in range 6..=150"
+    );
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn json_declare_validate_regex() {
+    json! { Account {
+        email: String @regex("^[^@]+@[^@]+$")
+    }}
+
+    let mut account = Account::new();
+    account.email = "tom@example.com".to_owned();
+    assert!(account.validate().is_ok());
+    account.email = "tom".to_owned();
+    assert!(account.validate().is_err());
+}
+
+#[test]
+fn json_declare_validate_option() {
+    json! { Club {
+        rank: Option<u32> @max(10),
+        name: Option<String> @min_len(1) @max_len(8)
+    }}
+
+    let mut club = Club::new();
+    assert!(club.validate().is_ok());
+    club.rank = Some(11);
+    club.name = Some("".to_owned());
+    let errors = club.validate().unwrap_err();
+    let paths: Vec<_> = errors.errors.iter().map(|e| e.path.as_str()).collect();
+    assert_eq!(paths, ["rank", "name"]);
 }
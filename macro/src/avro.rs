@@ -0,0 +1,132 @@
+use crate::json::*;
+use serde_json::{json, Value as V};
+
+//------------------- Avro Schema ------------------------------
+//
+// #[json(avro)] generates Name::avro_schema(), the Apache Avro schema (.avsc) of the serialized shape
+//
+// i8 .. i32, u8, u16  => "int"
+// i64, u32, u64, ...  => "long"
+// f32, f64            => "float", "double"
+// String, char        => "string"
+// decimals, dates     => "string" of the serialized text
+// Uuid                => { "type": "string", "logicalType": "uuid" }
+// bool                => "boolean"
+// Vec<T>, [T]         => { "type": "array", "items": T }
+// HashMap<String, V>  => { "type": "map", "values": V }
+// Option<T>, `type?`  => ["null", T] with the default null
+// bytes, @base64      => "string" of base64
+// nested struct       => { "type": "record", ... } at the first use, the name afterwards
+// others              => the name of the type, which is defined by another schema
+
+struct Avro<'a> {
+    structs: &'a [Struct],
+    // records which are defined, the later uses are by the names
+    defined: Vec<String>,
+}
+
+impl Avro<'_> {
+    fn record(&mut self, s: &Struct) -> V {
+        self.defined.push(s.name.clone());
+        let mut fields = Vec::new();
+        for f in &s.fields {
+            let mut schema = self.schema(&f.class);
+            for a in &f.annotations {
+                let unix = a.args.iter().any(|x| x.starts_with("unix"));
+                if a.name == "format" && unix {
+                    schema = json!("long");
+                } else if a.name == "base64" || a.name == "string" {
+                    schema = json!("string");
+                }
+            }
+            let nullable = schema
+                .as_array()
+                .is_some_and(|x| x.first() == Some(&json!("null")));
+            if f.optional && !nullable {
+                schema = json!(["null", schema]);
+            }
+            let mut field = json!({ "name": f.key, "type": schema });
+            // the missing value of the union is null
+            if f.optional || nullable {
+                field["default"] = V::Null;
+            }
+            fields.push(field);
+        }
+        return json!({ "type": "record", "name": s.name, "fields": fields });
+    }
+
+    // schema of a rust type
+    fn schema(&mut self, class: &str) -> V {
+        const INT: [&str; 5] = ["i8", "i16", "i32", "u8", "u16"];
+        const LONG: [&str; 7] = ["i64", "i128", "isize", "u32", "u64", "u128", "usize"];
+
+        let class = class.trim_start_matches('&').trim();
+        if class.starts_with('[') && class.ends_with(']') {
+            let item = self.schema(&class[1..class.len() - 1]);
+            return json!({ "type": "array", "items": item });
+        }
+
+        if self.defined.iter().any(|x| x == class) {
+            return json!(class);
+        } else if let Some(s) = self.structs.iter().find(|x| x.name == class) {
+            return self.record(s);
+        }
+
+        let (base, args) = split_generic(class);
+        let name = base_type(base);
+        let mut arg = |i: usize| match args.get(i) {
+            Some(x) => self.schema(x),
+            None => json!("string"),
+        };
+
+        if INT.contains(&name) {
+            return json!("int");
+        } else if LONG.contains(&name) {
+            return json!("long");
+        }
+        return match name {
+            "f32" => json!("float"),
+            "f64" => json!("double"),
+            "bool" => json!("boolean"),
+            "String" | "str" | "Cow" | "PathBuf" | "char" | "Decimal" | "BigDecimal" => {
+                json!("string")
+            }
+            "DateTime" | "NaiveDateTime" | "OffsetDateTime" | "NaiveDate" | "NaiveTime" => {
+                json!("string")
+            }
+            "IpAddr" | "Ipv4Addr" | "Ipv6Addr" | "SocketAddr" | "SocketAddrV4" | "SocketAddrV6" => {
+                json!("string")
+            }
+            "Uuid" => json!({ "type": "string", "logicalType": "uuid" }),
+            "Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet" => {
+                json!({ "type": "array", "items": arg(0) })
+            }
+            "Option" => match arg(0) {
+                // unions are not nested
+                V::Array(mut types) => {
+                    types.retain(|x| x != "null");
+                    types.insert(0, json!("null"));
+                    V::Array(types)
+                }
+                t => json!(["null", t]),
+            },
+            "HashMap" | "BTreeMap" | "IndexMap" | "OrderedMap" => {
+                json!({ "type": "map", "values": arg(1) })
+            }
+            "Box" | "Rc" | "Arc" => arg(0),
+            "()" => json!("null"),
+            _ => json!(name),
+        };
+    }
+}
+
+impl Json {
+    pub fn gen_avro(&self) -> String {
+        let structs = self.get_structs();
+        let mut avro = Avro {
+            structs: &structs,
+            defined: Vec::new(),
+        };
+        return avro.record(&structs[0]).to_string();
+    }
+}
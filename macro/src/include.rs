@@ -0,0 +1,218 @@
+use crate::json::*;
+use std::path::PathBuf;
+use syn::{
+    parse::{Parse, ParseStream},
+    *,
+};
+
+//------------------- include_json! Syntax ------------------------------
+//
+// include = #[json(option, ...)] "path" as Name
+//
+// The path is relative to the crate root, field types are inferred from the sample:
+//
+// string  => String
+// number  => i64 | u64 | f64
+// boolean => bool
+// null    => Option<type> or serde_json::Value
+// array   => [item], items are merged, keys missing from some objects are optional
+// object  => { key: type, ... }
+// mixed   => serde_json::Value
+
+// inferred type of a JSON value
+#[derive(Clone, PartialEq)]
+enum Shape {
+    Unknown,
+    Null,
+    Bool,
+    Int,
+    UInt,
+    Float,
+    String,
+    Any,
+    Nullable(Box<Shape>),
+    Array(Box<Shape>),
+    // (key, shape, optional)
+    Object(Vec<(String, Shape, bool)>),
+}
+
+impl Shape {
+    fn from(value: &serde_json::Value) -> Self {
+        use serde_json::Value as V;
+        return match value {
+            V::Null => Shape::Null,
+            V::Bool(_) => Shape::Bool,
+            V::Number(n) if n.is_i64() => Shape::Int,
+            V::Number(n) if n.is_u64() => Shape::UInt,
+            V::Number(_) => Shape::Float,
+            V::String(_) => Shape::String,
+            V::Array(items) => {
+                let item = items
+                    .iter()
+                    .map(Shape::from)
+                    .fold(Shape::Unknown, Shape::merge);
+                Shape::Array(Box::new(item))
+            }
+            V::Object(map) => {
+                let fields = map
+                    .iter()
+                    .map(|(k, v)| (k.clone(), Shape::from(v), false))
+                    .collect();
+                Shape::Object(fields)
+            }
+        };
+    }
+
+    // the shape which is able to hold both samples
+    fn merge(self, other: Shape) -> Shape {
+        use Shape::*;
+        return match (self, other) {
+            (Unknown, x) | (x, Unknown) => x,
+            (a, b) if a == b => a,
+            (Null, Nullable(x)) | (Nullable(x), Null) => Nullable(x),
+            (Null, x) | (x, Null) => Nullable(Box::new(x)),
+            (Nullable(a), b) | (b, Nullable(a)) => Nullable(Box::new(a.merge(b))),
+            (Int | UInt | Float, Int | UInt | Float) => Float,
+            (Array(a), Array(b)) => Array(Box::new(a.merge(*b))),
+            (Object(a), Object(b)) => {
+                let mut fields = Vec::new();
+                for (key, shape, optional) in &a {
+                    match b.iter().find(|x| &x.0 == key) {
+                        Some(x) => {
+                            let merged = shape.clone().merge(x.1.clone());
+                            fields.push((key.clone(), merged, *optional || x.2));
+                        }
+                        None => fields.push((key.clone(), shape.clone(), true)),
+                    }
+                }
+                for (key, shape, _) in b {
+                    if !a.iter().any(|x| x.0 == key) {
+                        fields.push((key, shape, true));
+                    }
+                }
+                Object(fields)
+            }
+            _ => Any,
+        };
+    }
+
+    // scalar type name
+    fn type_name(&self) -> String {
+        return match self {
+            Shape::Bool => "bool".to_owned(),
+            Shape::Int => "i64".to_owned(),
+            Shape::UInt => "u64".to_owned(),
+            Shape::Float => "f64".to_owned(),
+            Shape::String => "String".to_owned(),
+            Shape::Nullable(x) if x.is_scalar() => format!("Option<{}>", x.type_name()),
+            _ => "serde_json::Value".to_owned(),
+        };
+    }
+
+    fn is_scalar(&self) -> bool {
+        return matches!(
+            self,
+            Shape::Bool | Shape::Int | Shape::UInt | Shape::Float | Shape::String
+        );
+    }
+}
+
+pub struct Include {
+    pub json: Json,
+    pub path: PathBuf,
+}
+
+impl Include {
+    // lower the inferred shape into the declaration of json!
+    fn declare(json: &mut Json, shape: &Shape, optional: bool, file: &LitStr) -> Value {
+        match shape {
+            Shape::Object(fields) => {
+                let mut object = Object::new();
+                for (key, shape, optional) in fields {
+                    let name = sanitize(key);
+                    let rename = if &name == key {
+                        None
+                    } else {
+                        Some(key.clone())
+                    };
+                    let value = Include::declare(json, shape, *optional, file);
+                    let mut annotations = Vec::new();
+                    if *optional && value.t != ValueType::EXPRESSION {
+                        // missing object or array takes the default value
+                        annotations.push(Annotation {
+                            name: "default".to_owned(),
+                            args: Vec::new(),
+                        });
+                    }
+                    object.pairs.push(Pair {
+                        key: Ident::new(&name, file.span()),
+                        rename,
+                        value,
+                        annotations,
+                        attributes: Vec::new(),
+                    });
+                }
+                return json.append_object(object);
+            }
+            Shape::Array(item) => {
+                let mut array = Array::new();
+                array.items.push(Include::declare(json, item, false, file));
+                return json.append_array(array);
+            }
+            Shape::Nullable(x) if !x.is_scalar() => {
+                // nested object or array is not nullable
+                return Include::declare(json, x, optional, file);
+            }
+            _ => {
+                let mut t = shape.type_name();
+                // missing from some samples
+                if optional {
+                    t += "?";
+                }
+                return json.append_expression(t);
+            }
+        }
+    }
+}
+
+impl Parse for Include {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut json = Json::new();
+        json.parse_attributes(input)?;
+
+        let file: LitStr = input.parse()?;
+        input.parse::<Token![as]>()?;
+        let name: Ident = input.parse()?;
+
+        // relative to the crate root
+        let mut path = PathBuf::from(file.value());
+        if path.is_relative() {
+            if let Ok(root) = std::env::var("CARGO_MANIFEST_DIR") {
+                path = PathBuf::from(root).join(path);
+            }
+        }
+
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            let error = format!("failed to read {}: {}", path.display(), e);
+            Error::new(file.span(), error)
+        })?;
+        let sample: serde_json::Value = serde_json::from_str(&text).map_err(|e| {
+            let error = format!("failed to parse {}: {}", path.display(), e);
+            Error::new(file.span(), error)
+        })?;
+
+        if !matches!(sample, serde_json::Value::Object(_)) {
+            return Err(Error::new(file.span(), "expected a JSON object"));
+        }
+
+        let shape = Shape::from(&sample);
+        let value = Include::declare(&mut json, &shape, false, &file);
+        json.get_object_mut(&value).name = name.to_string();
+        json.value = Value {
+            t: ValueType::DECLARE,
+            i: value.i,
+        };
+
+        return Ok(Include { json, path });
+    }
+}
@@ -0,0 +1,221 @@
+use crate::json::*;
+use proc_macro2::Span;
+use quote::ToTokens;
+use syn::{
+    parse::{Parse, ParseStream},
+    *,
+};
+
+//------------------- json_type! Syntax ------------------------------
+//
+// json_type = #[json(option, ...)] Name = { key: value, ... }
+//
+// The field types are inferred from the example values:
+//
+// "string"        => String
+// 1, -1, 1u16     => i64 or the literal suffix
+// 1.5             => f64
+// true            => bool
+// 'c'             => char
+// null            => Option<serde_json::Value>
+// expr as type    => type
+// [] as type      => type, so as {} as type
+// [value, ...]    => [type of the first item]
+// { key: value }  => nested object
+
+pub struct JsonType {
+    pub json: Json,
+    // the example in instance syntax
+    pub example: Value,
+}
+
+// compact text of the tokens, the same as the parsed expressions
+fn tokens_text<T: ToTokens>(tokens: &T) -> String {
+    let mut s = String::new();
+    for tt in tokens.to_token_stream() {
+        push_token(&mut s, &tt.to_string());
+    }
+    return s;
+}
+
+fn is_null(expr: &Expr) -> bool {
+    match expr {
+        Expr::Path(x) => x.qself.is_none() && (x.path.is_ident("null") || x.path.is_ident("None")),
+        _ => false,
+    }
+}
+
+// type of a literal expression
+fn infer_type(expr: &Expr) -> Option<String> {
+    let class = match expr {
+        // explicit type
+        Expr::Cast(x) => return Some(tokens_text(&x.ty)),
+        // empty container, e.g. `[] as Vec<String>` is `<Vec<String>>::default()`
+        Expr::Call(x) if x.args.is_empty() => match &*x.func {
+            Expr::Path(ExprPath {
+                qself: Some(q),
+                path,
+                ..
+            }) if path.segments.len() == 1 && path.segments[0].ident == "default" => {
+                return Some(tokens_text(&q.ty));
+            }
+            _ => return None,
+        },
+        Expr::Unary(ExprUnary {
+            op: UnOp::Neg(_),
+            expr,
+            ..
+        }) => {
+            return match &**expr {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(_) | Lit::Float(_),
+                    ..
+                }) => infer_type(expr),
+                _ => None,
+            };
+        }
+        _ if is_null(expr) => "Option<serde_json::Value>",
+        Expr::Lit(x) => match &x.lit {
+            Lit::Str(_) => "String",
+            Lit::Char(_) => "char",
+            Lit::Bool(_) => "bool",
+            // the suffix of hex digits, e.g. `0x1f32` is an i64
+            Lit::Int(x) if x.suffix().is_empty() => "i64",
+            Lit::Int(x) => x.suffix(),
+            Lit::Float(x) if x.suffix().is_empty() => "f64",
+            Lit::Float(x) => x.suffix(),
+            _ => return None,
+        },
+        _ => return None,
+    };
+    return Some(class.to_owned());
+}
+
+impl JsonType {
+    // declaration of the example value
+    fn declare(json: &mut Json, value: &Value, span: Span) -> Result<Value> {
+        match value.t {
+            ValueType::OBJECT => {
+                let mut object = Object::new();
+                let count = json.get_object(value).pairs.len();
+                for i in 0..count {
+                    let pair = &json.get_object(value).pairs[i];
+                    let key = pair.key.clone();
+                    let rename = pair.rename.clone();
+                    let v = Value {
+                        t: pair.value.t,
+                        i: pair.value.i,
+                    };
+                    let declared = JsonType::declare(json, &v, key.span())?;
+                    object.pairs.push(Pair {
+                        key,
+                        rename,
+                        value: declared,
+                        annotations: Vec::new(),
+                        attributes: Vec::new(),
+                    });
+                }
+                return Ok(json.append_object(object));
+            }
+            ValueType::ARRAY => {
+                let first = json.get_array(value).items.first();
+                let first = match first {
+                    Some(x) => Value { t: x.t, i: x.i },
+                    None => return Err(Error::new(span, "cannot infer type of empty array")),
+                };
+                let mut array = Array::new();
+                array.items.push(JsonType::declare(json, &first, span)?);
+                return Ok(json.append_array(array));
+            }
+            _ => {
+                let expr = json.get_expression(value).clone();
+                let parsed = syn::parse_str::<Expr>(&expr).ok();
+                return match parsed.as_ref().and_then(infer_type) {
+                    Some(t) => Ok(json.append_expression(t)),
+                    None => {
+                        let error = format!("cannot infer type of `{expr}`, use `{expr} as Type`");
+                        Err(Error::new(span, error))
+                    }
+                };
+            }
+        }
+    }
+
+    // Name::example() builds the example value
+    fn gen_example(&self, path: &str, value: &Value) -> String {
+        let json = &self.json;
+        match value.t {
+            ValueType::OBJECT => {
+                let object = json.get_object(value);
+                let mut fields = Vec::new();
+                for pair in &object.pairs {
+                    let child = format!("{}_{}", path, pair.name());
+                    let v = self.gen_example(&child, &pair.value);
+                    fields.push(format!("{}: {}", pair.field(), v));
+                }
+                return format!("{} {{ {} }}", path, fields.join(", "));
+            }
+            ValueType::ARRAY => {
+                let array = json.get_array(value);
+                let child = format!("{}_item", path);
+                let items: Vec<_> = array
+                    .items
+                    .iter()
+                    .map(|x| self.gen_example(&child, x))
+                    .collect();
+                return format!("std::vec![{}]", items.join(", "));
+            }
+            _ => {
+                let expr = json.get_expression(value);
+                // the literal of the explicit type
+                let literal = match syn::parse_str::<Expr>(expr) {
+                    Ok(Expr::Cast(x)) => *x.expr,
+                    Ok(x) => x,
+                    Err(_) => return expr.clone(),
+                };
+                if is_null(&literal) {
+                    return "None".to_owned();
+                } else if let Expr::Lit(ExprLit {
+                    lit: Lit::Str(s), ..
+                }) = &literal
+                {
+                    return format!("{}.into()", tokens_text(s));
+                }
+                return expr.clone();
+            }
+        }
+    }
+
+    pub fn get_block(&self) -> Result<String> {
+        let name = self.json.get_object(&self.json.value).name.clone();
+        let mut code = self.json.get_block()?;
+        let example = self.gen_example(&name, &self.example);
+        code += &format!(
+            "impl {name} {{\n    pub fn example() -> Self {{\n        {example}\n    }}\n}}\n"
+        );
+        return Ok(code);
+    }
+}
+
+impl Parse for JsonType {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut json = Json::new();
+        json.parse_attributes(input)?;
+
+        let name: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        if !input.peek(syn::token::Brace) {
+            return Err(input.error("expected { key: value, ... }"));
+        }
+        let example = json.parse_object(input)?;
+
+        let value = JsonType::declare(&mut json, &example, name.span())?;
+        json.get_object_mut(&value).name = name.to_string();
+        json.value = Value {
+            t: ValueType::DECLARE,
+            i: value.i,
+        };
+
+        return Ok(JsonType { json, example });
+    }
+}
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 use syn::{
+    ext::IdentExt,
     parse::{Parse, ParseStream},
     *,
 };
@@ -7,12 +9,51 @@ use syn::{
 //------------------- JSON Syntax ------------------------------
 //
 // object = { pair, ...}
-// pair = key : value
-// key = identifier
+// pair = key : value | ...rest : type
+// key = identifier | "string" | digits
 // array  = [value, ...]
 // value =  object | array | expression
 // expression = string | number | identifier
 
+// keywords which require raw identifiers as field names
+const KEYWORDS: [&str; 47] = [
+    "as", "break", "const", "continue", "else", "enum", "extern", "false", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static",
+    "struct", "trait", "true", "type", "unsafe", "use", "where", "while", "async", "await", "dyn",
+    "abstract", "become", "box", "do", "final", "macro", "override", "priv", "typeof", "unsized",
+    "virtual", "yield", "try",
+];
+
+// keywords which are not allowed as raw identifiers
+const SPECIAL_KEYWORDS: [&str; 4] = ["self", "Self", "super", "crate"];
+
+// annotations of validation rules
+pub const VALIDATIONS: [&str; 6] = ["range", "min", "max", "min_len", "max_len", "regex"];
+
+// options of #[json(...)]
+const OPTIONS: [&str; 10] = [
+    // generate `NameBuilder` with chainable setters
+    "builder",
+    // reject unknown keys when deserialize
+    "deny_unknown_fields",
+    // generate `Name::typescript()`
+    "typescript",
+    // generate `Name::schema()`
+    "schema",
+    // generate `Name::avro_schema()`
+    "avro",
+    // derive `Serialize` only
+    "serialize_only",
+    // derive `Deserialize` only
+    "deserialize_only",
+    // generate `NamePatch` and `Name::apply(patch)`
+    "patch",
+    // generate `impl native_json::Reflect`
+    "reflect",
+    // derive `utoipa::ToSchema`
+    "utoipa",
+];
+
 const ATTRIBUTES: &str =
     "#[derive(Serialize, Deserialize, Debug, Clone)]\n#[allow(non_camel_case_types)]\n";
 
@@ -27,21 +68,48 @@ pub enum ValueType {
 
 pub struct Array {
     pub items: Vec<Value>,
+    // declared type of items in instance mode
+    pub class: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct Annotation {
+    pub name: String,
+    pub args: Vec<String>,
 }
 
 pub struct Pair {
     pub key: Ident,
+    // JSON key which is not a valid identifier
+    pub rename: Option<String>,
     pub value: Value,
+    pub annotations: Vec<Annotation>,
+    // #[cfg(...)] of field in declaration
+    pub attributes: Vec<String>,
 }
 
 pub struct Object {
     pub name: String,
     pub pairs: Vec<Pair>,
+    // ..base, the fields are overridden by pairs
+    pub spread: Option<String>,
+    // `..base` after the pairs, Rust struct update which is only allowed in typed objects
+    pub update: Option<proc_macro2::Span>,
+    // declared type in instance mode
+    pub class: Option<String>,
+    // Name extends Base
+    pub extends: Option<String>,
+    // impl { fn ... } of declaration
+    pub methods: Vec<String>,
 }
 
 pub struct Json {
     pub value: Value,
     pub id: i32,
+    // #[json(option, ...)] of declaration
+    pub options: Vec<String>,
+    // (visibility, name) of `mod name { declaration }`
+    module: Option<(String, String)>,
     objects: Vec<Object>,
     arrays: Vec<Array>,
     expressions: Vec<String>,
@@ -52,9 +120,210 @@ pub struct Value {
     pub i: usize,
 }
 
+// generated struct, the input of generators other than serde
+pub struct Struct {
+    pub name: String,
+    pub fields: Vec<Field>,
+    // Name extends Base, the field `base` is not included
+    pub extends: Option<String>,
+}
+
+pub struct Field {
+    // field identifier
+    pub field: String,
+    // key of JSON
+    pub key: String,
+    // rust type, the optional suffix `?` is removed
+    pub class: String,
+    pub optional: bool,
+    // #[serde(default)], the absent key is accepted
+    pub default: bool,
+    pub annotations: Vec<Annotation>,
+    // #[cfg(...)]
+    pub attributes: Vec<String>,
+}
+
+// annotations which replace the (de)serializer of the field
+pub const WITH: [&str; 6] = [
+    "string",
+    "base64",
+    "format",
+    "with",
+    "serialize_with",
+    "deserialize_with",
+];
+
+// serde attribute of the annotations in `WITH`, e.g. `with = "native_json::string"`
+pub fn serde_with(a: &Annotation) -> std::result::Result<String, String> {
+    match a.name.as_str() {
+        "string" if a.args.is_empty() => {
+            return Ok("with = \"native_json::string\"".to_owned());
+        }
+        "base64" => {
+            let alphabet = a.args.first().map(|x| x.trim_matches('"'));
+            let module = match alphabet {
+                None | Some("standard") => "standard",
+                Some("standard_no_pad") => "standard_no_pad",
+                Some("url_safe") => "url_safe",
+                Some("url_safe_no_pad") => "url_safe_no_pad",
+                _ => {
+                    let error =
+                        "expected @base64(standard | standard_no_pad | url_safe | url_safe_no_pad)";
+                    return Err(error.to_owned());
+                }
+            };
+            return Ok(format!("with = \"native_json::base64::{module}\""));
+        }
+        // custom (de)serializer, a module or function path
+        "with" | "serialize_with" | "deserialize_with" => {
+            let path = match a.args.first() {
+                Some(x) if a.args.len() == 1 => x.trim_matches('"'),
+                _ => return Err(format!("expected @{}(path)", a.name)),
+            };
+            return Ok(format!("{} = {:?}", a.name, path));
+        }
+        "format" => {
+            let format = a.args.first().map(|x| x.trim_matches('"'));
+            let module = match format {
+                Some("rfc3339") => "rfc3339",
+                Some("unix") => "unix",
+                Some("unix_ms") => "unix_ms",
+                _ => return Err("expected @format(rfc3339 | unix | unix_ms)".to_owned()),
+            };
+            return Ok(format!("with = \"native_json::datetime::{module}\""));
+        }
+        _ => return Err(format!("unknown annotation @{}", a.name)),
+    }
+}
+
+// append token text, words are kept apart, e.g. `5 as u8`
+pub fn push_token(s: &mut String, token: &str) {
+    let word = |c: char| c.is_alphanumeric() || c == '_' || c == '"';
+    if let (Some(a), Some(b)) = (s.chars().last(), token.chars().next()) {
+        if word(a) && word(b) {
+            s.push(' ');
+        }
+    }
+    s.push_str(token);
+}
+
+// valid identifier from any JSON key, e.g. `content-type` is `content_type`
+pub fn sanitize(name: &str) -> String {
+    let mut s: String = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    if s.is_empty() || s == "_" || s.starts_with(|c: char| c.is_ascii_digit()) {
+        s.insert(0, '_');
+    }
+    return s;
+}
+
+// generic type := base<arg, ...>, e.g. `HashMap<String,Vec<i32>>` is (`HashMap`, [`String`, `Vec<i32>`])
+pub fn split_generic(class: &str) -> (&str, Vec<&str>) {
+    let class = class.trim();
+    let start = match class.find("<") {
+        Some(i) if class.ends_with(">") => i,
+        _ => return (class, Vec::new()),
+    };
+    let mut args = Vec::new();
+    let mut nested = 0;
+    let mut begin = start + 1;
+    for (i, c) in class.char_indices().skip(begin) {
+        match c {
+            '<' | '(' | '[' => nested += 1,
+            '>' | ')' | ']' if nested > 0 => nested -= 1,
+            ',' if nested == 0 => {
+                args.push(class[begin..i].trim());
+                begin = i + 1;
+            }
+            _ => {}
+        }
+    }
+    args.push(class[begin..class.len() - 1].trim());
+    return (&class[0..start], args);
+}
+
+// `bytes` is Vec<u8> which is serialized as base64 string
+fn bytes_type(class: &str) -> Option<String> {
+    let optional = class.ends_with('?');
+    if class.trim_end_matches('?') != "bytes" {
+        return None;
+    }
+    let suffix = if optional { "?" } else { "" };
+    return Some(format!("std::vec::Vec<u8>{suffix}"));
+}
+
+// `raw` is the unparsed JSON text, `raw?` is absent by default
+fn raw_type(class: &str) -> Option<String> {
+    const RAW: &str = "std::boxed::Box<serde_json::value::RawValue>";
+    return match class {
+        "raw" => Some(RAW.to_owned()),
+        "raw?" => Some(format!("std::option::Option<{RAW}>?")),
+        _ => None,
+    };
+}
+
+// type name without path and generics, e.g. `chrono::DateTime<Utc>` is `DateTime`
+pub fn base_type(class: &str) -> &str {
+    let end = class.find("<").unwrap_or(class.len());
+    let name = &class[0..end];
+    return match name.rfind("::") {
+        Some(i) => &name[i + 2..],
+        None => name,
+    };
+}
+
+impl Pair {
+    // identifier of the struct field, keywords are escaped as raw identifiers
+    pub fn field(&self) -> String {
+        let name = self.name();
+        if SPECIAL_KEYWORDS.contains(&name.as_str()) {
+            // can not be raw identifiers, renamed by the underscore suffix
+            return format!("{name}_");
+        } else if KEYWORDS.contains(&name.as_str()) {
+            return format!("r#{name}");
+        }
+        return name;
+    }
+
+    // condition of @if(condition) in instance mode
+    pub fn condition(&self) -> Option<String> {
+        let condition = self.annotations.iter().find(|x| x.name == "if");
+        return condition.map(|x| x.args.join(", "));
+    }
+
+    // key name without the raw prefix `r#`
+    pub fn name(&self) -> String {
+        return self.key.unraw().to_string();
+    }
+
+    // #[serde(default)] besides the optional suffix, the keywords renamed by the underscore suffix and @default
+    pub fn has_default(&self) -> bool {
+        let suffix = self.rename.is_none() && self.rename().is_some();
+        return suffix || self.annotations.iter().any(|a| a.name == "default");
+    }
+
+    // JSON key if it differs from the field name
+    pub fn rename(&self) -> Option<String> {
+        if self.rename.is_some() {
+            return self.rename.clone();
+        }
+        // underscore suffix
+        let key = self.field();
+        if key.len() > 1 && key.ends_with("_") {
+            return Some(key[0..key.len() - 1].to_owned());
+        }
+        return None;
+    }
+}
+
 impl Array {
     pub fn new() -> Self {
-        Self { items: Vec::new() }
+        Self {
+            items: Vec::new(),
+            class: None,
+        }
     }
 }
 
@@ -63,6 +332,26 @@ impl Object {
         Self {
             name: "".to_string(),
             pairs: Vec::new(),
+            spread: None,
+            update: None,
+            class: None,
+            extends: None,
+            methods: Vec::new(),
+        }
+    }
+
+    // key type of the map shorthand `{String: type}` in declaration
+    pub fn map_key(&self) -> Option<String> {
+        const KEYS: [&str; 13] = [
+            "String", "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64",
+            "u128", "usize",
+        ];
+        match self.pairs.as_slice() {
+            [pair] if self.spread.is_none() && self.methods.is_empty() && pair.rename.is_none() => {
+                let key = pair.key.to_string();
+                KEYS.contains(&key.as_str()).then_some(key)
+            }
+            _ => None,
         }
     }
 }
@@ -78,12 +367,12 @@ impl ClassDict {
         }
     }
 
-    fn set(&mut self, key: &String, value: &Value) {
+    fn set(&mut self, key: &str, value: &Value) {
         let v = Value {
             t: value.t,
             i: value.i,
         };
-        self.map.insert(key.clone(), v);
+        self.map.insert(key.to_owned(), v);
     }
 }
 
@@ -95,20 +384,39 @@ impl Json {
                 i: 0,
             },
             id: 0,
+            options: Vec::new(),
+            module: None,
             objects: Vec::new(),
             arrays: Vec::new(),
             expressions: Vec::new(),
         };
     }
 
+    pub fn has_option(&self, name: &str) -> bool {
+        return self.options.iter().any(|x| x == name);
+    }
+
     pub fn get_object(&self, v: &Value) -> &Object {
         return &self.objects[v.i];
     }
 
+    // (key type, value) of the nested map shorthand `{String: type}`, the root is always a struct
+    pub fn get_map(&self, v: &Value) -> Option<(String, &Value)> {
+        if v.t != ValueType::OBJECT {
+            return None;
+        }
+        let object = self.get_object(v);
+        return object.map_key().map(|key| (key, &object.pairs[0].value));
+    }
+
     pub fn get_object_mut(&mut self, v: &Value) -> &mut Object {
         return &mut self.objects[v.i];
     }
 
+    pub fn get_array_mut(&mut self, v: &Value) -> &mut Array {
+        return &mut self.arrays[v.i];
+    }
+
     pub fn get_array(&self, v: &Value) -> &Array {
         return &self.arrays[v.i];
     }
@@ -117,21 +425,21 @@ impl Json {
         return &self.expressions[v.i];
     }
 
-    fn append_object(&mut self, v: Object) -> Value {
+    pub fn append_object(&mut self, v: Object) -> Value {
         self.objects.push(v);
         let i = self.objects.len() - 1;
         let t = ValueType::OBJECT;
         return Value { t, i };
     }
 
-    fn append_array(&mut self, v: Array) -> Value {
+    pub fn append_array(&mut self, v: Array) -> Value {
         self.arrays.push(v);
         let i = self.arrays.len() - 1;
         let t = ValueType::ARRAY;
         return Value { t, i };
     }
 
-    fn append_expression(&mut self, v: String) -> Value {
+    pub fn append_expression(&mut self, v: String) -> Value {
         self.expressions.push(v);
         let i = self.expressions.len() - 1;
         let t = ValueType::EXPRESSION;
@@ -155,7 +463,7 @@ impl Json {
                 } else if token == ">" {
                     nested -= 1;
                 }
-                s += &token;
+                push_token(&mut s, &token);
 
                 // peek
                 let mut peek = "".to_owned();
@@ -163,8 +471,8 @@ impl Json {
                     peek = lookhead.to_string();
                 }
 
-                // terminal
-                if nested == 0 && (peek == "," || next.eof()) {
+                // terminal, annotations start with `@`
+                if nested == 0 && (peek == "," || peek == "@" || next.eof()) {
                     return Ok((s, next));
                 }
 
@@ -183,19 +491,196 @@ impl Json {
         return Ok(value);
     }
 
+    // pair := #[cfg(...)] key: value
     fn parse_pair(&mut self, input: ParseStream) -> Result<Pair> {
+        let mut attributes = Vec::new();
+        for attr in input.call(Attribute::parse_outer)? {
+            if !attr.path.is_ident("cfg") {
+                let error = "only #[cfg(...)] is allowed on fields";
+                return Err(Error::new_spanned(attr.path, error));
+            }
+            attributes.push(format!("#[cfg{}]", attr.tokens));
+        }
+        let mut pair = self.parse_field(input)?;
+        pair.attributes = attributes;
+        return Ok(pair);
+    }
+
+    fn parse_field(&mut self, input: ParseStream) -> Result<Pair> {
+        // rest := ...: type, all the other keys are flattened into it
+        let mut rest = false;
+        if input.peek(Token![...]) {
+            let dots = input.parse::<Token![...]>()?;
+            rest = true;
+            if input.peek(Token![:]) {
+                let key = Ident::new("extra", dots.spans[0]);
+                return self.parse_rest(input, key);
+            }
+        }
         // key
-        let key: Ident = input.parse()?;
+        let (key, rename) = self.parse_key(input)?;
+        if rest {
+            return self.parse_rest(input, key);
+        }
+        // shorthand := identifier, the value is the variable of the same name
+        let shorthand = input.is_empty() || input.peek(Token![,]);
+        if shorthand && rename.is_none() {
+            let value = self.append_expression(key.to_string());
+            return Ok(Pair {
+                key,
+                rename,
+                value,
+                annotations: Vec::new(),
+                attributes: Vec::new(),
+            });
+        }
         // :
         input.parse::<Token![:]>()?;
         // value
-        let value = self.parse_value(&input)?;
+        let value = self.parse_value(input)?;
+        // @annotation(args), ...
+        let annotations = self.parse_annotations(input)?;
+
+        return Ok(Pair {
+            key,
+            rename,
+            value,
+            annotations,
+            attributes: Vec::new(),
+        });
+    }
+
+    // key := identifier | keyword | "string" | digits, e.g. `1m`, `24h`, `5xx`
+    fn parse_key(&mut self, input: ParseStream) -> Result<(Ident, Option<String>)> {
+        if input.peek(LitStr) {
+            let lit: LitStr = input.parse()?;
+            let name = lit.value();
+            let key = Ident::new(&sanitize(&name), lit.span());
+            return Ok((key, Some(name)));
+        } else if input.peek(LitInt) || input.peek(LitFloat) {
+            // number with suffix, the field is prefixed by underscore
+            let lit: Lit = input.parse()?;
+            let name = match &lit {
+                Lit::Int(x) => x.to_string(),
+                Lit::Float(x) => x.to_string(),
+                _ => unreachable!(),
+            };
+            let key = Ident::new(&sanitize(&name), lit.span());
+            return Ok((key, Some(name)));
+        }
+        let key = Ident::parse_any(input)?;
+        return Ok((key, None));
+    }
+
+    fn parse_rest(&mut self, input: ParseStream, key: Ident) -> Result<Pair> {
+        // the rest collects the unknown keys, serde would reject them all
+        if self.has_option("deny_unknown_fields") {
+            let error =
+                "`...rest` collects the unknown keys, it conflicts with deny_unknown_fields";
+            return Err(Error::new(key.span(), error));
+        }
+        input.parse::<Token![:]>()?;
+        let value = self.parse_expression(input)?;
+        let mut annotations = self.parse_annotations(input)?;
+        annotations.push(Annotation {
+            name: "flatten".to_owned(),
+            args: Vec::new(),
+        });
+        return Ok(Pair {
+            key,
+            rename: None,
+            value,
+            annotations,
+            attributes: Vec::new(),
+        });
+    }
+
+    // annotation := @name | @name(arg, ...)
+    fn parse_annotations(&mut self, input: ParseStream) -> Result<Vec<Annotation>> {
+        let mut annotations = Vec::new();
+        while input.peek(Token![@]) {
+            input.parse::<Token![@]>()?;
+            let name = Ident::parse_any(input)?.to_string();
+            let mut args = Vec::new();
+            if input.peek(syn::token::Paren) {
+                let inner;
+                parenthesized!(inner in input);
+                args = self.parse_arguments(&inner)?;
+            }
+            annotations.push(Annotation { name, args });
+        }
+        return Ok(annotations);
+    }
+
+    // comma separated token trees, each argument is kept as source text
+    fn parse_arguments(&mut self, input: ParseStream) -> Result<Vec<String>> {
+        return input.step(|cursor| {
+            let mut rest = *cursor;
+            let mut args = Vec::new();
+            let mut s = "".to_owned();
+            while let Some((tt, next)) = rest.token_tree() {
+                let token = tt.to_string();
+                if token == "," {
+                    args.push(s);
+                    s = "".to_owned();
+                } else {
+                    push_token(&mut s, &token);
+                }
+                rest = next;
+            }
+            if !s.is_empty() {
+                args.push(s);
+            }
+            return Ok((args, rest));
+        });
+    }
 
-        return Ok(Pair { key, value });
+    // attributes := #[json(option, ...)] ...
+    pub fn parse_attributes(&mut self, input: ParseStream) -> Result<()> {
+        for attr in input.call(Attribute::parse_outer)? {
+            if !attr.path.is_ident("json") {
+                return Err(Error::new_spanned(
+                    attr.path,
+                    "expected #[json(option, ...)]",
+                ));
+            }
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(Error::new_spanned(meta, "expected #[json(option, ...)]")),
+            };
+            for nested in list.nested {
+                match nested {
+                    NestedMeta::Meta(Meta::Path(path)) if path.get_ident().is_some() => {
+                        let option = path.get_ident().unwrap().to_string();
+                        if !OPTIONS.contains(&option.as_str()) {
+                            let error = format!("unknown option {option}");
+                            return Err(Error::new_spanned(path, error));
+                        }
+                        self.options.push(option);
+                    }
+                    other => return Err(Error::new_spanned(other, "expected option name")),
+                }
+            }
+            if self.has_option("serialize_only") && self.has_option("deserialize_only") {
+                let error = "serialize_only conflicts with deserialize_only";
+                return Err(Error::new_spanned(attr, error));
+            }
+        }
+        return Ok(());
     }
 
+    // declare := Name { ... } | Name extends Base { ... }
     fn parse_declare(&mut self, input: ParseStream) -> Result<Value> {
         let name: Ident = input.parse()?;
+        let mut extends = None;
+        if input.peek(Ident) {
+            let keyword: Ident = input.parse()?;
+            if keyword != "extends" {
+                return Err(Error::new(keyword.span(), "expected `extends` or `{`"));
+            }
+            let base: Path = input.parse()?;
+            extends = Some((path_text(&base), keyword.span()));
+        }
         let mut value = self.parse_object(input)?;
 
         // using declared name
@@ -203,11 +688,31 @@ impl Json {
         object.name = name.to_string();
         value.t = ValueType::DECLARE;
 
+        // fields of base are flattened into the field `base`
+        if let Some((base, span)) = extends {
+            let class = self.append_expression(base.clone());
+            let object = self.get_object_mut(&value);
+            object.extends = Some(base);
+            object.pairs.insert(
+                0,
+                Pair {
+                    key: Ident::new("base", span),
+                    rename: None,
+                    value: class,
+                    annotations: vec![Annotation {
+                        name: "flatten".to_owned(),
+                        args: Vec::new(),
+                    }],
+                    attributes: Vec::new(),
+                },
+            );
+        }
+
         return Ok(value);
     }
 
     // object := { key: value, ...}
-    fn parse_object(&mut self, input: ParseStream) -> Result<Value> {
+    pub fn parse_object(&mut self, input: ParseStream) -> Result<Value> {
         let inner;
         let mut content;
 
@@ -217,13 +722,42 @@ impl Json {
             content = &inner;
         }
 
+        // empty object := {} | {} as type
+        if content.is_empty() && input.peek(Token![as]) {
+            return self.parse_typed_empty(input);
+        }
+
         let mut object = Object::new();
         object.name = format!("Object{}", self.id);
         self.id += 1;
 
-        loop {
-            let pair = self.parse_pair(content)?;
-            object.pairs.push(pair);
+        while !content.is_empty() {
+            // methods := impl { fn ... }, the comma is optional
+            if content.peek(Token![impl]) && content.peek2(syn::token::Brace) {
+                content.parse::<Token![impl]>()?;
+                let block;
+                braced!(block in content);
+                let methods: proc_macro2::TokenStream = block.parse()?;
+                object.methods.push(methods.to_string());
+                if content.peek(Token![,]) {
+                    content.parse::<Token![,]>()?;
+                }
+                continue;
+            }
+            // spread := ..expression, it comes first so the pairs after it override its fields
+            if content.peek(Token![..]) && !content.peek(Token![...]) {
+                let dots = content.parse::<Token![..]>()?;
+                if object.spread.is_some() {
+                    return Err(Error::new(dots.spans[0], "duplicated spread"));
+                } else if !object.pairs.is_empty() {
+                    object.update = Some(dots.spans[0]);
+                }
+                let base = self.parse_expression(content)?;
+                object.spread = Some(self.get_expression(&base).clone());
+            } else {
+                let pair = self.parse_pair(content)?;
+                object.pairs.push(pair);
+            }
             if !content.peek(Token![,]) {
                 break;
             }
@@ -249,7 +783,25 @@ impl Json {
             content = &inner;
         }
 
-        loop {
+        // empty array := [] | [] as type
+        if content.is_empty() && input.peek(Token![as]) {
+            return self.parse_typed_empty(input);
+        }
+
+        // typed array := [Type; { ... }, ...], otherwise `[value; n]` is a Rust repeat expression
+        if content.peek2(Token![;]) || content.peek2(Token![::]) {
+            let fork = content.fork();
+            let typed = fork.parse::<Path>().is_ok()
+                && fork.parse::<Token![;]>().is_ok()
+                && fork.peek(syn::token::Brace);
+            if typed {
+                let path: Path = content.parse()?;
+                content.parse::<Token![;]>()?;
+                array.class = Some(path_text(&path));
+            }
+        }
+
+        while !content.is_empty() {
             let value = self.parse_value(content)?;
             array.items.push(value);
             if !content.peek(Token![,]) {
@@ -261,12 +813,69 @@ impl Json {
             }
         }
 
+        // items of typed array
+        if let Some(class) = &array.class {
+            for item in &array.items {
+                self.set_class(item, class.clone());
+            }
+        }
+
         let value = self.append_array(array);
         return Ok(value);
     }
 
     // value ：= object | array | expression
+    // the declared type of object, the nested objects are named after it, e.g. `School_students_item`
+    fn set_class(&mut self, value: &Value, class: String) {
+        match value.t {
+            ValueType::OBJECT => {
+                let object = self.get_object(value);
+                if object.class.is_some() {
+                    return;
+                }
+                let children: Vec<_> = object
+                    .pairs
+                    .iter()
+                    .map(|x| (format!("{}_{}", class, x.name()), x.value.t, x.value.i))
+                    .collect();
+                self.get_object_mut(value).class = Some(class);
+                for (child, t, i) in children {
+                    self.set_class(&Value { t, i }, child);
+                }
+            }
+            ValueType::ARRAY => {
+                let array = self.get_array(value);
+                if array.class.is_some() {
+                    return;
+                }
+                let items: Vec<_> = array.items.iter().map(|x| (x.t, x.i)).collect();
+                let item = class + "_item";
+                for (t, i) in items {
+                    self.set_class(&Value { t, i }, item.clone());
+                }
+                self.get_array_mut(value).class = Some(item);
+            }
+            _ => {}
+        }
+    }
+
+    // empty container with explicit type, initialized by its default value
+    fn parse_typed_empty(&mut self, input: ParseStream) -> Result<Value> {
+        input.parse::<Token![as]>()?;
+        let class = self.parse_expression(input)?;
+        let class = self.get_expression(&class).clone();
+        return Ok(self.append_expression(format!("<{class}>::default()")));
+    }
+
     fn parse_value(&mut self, input: ParseStream) -> Result<Value> {
+        // typed object := Type { key: value, ... }
+        let fork = input.fork();
+        if fork.parse::<Path>().is_ok() && fork.peek(syn::token::Brace) {
+            let path: Path = input.parse()?;
+            let value = self.parse_object(input)?;
+            self.set_class(&value, path_text(&path));
+            return Ok(value);
+        }
         if input.peek(syn::token::Brace) {
             return self.parse_object(input);
         } else if input.peek(syn::token::Bracket) {
@@ -279,23 +888,28 @@ impl Json {
         let mut defines = Vec::new();
         defines.push("".to_owned());
 
-        for obj in &self.objects {
-            let mut i = 0;
+        // typed objects and spreads are not generic structs
+        let generics = self
+            .objects
+            .iter()
+            .filter(|x| x.spread.is_none() && x.class.is_none());
+        for obj in generics {
             let mut types = Vec::new();
             let mut fields = Vec::new();
-            for pair in &obj.pairs {
-                let t = format!("T{}", {
-                    i += 1;
-                    i
-                });
-                let key = pair.key.to_string();
+            for (i, pair) in obj.pairs.iter().enumerate() {
+                let t = format!("T{}", i + 1);
+                let key = pair.field();
                 // rename
                 let mut rename = "".to_string();
-                if key.len() > 1 && key.ends_with("_") {
-                    let name = &key[0..key.len() - 1];
-                    rename = format!("#[serde(rename = \"{name}\")]\n");
+                if let Some(name) = pair.rename() {
+                    rename = format!("#[serde(rename = {name:?})]\n");
+                }
+                let mut f = format!("{rename} {}:{}", key, t);
+                // conditional field is Option
+                if pair.condition().is_some() {
+                    let skip = "#[serde(skip_serializing_if = \"Option::is_none\")]";
+                    f = format!("{rename}{skip} {}:Option<{}>", key, t);
                 }
-                let f = format!("{rename} {}:{}", key, t);
                 types.push(t);
                 fields.push(f);
             }
@@ -311,16 +925,119 @@ impl Json {
         return defines.join(ATTRIBUTES);
     }
 
+    // serde traits of declared struct
+    pub fn get_serde(&self) -> &'static str {
+        if self.has_option("serialize_only") {
+            return "Serialize";
+        } else if self.has_option("deserialize_only") {
+            return "Deserialize";
+        }
+        return "Serialize, Deserialize";
+    }
+
+    // attributes of declared struct
+    fn get_attributes(&self) -> String {
+        let mut attributes = ATTRIBUTES.replace("Serialize, Deserialize", self.get_serde());
+        // utoipa reads the serde attributes, the fields with `#[serde(default)]` are not required
+        if self.has_option("utoipa") {
+            attributes += "#[derive(utoipa::ToSchema)]\n";
+        }
+        return attributes;
+    }
+
     pub fn get_code(&self) -> String {
         let name = "".to_owned();
         let code = self.gen_code(&self.value, &name);
         return code;
     }
 
-    pub fn get_block(&self) -> String {
+    // annotations of the pair, `bytes` implies @base64
+    fn get_annotations(&self, pair: &Pair) -> Vec<Annotation> {
+        let mut annotations = pair.annotations.clone();
+        let bytes = pair.value.t == ValueType::EXPRESSION
+            && bytes_type(self.get_expression(&pair.value)).is_some();
+        if bytes && !annotations.iter().any(|a| a.name == "base64") {
+            annotations.push(Annotation {
+                name: "base64".to_owned(),
+                args: Vec::new(),
+            });
+        }
+        return annotations;
+    }
+
+    // structs of declaration, the root is the first
+    pub fn get_structs(&self) -> Vec<Struct> {
+        let mut structs = Vec::new();
+        self.collect_structs("".to_owned(), &self.value, &mut structs);
+        structs.sort_by_key(|x| x.name.len());
+        return structs;
+    }
+
+    fn collect_structs(
+        &self,
+        mut path: String,
+        value: &Value,
+        structs: &mut Vec<Struct>,
+    ) -> String {
+        if let Some((key, item)) = self.get_map(value) {
+            let item = self.collect_structs(path + "_value", item, structs);
+            return format!("std::collections::HashMap<{}, {}>", key, item);
+        }
+        match value.t {
+            ValueType::DECLARE | ValueType::OBJECT => {
+                let object = self.get_object(value);
+                if path.is_empty() {
+                    path = object.name.clone();
+                }
+                let mut fields = Vec::new();
+                for pair in object.pairs.iter().skip(object.extends.iter().count()) {
+                    let child = path.clone() + "_" + &pair.name();
+                    let mut class = self.collect_structs(child, &pair.value, structs);
+                    let optional = class.ends_with("?");
+                    if optional {
+                        class.pop();
+                    }
+                    fields.push(Field {
+                        field: pair.field(),
+                        key: pair.rename().unwrap_or(pair.name()),
+                        class,
+                        optional,
+                        default: optional || pair.has_default(),
+                        annotations: self.get_annotations(pair),
+                        attributes: pair.attributes.clone(),
+                    });
+                }
+                structs.push(Struct {
+                    name: path.clone(),
+                    fields,
+                    extends: object.extends.clone(),
+                });
+                return path;
+            }
+            ValueType::ARRAY => {
+                let array = self.get_array(value);
+                let item = self.collect_structs(path + "_item", &array.items[0], structs);
+                return format!("std::vec::Vec<{}>", item);
+            }
+            ValueType::EXPRESSION => {
+                let class = self.get_expression(value);
+                if class == "str" || class == "&str" {
+                    return "String".to_owned();
+                } else if let Some(bytes) = bytes_type(class) {
+                    return bytes;
+                } else if let Some(raw) = raw_type(class) {
+                    return raw;
+                }
+                return class.clone();
+            }
+            ValueType::NULL => return "".to_owned(),
+        }
+    }
+
+    pub fn get_block(&self) -> Result<String> {
         if self.value.t == ValueType::DECLARE {
             let path = "".to_owned();
-            let (name, declare) = self.gen_declare(path, &self.value);
+            let (name, declare) = self.gen_declare(path, &self.value)?;
             let mut code = declare;
             // objects which require initializers
             let mut dict = ClassDict::new();
@@ -332,15 +1049,167 @@ impl Json {
                     key, init
                 );
                 code += &implement;
+                // delegate to the same initializer
+                let default = format!(
+                    "impl Default for {} {{\n    fn default() -> Self {{\n        Self::new()\n    }}\n}}\n",
+                    key
+                );
+                code += &default;
+            }
+
+            // every declared struct implements Validate, e.g. the base of `Name extends Base`
+            code += &self.gen_validate()?;
+
+            if self.has_option("patch") {
+                code += &self.gen_patch();
+            }
+
+            if self.has_option("reflect") {
+                code += &self.gen_reflect();
+            }
+
+            if self.has_option("typescript") {
+                let ts = self.gen_typescript();
+                code += &format!(
+                    "impl {name} {{\n    /// TypeScript interfaces of the serialized shape\n    pub fn typescript() -> &'static str {{\n        {ts:?}\n    }}\n}}\n"
+                );
+            }
+
+            // the fields of base are merged from its schemas
+            let extends = &self.get_object(&self.value).extends;
+
+            if self.has_option("schema") {
+                let mut schema = format!("serde_json::from_str({:?}).unwrap()", self.gen_schema());
+                if let Some(base) = extends {
+                    schema = format!("native_json::extends::schema(<{base}>::schema(), {schema})");
+                }
+                code += &format!(
+                    "impl {name} {{\n    /// JSON Schema (draft 2020-12) of the serialized shape\n    pub fn schema() -> serde_json::Value {{\n        {schema}\n    }}\n}}\n"
+                );
             }
 
-            return code;
+            if self.has_option("avro") {
+                let mut avro = format!("serde_json::from_str({:?}).unwrap()", self.gen_avro());
+                if let Some(base) = extends {
+                    avro = format!("native_json::extends::avro(<{base}>::avro_schema(), {avro})");
+                }
+                code += &format!(
+                    "impl {name} {{\n    /// Apache Avro schema (.avsc) of the serialized shape\n    pub fn avro_schema() -> serde_json::Value {{\n        {avro}\n    }}\n}}\n"
+                );
+            }
+
+            return Ok(code);
         } else {
+            let cfg = self.objects.iter().flat_map(|x| &x.pairs);
+            if let Some(pair) = cfg.into_iter().find(|x| !x.attributes.is_empty()) {
+                let error = "#[cfg(...)] is only allowed in declaration";
+                return Err(Error::new(pair.key.span(), error));
+            }
+            if self.objects.iter().any(|x| !x.methods.is_empty()) {
+                let error = "impl { ... } is only allowed in declaration";
+                return Err(Error::new(proc_macro2::Span::call_site(), error));
+            }
+            self.check_instance()?;
             let prototypes = self.get_generics();
             let code = self.get_code();
             let block = format!("{{ {}\n{} }}", prototypes, code);
-            return block;
+            return Ok(block);
+        }
+    }
+
+    // value of declared type, string literals are converted to the field type
+    fn gen_typed(&self, value: &Value) -> String {
+        if value.t != ValueType::EXPRESSION {
+            return self.gen_code(value, &"".to_owned());
+        }
+        let expr = self.get_expression(value);
+        if expr == "null" {
+            return "None".to_owned();
+        } else if expr.starts_with('"') {
+            return format!("{expr}.into()");
+        }
+        return self.gen_code(value, &"".to_owned());
+    }
+
+    // referenced types which are initialized by Default::default(), e.g. `tutor: School_students_item_tutor`,
+    // the assertions report the missing or non-default types at the fields
+    fn get_checks(&self) -> proc_macro2::TokenStream {
+        let mut checks = proc_macro2::TokenStream::new();
+        if self.value.t != ValueType::DECLARE {
+            return checks;
+        }
+        // the map values are not initialized
+        let structs = self.objects.iter().enumerate();
+        let structs = structs.filter(|(i, x)| *i == self.value.i || x.map_key().is_none());
+        for pair in structs.flat_map(|(_, x)| &x.pairs) {
+            if pair.value.t != ValueType::EXPRESSION
+                || pair.annotations.iter().any(|a| a.name == "init")
+            {
+                continue;
+            }
+            let class = self.get_expression(&pair.value).trim_end_matches('?');
+            if bytes_type(class).is_some() || self.get_instance(class) != "Default::default()" {
+                continue;
+            }
+            let check = format!(
+                "{} const _: fn() -> {class} = <{class} as Default>::default;",
+                pair.attributes.join(" ")
+            );
+            if let Ok(tokens) = proc_macro2::TokenStream::from_str(&check) {
+                checks.extend(respan(tokens, pair.key.span()));
+            }
+        }
+        return checks;
+    }
+
+    // the expansion of json!, with the checks of referenced types
+    pub fn get_tokens(&self) -> Result<proc_macro2::TokenStream> {
+        let block = self.get_block()?;
+        // the referenced types are checked first, the errors are located at the fields
+        let mut tokens = self.get_checks();
+        tokens.extend(proc_macro2::TokenStream::from_str(&block)?);
+
+        // helper structs are kept in the module, only the root is exported
+        if let Some((vis, name)) = &self.module {
+            let root = &self.get_object(&self.value).name;
+            let mut body = proc_macro2::TokenStream::from_str("use super::*;")?;
+            body.extend(tokens);
+            let group = proc_macro2::Group::new(proc_macro2::Delimiter::Brace, body);
+            tokens = proc_macro2::TokenStream::from_str(&format!("{vis} mod {name}"))?;
+            tokens.extend([proc_macro2::TokenTree::Group(group)]);
+            tokens.extend(proc_macro2::TokenStream::from_str(&format!(
+                "{vis} use {name}::{root};"
+            ))?);
+        }
+        return Ok(tokens);
+    }
+
+    // instance mode: the spread comes first unless the object is typed,
+    // the only annotation is @if where the field type is generated
+    fn check_instance(&self) -> Result<()> {
+        for obj in &self.objects {
+            // `Type { key: value, ..base }` is Rust struct update
+            if let (Some(span), None) = (obj.update, &obj.class) {
+                let error = "the spread must come first, e.g. `{ ..base, key: value }`";
+                return Err(Error::new(span, error));
+            }
+            for pair in &obj.pairs {
+                for a in &pair.annotations {
+                    let error = if a.name != "if" {
+                        format!("unknown annotation @{} in instance", a.name)
+                    } else if a.args.is_empty() {
+                        "@if requires a condition, e.g. `@if(verbose)`".to_owned()
+                    } else if obj.class.is_some() || obj.spread.is_some() {
+                        "@if is not allowed in typed or spread objects, the field is not an Option"
+                            .to_owned()
+                    } else {
+                        continue;
+                    };
+                    return Err(Error::new(pair.key.span(), error));
+                }
+            }
         }
+        return Ok(());
     }
 
     fn gen_code(&self, value: &Value, object_type: &String) -> String {
@@ -350,41 +1219,75 @@ impl Json {
             ValueType::OBJECT => {
                 let obj = self.get_object(value);
                 let mut fields = Vec::new();
+                let mut assignments = Vec::new();
                 for pair in &obj.pairs {
-                    let v = self.gen_code(&pair.value, object_type);
-                    let f = format!("{}:{}", pair.key.to_string(), v);
+                    let mut v = if obj.class.is_some() {
+                        self.gen_typed(&pair.value)
+                    } else {
+                        self.gen_code(&pair.value, object_type)
+                    };
+                    // key: value @if(condition)
+                    if let Some(condition) = pair.condition() {
+                        v = format!("if {} {{ Some({}) }} else {{ None }}", condition, v);
+                    }
+                    assignments.push(format!("object.{} = {};", pair.field(), v));
+                    let f = format!("{}:{}", pair.field(), v);
                     fields.push(f);
                 }
-                let name = if object_type.is_empty() {
-                    &obj.name
-                } else {
-                    object_type
+                let name = match &obj.class {
+                    Some(class) => class,
+                    None if object_type.is_empty() => &obj.name,
+                    None => object_type,
                 };
                 code = format!("{} {{ {} }}", name, fields.join(","));
+                // spread: the type of base, with the fields overridden
+                if let (Some(base), Some(class)) = (&obj.spread, &obj.class) {
+                    fields.push(format!("..{}", base));
+                    code = format!("{} {{ {} }}", class, fields.join(","));
+                } else if let Some(base) = &obj.spread {
+                    code = format!(
+                        "{{ #[allow(unused_mut)] let mut object = {}; {} object }}",
+                        base,
+                        assignments.join(" ")
+                    );
+                }
+            }
+            ValueType::ARRAY if self.get_array(value).class.is_some() => {
+                // typed array is Vec
+                let array = self.get_array(value);
+                let items: Vec<_> = array.items.iter().map(|x| self.gen_typed(x)).collect();
+                code = format!("std::vec![{}]", items.join(","));
             }
             ValueType::ARRAY => {
                 let array = self.get_array(value);
                 let mut item_type = &none;
                 // use the first item type
-                if array.items.len() > 0 && matches!(array.items[0].t, ValueType::OBJECT) {
+                if !array.items.is_empty() && matches!(array.items[0].t, ValueType::OBJECT) {
                     let obj = self.get_object(&array.items[0]);
                     item_type = &obj.name;
                 }
                 let items: Vec<_> = array
                     .items
                     .iter()
-                    .map(|x| {
-                        let c = self.gen_code(x, &item_type);
-                        c
-                    })
+                    .map(|x| self.gen_code(x, item_type))
                     .collect();
                 code = format!("[{}]", items.join(","));
+                if items.is_empty() {
+                    // untyped empty array
+                    code = "[(); 0]".to_owned();
+                }
             }
             ValueType::EXPRESSION => {
                 let expr = self.get_expression(value);
                 code = expr.clone();
                 if code.eq("null") || code.eq("None") {
                     code = "Option::<String>::None".to_owned();
+                } else if let Some(class) = code
+                    .strip_prefix("null as ")
+                    .or_else(|| code.strip_prefix("None as "))
+                {
+                    // typed null, e.g. `null as Option<i32>`
+                    code = format!("<{}>::default()", class);
                 }
             }
             ValueType::DECLARE => {
@@ -397,14 +1300,28 @@ impl Json {
         return code;
     }
 
-    fn get_instance(&self, class: &String) -> String {
+    fn get_instance(&self, class: &str) -> String {
         const PRIMITIVES: [&str; 15] = [
             "u8", "u16", "u32", "u64", "u128", "i8", "i16", "i32", "i64", "i128", "f32", "f64",
             "char", "isize", "usize",
         ];
+        // collections which have new() initializer, with the number of type arguments
+        const CONSTRUCTIBLE: [(&str, usize); 10] = [
+            ("String", 0),
+            ("PathBuf", 0),
+            ("Vec", 1),
+            ("VecDeque", 1),
+            ("LinkedList", 1),
+            ("BinaryHeap", 1),
+            ("BTreeSet", 1),
+            ("BTreeMap", 2),
+            // new() is for the default hasher only
+            ("HashSet", 1),
+            ("HashMap", 2),
+        ];
 
         // our optional suffix
-        let mut t = class.clone();
+        let mut t = class.to_owned();
         if class.ends_with("?") {
             t = (&t[0..t.len() - 1]).into();
         }
@@ -414,47 +1331,89 @@ impl Json {
         }
 
         for c in PRIMITIVES {
-            if c == &t {
+            if c == t {
                 return format!("0 as {}", t);
             }
         }
 
-        if class.find("Option<").is_some() {
+        if t == "raw" && class.ends_with('?') {
+            return "None".to_owned();
+        } else if t == "raw" {
+            t = raw_type(&t).unwrap();
+        }
+
+        let (generic, args) = split_generic(&t);
+        let base = base_type(generic);
+        if base == "Option" {
             return "None".to_owned();
+        } else if base == "Box" && args.first().map(|x| base_type(x)) == Some("RawValue") {
+            // JSON null
+            return "serde_json::value::RawValue::from_string(\"null\".to_owned()).unwrap()"
+                .to_owned();
+        }
+
+        if t == "serde_json::Value" {
+            return "serde_json::Value::Null".to_owned();
+        }
+
+        if t == "str" || t == "&str" {
+            return "std::string::String::new()".to_owned();
+        } else if t == "bytes" {
+            return "std::vec::Vec::new()".to_owned();
         }
 
-        let mut c = class.as_str();
+        if base == "OffsetDateTime" {
+            // the unix epoch
+            return format!("{}::UNIX_EPOCH", t);
+        } else if base == "Decimal" {
+            return format!("{}::ZERO", t);
+        } else if base == "Uuid" {
+            return format!("{}::nil()", t);
+        }
+
+        // unspecified address
+        match base {
+            "Ipv4Addr" | "Ipv6Addr" => return format!("{}::UNSPECIFIED", t),
+            "IpAddr" => return "std::net::IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED)".to_owned(),
+            "SocketAddr" | "SocketAddrV4" => {
+                return format!("{}::from((std::net::Ipv4Addr::UNSPECIFIED, 0))", t)
+            }
+            "SocketAddrV6" => {
+                return format!("{}::new(std::net::Ipv6Addr::UNSPECIFIED, 0, 0, 0)", t)
+            }
+            _ => {}
+        }
 
-        // generic
-        if let Some(i) = class.find("<") {
-            c = &class[0..i];
-        } else if class == "str" || class == "&str" {
-            c = "std::string::String";
-        } else if class.ends_with("?") {
-            // our optional suffix
-            c = &t;
+        if CONSTRUCTIBLE.contains(&(base, args.len())) {
+            // type arguments are inferred from the field, e.g. HashMap<String, Vec<Item>>
+            return format!("{}::new()", generic);
         }
 
-        // type must have new() initializer
-        return format!("{}::new()", c);
+        // enums, newtypes and third-party types
+        return "Default::default()".to_owned();
     }
 
     // dict of (path, object)
-    fn get_dict(&self, mut dict: ClassDict, path: &String, value: &Value) -> ClassDict {
+    fn get_dict(&self, mut dict: ClassDict, path: &str, value: &Value) -> ClassDict {
+        if let Some((_, item)) = self.get_map(value) {
+            // initializer for map value
+            let child = path.to_owned() + "_value";
+            return self.get_dict(dict, &child, item);
+        }
         match value.t {
             ValueType::DECLARE | ValueType::OBJECT => {
                 // initializer for object
                 dict.set(path, value);
                 let object = self.get_object(value);
                 for pair in &object.pairs {
-                    let child = path.clone() + "_" + &pair.key.to_string();
+                    let child = path.to_owned() + "_" + &pair.name();
                     dict = self.get_dict(dict, &child, &pair.value);
                 }
             }
             ValueType::ARRAY => {
                 // initializer for array item
                 let array = self.get_array(value);
-                let child = path.clone() + "_item";
+                let child = path.to_owned() + "_item";
                 dict = self.get_dict(dict, &child, &array.items[0]);
             }
             ValueType::EXPRESSION => {}
@@ -466,16 +1425,23 @@ impl Json {
 
     fn gen_initializer(&self, path: &String, value: &Value) -> String {
         let mut code = "".to_owned();
+        if self.get_map(value).is_some() {
+            return "std::collections::HashMap::new()".to_owned();
+        }
 
         match value.t {
             ValueType::DECLARE | ValueType::OBJECT => {
                 let object = self.get_object(value);
                 let mut fields = Vec::new();
                 for pair in &object.pairs {
-                    let child = path.clone() + "_" + &pair.key.to_string();
-                    let c = self.gen_initializer(&child, &pair.value);
-                    let key = pair.key.to_string();
-                    let f = format!("{}: {}", key, c);
+                    let child = path.clone() + "_" + &pair.name();
+                    let mut c = self.gen_initializer(&child, &pair.value);
+                    // @init(expr) overrides the initializer
+                    if let Some(a) = pair.annotations.iter().find(|a| a.name == "init") {
+                        c = a.args.join(", ");
+                    }
+                    let key = pair.field();
+                    let f = format!("{}{}: {}", pair.attributes.join(" "), key, c);
                     fields.push(f);
                 }
                 code += format!("{} {{ {} }}", path, fields.join(",")).as_str();
@@ -485,7 +1451,7 @@ impl Json {
             }
             ValueType::EXPRESSION => {
                 let expr = self.get_expression(value);
-                code = self.get_instance(&expr);
+                code = self.get_instance(expr);
             }
             ValueType::NULL => {}
         }
@@ -493,62 +1459,153 @@ impl Json {
         return code;
     }
 
-    fn gen_declare(&self, mut path: String, value: &Value) -> (String, String) {
+    // builder := Name::builder().with_key(value)...build()
+    fn gen_builder(&self, class: &str, members: &[(String, String, String, String)]) -> String {
+        let builder = format!("{class}Builder");
+        let mut setters = Vec::new();
+        for (key, name, t, cfg) in members {
+            let setter = format!(
+                "    {cfg}\n    pub fn with_{name}(mut self, value: {t}) -> Self {{\n        self.inner.{key} = value;\n        self\n    }}\n"
+            );
+            setters.push(setter);
+        }
+        return format!(
+            "#[allow(non_camel_case_types)]\n#[derive(Debug, Clone)]\n\
+            pub struct {builder} {{\n    inner: {class},\n}}\n\
+            impl {builder} {{\n{}\
+            \n    pub fn build(self) -> {class} {{\n        self.inner\n    }}\n}}\n\
+            impl {class} {{\n    pub fn builder() -> {builder} {{\n        {builder} {{ inner: {class}::new() }}\n    }}\n}}\n",
+            setters.join("\n")
+        );
+    }
+
+    fn gen_declare(&self, mut path: String, value: &Value) -> Result<(String, String)> {
         // class of current node
         let mut class = "".to_owned();
         let mut code = "".to_owned();
+        // map := {String: type}, the value struct is declared as Name_key_value
+        if let Some((key, item)) = self.get_map(value) {
+            let (n, c) = self.gen_declare(path + "_value", item)?;
+            return Ok((format!("std::collections::HashMap<{key}, {n}>"), c));
+        }
         match value.t {
             ValueType::DECLARE | ValueType::OBJECT => {
                 let object = self.get_object(value);
                 if path.is_empty() {
                     path = object.name.clone();
                 }
+                if object.spread.is_some() {
+                    let error = format!("spread is not allowed in declaration {path}");
+                    return Err(Error::new(proc_macro2::Span::call_site(), error));
+                }
                 class = path;
                 let mut fields = Vec::new();
+                // (field, name, type, cfg) of fields
+                let mut members = Vec::new();
                 for pair in &object.pairs {
-                    let child = class.clone() + "_" + &pair.key.to_string();
-                    let (mut n, c) = self.gen_declare(child, &pair.value);
+                    let child = class.clone() + "_" + &pair.name();
+                    let (mut n, c) = self.gen_declare(child, &pair.value)?;
                     code += &c;
-                    let key = pair.key.to_string();
+                    let key = pair.field();
+                    let mut serde = Vec::new();
+                    let mut default = false;
+                    let mut secret = false;
                     // optional
-                    let mut optional = "";
                     if n.ends_with("?") {
                         n = (&n[0..n.len() - 1]).into();
-                        optional = "skip_serializing_if = \"is_default\"";
+                        default = true;
+                        // RawValue is not comparable, the absent value is None
+                        let skip = match raw_type("raw?") == Some(format!("{n}?")) {
+                            true => "Option::is_none",
+                            false => "is_default",
+                        };
+                        serde.push(format!("skip_serializing_if = {skip:?}"));
                     }
                     // rename
-                    let mut rename = "".to_string();
-                    if key.len() > 1 && key.ends_with("_") {
-                        let name = &key[0..key.len() - 1];
-                        rename = format!("rename = \"{name}\"");
+                    if let Some(name) = pair.rename() {
+                        // underscore suffix
+                        if pair.rename.is_none() {
+                            default = true;
+                        }
+                        serde.push(format!("rename = {name:?}"));
                     }
-                    // attributes
-                    let mut attributes = "".to_string();
-                    if optional.len() > 0 || rename.len() > 0 {
-                        attributes = "#[serde(default".into();
-                        if optional.len() > 0 {
-                            attributes += ",";
-                            attributes += optional;
+                    // annotations
+                    for a in &self.get_annotations(pair) {
+                        match a.name.as_str() {
+                            "flatten" => serde.push("flatten".to_owned()),
+                            // validation rules
+                            x if VALIDATIONS.contains(&x) => {}
+                            "default" => default = true,
+                            // masked by stringify_redacted()
+                            "secret" if a.args.is_empty() => secret = true,
+                            // initializer of new()
+                            "init" if !a.args.is_empty() => {}
+                            // number as string, base64, timestamps and custom (de)serializers
+                            x if WITH.contains(&x) => {
+                                let with =
+                                    serde_with(a).map_err(|e| Error::new(pair.key.span(), e))?;
+                                serde.push(with);
+                            }
+                            _ => {
+                                let error = format!("unknown annotation @{}", a.name);
+                                return Err(Error::new(pair.key.span(), error));
+                            }
                         }
-                        if rename.len() > 0 {
-                            attributes += ",";
-                            attributes += &rename;
+                    }
+                    if secret {
+                        let custom =
+                            |x: &String| x.starts_with("with") || x.starts_with("serialize_with");
+                        if serde.iter().any(custom) {
+                            let error = "@secret conflicts with the custom serialization";
+                            return Err(Error::new(pair.key.span(), error));
                         }
-                        attributes += ")]\n";
+                        let with = "serialize_with = \"native_json::secret::serialize\"";
+                        serde.push(with.to_owned());
                     }
+                    // attributes
+                    if default {
+                        serde.insert(0, "default".to_owned());
+                    }
+                    let mut attributes = "".to_string();
+                    if !serde.is_empty() {
+                        attributes = format!("#[serde({})]\n", serde.join(","));
+                    }
+                    // #[cfg(...)]
+                    let cfg = pair.attributes.join("\n");
                     // collapse to "key: type"
-                    let f = format!("{attributes}pub {}:{}", key, n);
+                    let f = format!("{cfg}{attributes}pub {}:{}", key, n);
                     fields.push(f);
+                    members.push((key, pair.name(), n, cfg));
                 }
                 let c = format!("pub struct {} {{\n{}\n}}\n", class, fields.join(",\n"));
-                code += ATTRIBUTES;
+                code += &self.get_attributes();
+                if self.has_option("deny_unknown_fields") {
+                    code += "#[serde(deny_unknown_fields)]\n";
+                }
                 code += &c;
+                for methods in &object.methods {
+                    code += &format!("impl {class} {{\n{methods}\n}}\n");
+                }
+                // fields of base are accessible by deref
+                if let Some(base) = &object.extends {
+                    code += &format!(
+                        "impl std::ops::Deref for {class} {{\n    type Target = {base};\n    fn deref(&self) -> &{base} {{\n        &self.base\n    }}\n}}\n\
+                        impl std::ops::DerefMut for {class} {{\n    fn deref_mut(&mut self) -> &mut {base} {{\n        &mut self.base\n    }}\n}}\n"
+                    );
+                }
+                if self.has_option("builder") {
+                    code += &self.gen_builder(&class, &members);
+                }
             }
             ValueType::ARRAY => {
                 // array: [type]
                 let array = self.get_array(value);
+                if array.items.is_empty() {
+                    let error = format!("expected item type of array {path}: [type]");
+                    return Err(Error::new(proc_macro2::Span::call_site(), error));
+                }
                 let child = path + "_item";
-                let (n, c) = self.gen_declare(child, &array.items[0]);
+                let (n, c) = self.gen_declare(child, &array.items[0])?;
                 code += &c;
                 class = format!("std::vec::Vec<{}>", n);
             }
@@ -558,23 +1615,104 @@ impl Json {
                 class = v.clone();
                 if class == "str" || class == "&str" {
                     class = "String".to_owned();
+                } else if let Some(bytes) = bytes_type(&class) {
+                    class = bytes;
+                } else if let Some(raw) = raw_type(&class) {
+                    class = raw;
                 }
             }
             ValueType::NULL => {}
         }
 
-        return (class, code);
+        return Ok((class, code));
     }
 }
 
+// tokens located at the span
+fn respan(tokens: proc_macro2::TokenStream, span: proc_macro2::Span) -> proc_macro2::TokenStream {
+    use proc_macro2::{Group, TokenTree};
+    return tokens
+        .into_iter()
+        .map(|tt| match tt {
+            TokenTree::Group(g) => {
+                let mut group = Group::new(g.delimiter(), respan(g.stream(), span));
+                group.set_span(span);
+                TokenTree::Group(group)
+            }
+            mut other => {
+                other.set_span(span);
+                other
+            }
+        })
+        .collect();
+}
+
+// source text of type path, e.g. `models::Student`
+fn path_text(path: &Path) -> String {
+    let segments: Vec<_> = path.segments.iter().map(|x| x.ident.to_string()).collect();
+    let prefix = if path.leading_colon.is_some() {
+        "::"
+    } else {
+        ""
+    };
+    return format!("{}{}", prefix, segments.join("::"));
+}
+
+// shorthand object := identifier, ... e.g. `json!{ name, age }`
+fn is_shorthand(input: ParseStream) -> bool {
+    let fork = input.fork();
+    return match fork.parse::<Ident>() {
+        Ok(ident) => ident != "null" && (fork.is_empty() || fork.peek(Token![,])),
+        Err(_) => false,
+    };
+}
+
 impl Parse for Json {
     fn parse(input: ParseStream) -> Result<Self> {
+        // module := pub mod name { declare }
+        if input.peek(Token![mod]) || (input.peek(Token![pub]) && !input.peek2(Token![:])) {
+            let vis: Visibility = input.parse()?;
+            input.parse::<Token![mod]>()?;
+            let name: Ident = input.parse()?;
+            let inner;
+            braced!(inner in input);
+            let mut json: Json = inner.parse()?;
+            if json.value.t != ValueType::DECLARE {
+                return Err(Error::new(name.span(), "expected declaration in module"));
+            }
+            let vis = match vis {
+                Visibility::Inherited => "".to_owned(),
+                Visibility::Public(_) => "pub".to_owned(),
+                Visibility::Crate(_) => "pub(crate)".to_owned(),
+                Visibility::Restricted(r) => match r.in_token {
+                    Some(_) => format!("pub(in {})", path_text(&r.path)),
+                    None => format!("pub({})", path_text(&r.path)),
+                },
+            };
+            json.module = Some((vis, name.to_string()));
+            return Ok(json);
+        }
+
         let mut json = Json::new();
 
-        if input.peek2(syn::token::Brace) {
+        // declare := identifier { ... } | identifier extends ...
+        let declare = |input: ParseStream| {
+            let fork = input.fork();
+            fork.parse::<Ident>().is_ok() && (fork.peek(syn::token::Brace) || fork.peek(Ident))
+        };
+
+        // attributes := #[...] declare
+        if input.peek(Token![#]) {
+            json.parse_attributes(input)?;
+            if !declare(input) {
+                return Err(input.error("attributes are only allowed on declaration"));
+            }
+        }
+
+        if declare(input) {
             // declare := identifier { ... }
             json.value = json.parse_declare(input)?;
-        } else if input.peek2(syn::token::Colon) {
+        } else if input.peek2(syn::token::Colon) || input.peek(Token![..]) || is_shorthand(input) {
             // value := object | array
             json.value = json.parse_object(input)?;
         } else {
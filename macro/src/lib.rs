@@ -52,14 +52,14 @@
 //!```
 //!## Declare a named JSON struct
 //!
-//!With JSON declare syntax, you can declare nested native JSON object in place. 
+//!With JSON declare syntax, you can declare nested native JSON object in place.
 //!Note: Identifier with underscore suffix will be renamed when serialize and deserialize, `type_` will be renamed to `type`.
 //!
 //!### JSON Declare Syntax
 //!```rust
 //!json!{
-//!JSON_OBJECT_NAME { 
-//!    name : type, 
+//!JSON_OBJECT_NAME {
+//!    name : type,
 //!    value: type?,  // optional field when serialize & deserialize
 //!    type_: String, // suffix underscore will be removed when serialize & deserialize
 //!    array: [type],
@@ -83,7 +83,7 @@
 //!use serde::{Deserialize, Serialize};
 //!use std::collections::HashMap;
 //!
-//!json!{ 
+//!json!{
 //!School {
 //!    name: String,
 //!    rank: u32?, // optional
@@ -117,20 +117,112 @@
 //!}
 //!```
 //!
+#![allow(clippy::needless_return, clippy::upper_case_acronyms)]
 extern crate proc_macro;
+mod avro;
+mod include;
+mod infer;
 mod json;
+mod openapi;
+mod patch;
+mod reflect;
+mod rpc;
+mod schema;
+mod typescript;
+mod validate;
+mod ws;
 
+use include::*;
+use infer::*;
 use json::*;
+use openapi::*;
 use proc_macro::TokenStream;
+use rpc::*;
 use std::str::FromStr;
 use syn::parse_macro_input;
+use ws::*;
 
 /// Declare or instantiate a native JSON object, please refere to module [json](index.html)
 #[proc_macro]
 pub fn json(input: TokenStream) -> TokenStream {
+    // rpc name { params } -> result
+    if is_rpc(&input) {
+        let rpc = parse_macro_input!(input as Rpc);
+        return match rpc.get_tokens() {
+            Ok(tokens) => tokens.into(),
+            Err(e) => e.to_compile_error().into(),
+        };
+    }
+    // ws Name { Variant { fields }, ... }
+    if is_ws(&input) {
+        let ws = parse_macro_input!(input as Ws);
+        return match ws.get_tokens() {
+            Ok(tokens) => tokens.into(),
+            Err(e) => e.to_compile_error().into(),
+        };
+    }
     let parser = parse_macro_input!(input as Json);
-    let block = parser.get_block();
+    let tokens = match parser.get_tokens() {
+        Ok(tokens) => tokens,
+        Err(e) => return e.to_compile_error().into(),
+    };
     // Show me the code
-    // println!("XXXXXXXXXXXXXXXX\n{}", block);
+    // println!("XXXXXXXXXXXXXXXX\n{}", tokens);
+    return tokens.into();
+}
+
+/// Declare a named JSON struct from a sample JSON file, the field types are inferred
+///
+/// ```rust
+/// include_json!("fixtures/response.json" as ApiResponse);
+/// ```
+///
+/// The path is relative to the crate root, options of `#[json(...)]` are allowed in front of the path.
+#[proc_macro]
+pub fn include_json(input: TokenStream) -> TokenStream {
+    let include = parse_macro_input!(input as Include);
+    let mut block = match include.json.get_block() {
+        Ok(block) => block,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    // rebuild when the sample changes
+    block += &format!("const _: &str = include_str!({:?});", include.path);
+    return TokenStream::from_str(block.as_str()).unwrap();
+}
+
+/// Declare the named JSON structs of the schemas in an OpenAPI document
+///
+/// ```rust
+/// openapi_json!("api.yaml", components = ["Order", "Trade"]);
+/// ```
+///
+/// The path is relative to the crate root, the referenced schemas are declared as well, all schemas are declared
+/// without `components`. Options of `#[json(...)]` are allowed in front of the path.
+#[proc_macro]
+pub fn openapi_json(input: TokenStream) -> TokenStream {
+    let openapi = parse_macro_input!(input as OpenApi);
+    let block = match openapi.get_block() {
+        Ok(block) => block,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    return TokenStream::from_str(block.as_str()).unwrap();
+}
+
+/// Declare a named JSON struct from an example in instance syntax, the field types are inferred
+///
+/// ```rust
+/// json_type!{ Config = { host: "localhost", port: 8080 as u16, debug: true } }
+///
+/// let config = Config::example();
+/// ```
+///
+/// `Config::example()` returns the example value, `Config::new()` is the default as usual.
+#[proc_macro]
+pub fn json_type(input: TokenStream) -> TokenStream {
+    let parser = parse_macro_input!(input as JsonType);
+    let block = match parser.get_block() {
+        Ok(block) => block,
+        Err(e) => return e.to_compile_error().into(),
+    };
     return TokenStream::from_str(block.as_str()).unwrap();
 }
@@ -0,0 +1,321 @@
+use crate::json::*;
+use serde_json::{Map, Value as V};
+use std::path::PathBuf;
+use syn::{
+    parse::{Parse, ParseStream},
+    *,
+};
+
+//------------------- openapi_json! Syntax ------------------------------
+//
+// openapi = #[json(option, ...)] "path" [, components = ["Name", ...]]
+//
+// The path is relative to the crate root, YAML or JSON by the extension. The listed schemas of
+// `components.schemas` are declared, all of them if the list is absent, along with the schemas they reference.
+//
+// string           => String
+// integer          => i32 (int32) | i64
+// number           => f32 (float) | f64
+// boolean          => bool
+// array            => [item]
+// object           => { key: type, ... }, keys missing from `required` are optional
+// map              => HashMap<String, type> of `additionalProperties`
+// $ref             => Name of the referenced component
+// allOf            => properties of all schemas are merged
+// oneOf, anyOf     => serde_json::Value
+// nullable: true   => Option<type>, also `type: [type, "null"]`
+
+const REFERENCES: [&str; 2] = ["#/components/schemas/", "#/definitions/"];
+
+pub struct OpenApi {
+    // declarations of the object components
+    pub jsons: Vec<Json>,
+    // `pub type Name = type;` of the other components
+    pub aliases: Vec<String>,
+    pub path: PathBuf,
+}
+
+struct Spec<'a> {
+    schemas: &'a Map<String, V>,
+    file: &'a LitStr,
+    // components to declare, in order of reference
+    queue: Vec<String>,
+}
+
+impl<'a> Spec<'a> {
+    fn error(&self, message: String) -> Error {
+        return Error::new(self.file.span(), message);
+    }
+
+    fn component(&self, name: &str) -> Result<&'a V> {
+        return match self.schemas.get(name) {
+            Some(schema) => Ok(schema),
+            None => Err(self.error(format!("unknown component `{name}`"))),
+        };
+    }
+
+    // component of `$ref`, which is queued to be declared
+    fn reference(&mut self, reference: &str) -> Result<String> {
+        let name = REFERENCES
+            .iter()
+            .find_map(|x| reference.strip_prefix(x))
+            .ok_or_else(|| self.error(format!("unsupported reference `{reference}`")))?;
+        self.component(name)?;
+        if !self.queue.iter().any(|x| x == name) {
+            self.queue.push(name.to_owned());
+        }
+        return Ok(sanitize(name));
+    }
+
+    // properties and required keys of `allOf`, the references are inlined
+    fn merge(&self, schema: &V, object: &mut Map<String, V>) -> Result<()> {
+        if let Some(reference) = schema.get("$ref").and_then(V::as_str) {
+            let name = REFERENCES
+                .iter()
+                .find_map(|x| reference.strip_prefix(x))
+                .ok_or_else(|| self.error(format!("unsupported reference `{reference}`")))?;
+            return self.merge(self.component(name)?, object);
+        }
+        for part in schema
+            .get("allOf")
+            .and_then(V::as_array)
+            .into_iter()
+            .flatten()
+        {
+            self.merge(part, object)?;
+        }
+        for key in ["properties", "required"] {
+            match (schema.get(key), object.get_mut(key)) {
+                (Some(V::Object(a)), Some(V::Object(b))) => b.extend(a.clone()),
+                (Some(V::Array(a)), Some(V::Array(b))) => b.extend(a.clone()),
+                (Some(a), None) => {
+                    object.insert(key.to_owned(), a.clone());
+                }
+                _ => {}
+            }
+        }
+        return Ok(());
+    }
+
+    // lower the schema into the declaration of json!, the flag tells a missing value takes the default
+    fn declare(&mut self, json: &mut Json, schema: &V, optional: bool) -> Result<(Value, bool)> {
+        let types: Vec<&str> = match schema.get("type") {
+            Some(V::String(t)) => vec![t.as_str()],
+            Some(V::Array(t)) => t.iter().filter_map(V::as_str).collect(),
+            _ => Vec::new(),
+        };
+        let nullable = schema.get("nullable") == Some(&V::Bool(true)) || types.contains(&"null");
+        let kind = types.iter().find(|x| **x != "null").copied();
+        let properties = schema.get("properties").and_then(V::as_object);
+
+        if let Some(reference) = schema.get("$ref").and_then(V::as_str) {
+            // declared structs are not comparable, missing or null is None
+            let name = self.reference(reference)?;
+            if optional || nullable {
+                let value = json.append_expression(format!("Option<{name}>"));
+                return Ok((value, optional));
+            }
+            return Ok((json.append_expression(name), false));
+        }
+
+        if let Some(parts) = schema.get("allOf").and_then(V::as_array) {
+            if let [part] = parts.as_slice() {
+                return self.declare(json, part, optional);
+            }
+            let mut object = Map::new();
+            self.merge(schema, &mut object)?;
+            object.insert("type".to_owned(), V::from("object"));
+            return self.declare(json, &V::Object(object), optional);
+        }
+
+        if kind == Some("array") {
+            let item = schema.get("items").unwrap_or(&V::Null);
+            let mut array = Array::new();
+            array.items.push(self.declare(json, item, false)?.0);
+            return Ok((json.append_array(array), optional));
+        }
+
+        if let Some(properties) = properties.filter(|x| !x.is_empty()) {
+            let required: Vec<&str> = match schema.get("required") {
+                Some(V::Array(keys)) => keys.iter().filter_map(V::as_str).collect(),
+                _ => Vec::new(),
+            };
+            let mut object = Object::new();
+            for (key, property) in properties {
+                let name = sanitize(key);
+                let rename = if &name == key {
+                    None
+                } else {
+                    Some(key.clone())
+                };
+                let optional = !required.contains(&key.as_str());
+                let (value, default) = self.declare(json, property, optional)?;
+                let mut annotations = Vec::new();
+                if default {
+                    annotations.push(Annotation {
+                        name: "default".to_owned(),
+                        args: Vec::new(),
+                    });
+                }
+                object.pairs.push(Pair {
+                    key: Ident::new(&name, self.file.span()),
+                    rename,
+                    value,
+                    annotations,
+                    attributes: Vec::new(),
+                });
+            }
+            return Ok((json.append_object(object), optional));
+        }
+
+        let mut t = match kind {
+            Some("string") => "String".to_owned(),
+            Some("integer") if schema.get("format") == Some(&V::from("int32")) => "i32".to_owned(),
+            Some("integer") => "i64".to_owned(),
+            Some("number") if schema.get("format") == Some(&V::from("float")) => "f32".to_owned(),
+            Some("number") => "f64".to_owned(),
+            Some("boolean") => "bool".to_owned(),
+            _ => match schema.get("additionalProperties") {
+                Some(V::Object(item)) => {
+                    let item = V::Object(item.clone());
+                    let (value, _) = self.declare(json, &item, false)?;
+                    let class = match value.t {
+                        ValueType::EXPRESSION => json.get_expression(&value).clone(),
+                        // nested structure of the map values
+                        _ => "serde_json::Value".to_owned(),
+                    };
+                    format!("std::collections::HashMap<String, {class}>")
+                }
+                _ => "serde_json::Value".to_owned(),
+            },
+        };
+        if nullable && t != "serde_json::Value" {
+            t = format!("Option<{t}>");
+        }
+        if optional {
+            t += "?";
+        }
+        return Ok((json.append_expression(t), false));
+    }
+}
+
+impl OpenApi {
+    pub fn get_block(&self) -> Result<String> {
+        let mut block = self.aliases.join("");
+        for json in &self.jsons {
+            block += &json.get_block()?;
+        }
+        // rebuild when the spec changes
+        block += &format!("const _: &str = include_str!({:?});", self.path);
+        return Ok(block);
+    }
+}
+
+impl Parse for OpenApi {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let mut options = Json::new();
+        options.parse_attributes(input)?;
+
+        let file: LitStr = input.parse()?;
+        let mut components = Vec::new();
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            let key: Ident = input.parse()?;
+            if key != "components" {
+                return Err(Error::new(key.span(), "expected `components`"));
+            }
+            input.parse::<Token![=]>()?;
+            let content;
+            bracketed!(content in input);
+            let names = content.parse_terminated::<LitStr, Token![,]>(|x| x.parse())?;
+            components.extend(names);
+        }
+
+        // relative to the crate root
+        let mut path = PathBuf::from(file.value());
+        if path.is_relative() {
+            if let Ok(root) = std::env::var("CARGO_MANIFEST_DIR") {
+                path = PathBuf::from(root).join(path);
+            }
+        }
+
+        let text = std::fs::read_to_string(&path).map_err(|e| {
+            let error = format!("failed to read {}: {}", path.display(), e);
+            Error::new(file.span(), error)
+        })?;
+        let spec: V = match path.extension().and_then(|x| x.to_str()) {
+            Some("json") => serde_json::from_str(&text).map_err(|e| e.to_string()),
+            _ => serde_yaml::from_str(&text).map_err(|e| e.to_string()),
+        }
+        .map_err(|e| {
+            let error = format!("failed to parse {}: {}", path.display(), e);
+            Error::new(file.span(), error)
+        })?;
+
+        // OpenAPI 3 or Swagger 2
+        let schemas = spec
+            .pointer("/components/schemas")
+            .or_else(|| spec.pointer("/definitions"))
+            .and_then(V::as_object)
+            .ok_or_else(|| Error::new(file.span(), "expected `components.schemas`"))?;
+
+        let mut queue = Vec::new();
+        for name in &components {
+            if !schemas.contains_key(&name.value()) {
+                let error = format!("unknown component `{}`", name.value());
+                return Err(Error::new(name.span(), error));
+            }
+            queue.push(name.value());
+        }
+        if components.is_empty() {
+            queue.extend(schemas.keys().cloned());
+        }
+
+        let mut spec = Spec {
+            schemas,
+            file: &file,
+            queue,
+        };
+        let mut jsons = Vec::new();
+        let mut aliases = Vec::new();
+        let mut i = 0;
+        while i < spec.queue.len() {
+            let name = spec.queue[i].clone();
+            i += 1;
+            let schema = spec.component(&name)?;
+            let mut json = Json::new();
+            json.options = options.options.clone();
+            let (value, _) = spec.declare(&mut json, schema, false)?;
+            let class = sanitize(&name);
+            match value.t {
+                ValueType::OBJECT => {
+                    json.get_object_mut(&value).name = class;
+                    json.value = Value {
+                        t: ValueType::DECLARE,
+                        i: value.i,
+                    };
+                    jsons.push(json);
+                }
+                ValueType::ARRAY => {
+                    let item = &json.get_array(&value).items[0];
+                    let item = match item.t {
+                        ValueType::EXPRESSION => json.get_expression(item).clone(),
+                        // nested structure of the items
+                        _ => "serde_json::Value".to_owned(),
+                    };
+                    aliases.push(format!("pub type {class} = Vec<{item}>;\n"));
+                }
+                _ => {
+                    let t = json.get_expression(&value);
+                    aliases.push(format!("pub type {class} = {t};\n"));
+                }
+            }
+        }
+
+        return Ok(OpenApi {
+            jsons,
+            aliases,
+            path,
+        });
+    }
+}
@@ -0,0 +1,95 @@
+use crate::json::*;
+
+//------------------- Patch ------------------------------
+//
+// #[json(patch)] generates NamePatch for every struct, all fields are Option<T>
+//
+// Name::apply(&mut self, patch) overwrites the fields which are Some,
+// nested structs are patched recursively, the other values are replaced
+//
+// `Option<T>` fields are `Option<Option<T>>`, `null` clears the field
+//
+// fields with custom serialization are wrapped in `NamePatch_field` with the same serde attribute,
+// Name extends Base patches the base by BasePatch, which is flattened into the field `base`,
+// the other flattened fields are not patchable
+
+impl Json {
+    pub fn gen_patch(&self) -> String {
+        let structs = self.get_structs();
+        let names: Vec<_> = structs.iter().map(|x| x.name.clone()).collect();
+        let serde = self.get_serde();
+        let deserialize = !self.has_option("serialize_only");
+        let mut code = "".to_owned();
+        for s in &structs {
+            let name = &s.name;
+            let mut fields = Vec::new();
+            let mut applies = Vec::new();
+            if let Some(base) = &s.extends {
+                fields.push(format!("#[serde(flatten)]\npub base: {base}Patch"));
+                applies.push("self.base.apply(patch.base);".to_owned());
+            }
+            for f in &s.fields {
+                if f.annotations.iter().any(|a| a.name == "flatten") {
+                    continue;
+                }
+                let cfg = f.attributes.join("\n");
+                let nested = names.contains(&f.class);
+                let with: Vec<_> = f
+                    .annotations
+                    .iter()
+                    .filter(|a| WITH.contains(&a.name.as_str()))
+                    .filter_map(|a| serde_with(a).ok())
+                    .collect();
+                let (class, value) = if !with.is_empty() {
+                    // the same (de)serializer in a transparent wrapper
+                    let wrapper = format!("{name}Patch_{}", f.field.trim_start_matches("r#"));
+                    code += &format!(
+                        "#[derive({serde}, Debug, Clone)]\n#[allow(non_camel_case_types)]\n#[serde(transparent)]\n\
+                        pub struct {wrapper}(#[serde({})] pub {});\n",
+                        with.join(", "),
+                        f.class
+                    );
+                    (wrapper, "value.0")
+                } else if nested {
+                    (format!("{}Patch", f.class), "value")
+                } else {
+                    (f.class.clone(), "value")
+                };
+                let mut serde = vec![
+                    format!("rename = {:?}", f.key),
+                    "default".to_owned(),
+                    "skip_serializing_if = \"Option::is_none\"".to_owned(),
+                ];
+                // `null` is the present value `None`
+                let (base, args) = split_generic(&f.class);
+                if deserialize && base_type(base) == "Option" && args.len() == 1 {
+                    serde.push("deserialize_with = \"native_json::patch::present\"".to_owned());
+                }
+                fields.push(format!(
+                    "{cfg}#[serde({})]\npub {}: Option<{}>",
+                    serde.join(", "),
+                    f.field,
+                    class
+                ));
+                let apply = if nested {
+                    format!("self.{}.apply({value});", f.field)
+                } else {
+                    format!("self.{} = {value};", f.field)
+                };
+                applies.push(format!(
+                    "{cfg}if let Some(value) = patch.{} {{\n    {apply}\n}}",
+                    f.field
+                ));
+            }
+            code += &format!(
+                "#[derive({serde}, Debug, Clone, Default)]\n#[allow(non_camel_case_types)]\n\
+                pub struct {name}Patch {{\n{}\n}}\n\
+                impl {name} {{\n    /// Overwrite the fields which are present in the patch\n    \
+                pub fn apply(&mut self, patch: {name}Patch) {{\n{}\n    }}\n}}\n",
+                fields.join(",\n"),
+                applies.join("\n")
+            );
+        }
+        return code;
+    }
+}
@@ -0,0 +1,44 @@
+use crate::json::*;
+
+//------------------- Reflection ------------------------------
+//
+// #[json(reflect)] generates `impl native_json::Reflect` for every struct
+//
+// fields() lists name, JSON key, declared type and optionality of the fields,
+// get_path / set_path of the trait round-trip through serde_json::Value
+//
+// Name extends Base lists the fields of base first, the base implements `Reflect`
+
+impl Json {
+    pub fn gen_reflect(&self) -> String {
+        let mut code = "".to_owned();
+        for s in self.get_structs() {
+            let mut fields = Vec::new();
+            // the fields of base are flattened
+            if let Some(base) = &s.extends {
+                fields.push(format!(
+                    "fields.extend_from_slice(<{base} as native_json::Reflect>::fields());"
+                ));
+            }
+            for f in &s.fields {
+                // #[cfg] on statements, which is not allowed on array items
+                let cfg = f.attributes.join("\n");
+                fields.push(format!(
+                    "{cfg}fields.push(native_json::FieldInfo {{ name: {:?}, key: {:?}, ty: {:?}, optional: {} }});",
+                    f.field.trim_start_matches("r#"),
+                    f.key,
+                    f.class,
+                    f.optional
+                ));
+            }
+            code += &format!(
+                "impl native_json::Reflect for {} {{\n    fn fields() -> &'static [native_json::FieldInfo] {{\n        \
+                static FIELDS: std::sync::OnceLock<Vec<native_json::FieldInfo>> = std::sync::OnceLock::new();\n        \
+                FIELDS.get_or_init(|| {{\n            #[allow(unused_mut)]\n            let mut fields = Vec::new();\n{}\n            fields\n        }})\n    }}\n}}\n",
+                s.name,
+                fields.join("\n")
+            );
+        }
+        return code;
+    }
+}
@@ -0,0 +1,137 @@
+use crate::json::*;
+use std::str::FromStr;
+use syn::{
+    parse::{Parse, ParseStream},
+    *,
+};
+
+//------------------- JSON-RPC Syntax ------------------------------
+//
+// rpc = rpc name { key: type, ... } -> result
+// result = type | { key: type, ... }
+//
+// The params and the object result are declared as `NameParams` and `NameResult`, the unit struct `Name`
+// implements `native_json::rpc::Method` with the wire name `name`. The omitted params or result is `()`.
+
+enum Returns {
+    Type(String),
+    Object(Json),
+}
+
+pub struct Rpc {
+    name: Ident,
+    params: Option<Json>,
+    returns: Option<Returns>,
+}
+
+// `get_balance` => `GetBalance`
+pub fn camel(name: &str) -> String {
+    let mut class = String::new();
+    for part in name.split('_').filter(|x| !x.is_empty()) {
+        let mut chars = part.chars();
+        if let Some(c) = chars.next() {
+            class.extend(c.to_uppercase());
+            class.push_str(chars.as_str());
+        }
+    }
+    return class;
+}
+
+// the object is declared as the named struct
+pub fn declare(input: ParseStream, name: String) -> Result<Json> {
+    let mut json = Json::new();
+    let value = json.parse_object(input)?;
+    json.get_object_mut(&value).name = name;
+    json.value = Value {
+        t: ValueType::DECLARE,
+        i: value.i,
+    };
+    return Ok(json);
+}
+
+// rpc := `rpc` name ..., a struct named `rpc` is declared otherwise
+pub fn is_rpc(input: &proc_macro::TokenStream) -> bool {
+    use proc_macro::TokenTree;
+    let mut tokens = input.clone().into_iter();
+    return match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(keyword)), Some(TokenTree::Ident(name))) => {
+            keyword.to_string() == "rpc" && name.to_string() != "extends"
+        }
+        _ => false,
+    };
+}
+
+impl Rpc {
+    pub fn get_tokens(&self) -> Result<proc_macro2::TokenStream> {
+        let method = self.name.to_string();
+        let class = camel(&method);
+        let mut tokens = proc_macro2::TokenStream::new();
+        let mut params = "()".to_owned();
+        if let Some(json) = &self.params {
+            tokens.extend(json.get_tokens()?);
+            params = format!("{class}Params");
+        }
+        let result = match &self.returns {
+            Some(Returns::Object(json)) => {
+                tokens.extend(json.get_tokens()?);
+                format!("{class}Result")
+            }
+            Some(Returns::Type(t)) => t.clone(),
+            None => "()".to_owned(),
+        };
+        let code = format!(
+            "#[derive(Debug, Clone, Copy, Default)]\npub struct {class};\n\
+            impl native_json::rpc::Method for {class} {{\n    const NAME: &'static str = {method:?};\n    \
+            type Params = {params};\n    type Result = {result};\n}}\n"
+        );
+        tokens.extend(proc_macro2::TokenStream::from_str(&code)?);
+        return Ok(tokens);
+    }
+}
+
+impl Parse for Rpc {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<Ident>()?;
+        let name: Ident = input.parse()?;
+        let class = camel(&name.to_string());
+        if class.is_empty() {
+            return Err(Error::new(name.span(), "expected method name"));
+        }
+
+        let mut params = None;
+        if input.peek(token::Brace) {
+            params = Some(declare(input, format!("{class}Params"))?);
+        }
+
+        let mut returns = None;
+        if input.peek(Token![->]) {
+            input.parse::<Token![->]>()?;
+            if input.peek(token::Brace) {
+                returns = Some(Returns::Object(declare(input, format!("{class}Result"))?));
+            } else {
+                returns = Some(Returns::Type(parse_type(input)?));
+            }
+        }
+
+        if !input.is_empty() {
+            return Err(input.error("expected `{ params }` or `-> result`"));
+        }
+        return Ok(Rpc {
+            name,
+            params,
+            returns,
+        });
+    }
+}
+
+// type := rust type | [type], `[T]` is Vec<T> as in declaration
+fn parse_type(input: ParseStream) -> Result<String> {
+    if input.peek(token::Bracket) {
+        let inner;
+        bracketed!(inner in input);
+        return Ok(format!("std::vec::Vec<{}>", parse_type(&inner)?));
+    }
+    let tokens: proc_macro2::TokenStream = input.parse()?;
+    parse2::<Type>(tokens.clone())?;
+    return Ok(tokens.to_string());
+}
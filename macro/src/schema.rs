@@ -0,0 +1,142 @@
+use crate::json::*;
+use serde_json::{json, Map, Value as V};
+
+//------------------- JSON Schema ------------------------------
+//
+// #[json(schema)] generates Name::schema(), the JSON Schema (draft 2020-12) of the serialized shape
+//
+// integers      => { "type": "integer" }, unsigned with "minimum": 0
+// floats        => { "type": "number" }
+// String, char  => { "type": "string" }
+// decimals      => { "type": "string" } of decimal number
+// bool          => { "type": "boolean" }
+// Vec<T>, [T]   => { "type": "array", "items": T }
+// Option<T>     => { "anyOf": [T, { "type": "null" }] }
+// bytes         => { "type": "string", "contentEncoding": "base64" }
+// raw           => {}, any value
+// @string       => { "type": "string" }
+// HashMap<K, V> => { "type": "object", "additionalProperties": V }
+// nested struct => { "$ref": "#/$defs/Name" }
+// others        => {}, any value
+
+// schema of a rust type
+fn json_schema(class: &str, structs: &[String]) -> V {
+    const UNSIGNED: [&str; 6] = ["u8", "u16", "u32", "u64", "u128", "usize"];
+    const SIGNED: [&str; 6] = ["i8", "i16", "i32", "i64", "i128", "isize"];
+
+    let class = class.trim_start_matches('&').trim();
+    if class.starts_with('[') && class.ends_with(']') {
+        let item = json_schema(&class[1..class.len() - 1], structs);
+        return json!({ "type": "array", "items": item });
+    }
+
+    let (base, args) = split_generic(class);
+    let name = base_type(base);
+    let arg = |i: usize| {
+        args.get(i)
+            .map(|x| json_schema(x, structs))
+            .unwrap_or(json!({}))
+    };
+
+    if structs.iter().any(|x| x == class) {
+        return json!({ "$ref": format!("#/$defs/{}", class) });
+    } else if UNSIGNED.contains(&name) {
+        return json!({ "type": "integer", "minimum": 0 });
+    } else if SIGNED.contains(&name) {
+        return json!({ "type": "integer" });
+    }
+    return match name {
+        "f32" | "f64" => json!({ "type": "number" }),
+        "bool" => json!({ "type": "boolean" }),
+        "String" | "str" | "Cow" | "PathBuf" => json!({ "type": "string" }),
+        "Decimal" | "BigDecimal" => {
+            json!({ "type": "string", "pattern": "^-?[0-9]+(\\.[0-9]+)?$" })
+        }
+        "char" => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        "DateTime" | "NaiveDateTime" | "OffsetDateTime" => {
+            json!({ "type": "string", "format": "date-time" })
+        }
+        "NaiveDate" => json!({ "type": "string", "format": "date" }),
+        "NaiveTime" => json!({ "type": "string", "format": "time" }),
+        "Uuid" => json!({ "type": "string", "format": "uuid" }),
+        "Ipv4Addr" => json!({ "type": "string", "format": "ipv4" }),
+        "Ipv6Addr" => json!({ "type": "string", "format": "ipv6" }),
+        "IpAddr" | "SocketAddr" | "SocketAddrV4" | "SocketAddrV6" => json!({ "type": "string" }),
+        "Vec" | "VecDeque" | "LinkedList" => json!({ "type": "array", "items": arg(0) }),
+        "HashSet" | "BTreeSet" => {
+            json!({ "type": "array", "items": arg(0), "uniqueItems": true })
+        }
+        "Option" => json!({ "anyOf": [arg(0), { "type": "null" }] }),
+        "HashMap" | "BTreeMap" | "IndexMap" | "OrderedMap" => {
+            json!({ "type": "object", "additionalProperties": arg(1) })
+        }
+        "Box" | "Rc" | "Arc" => arg(0),
+        "()" => json!({ "type": "null" }),
+        _ => json!({}),
+    };
+}
+
+impl Json {
+    pub fn gen_schema(&self) -> String {
+        let structs = self.get_structs();
+        let names: Vec<String> = structs.iter().map(|x| x.name.clone()).collect();
+        let mut defs = Map::new();
+        for s in &structs {
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+            let mut additional = None;
+            for f in &s.fields {
+                let mut schema = json_schema(&f.class, &names);
+                for a in &f.annotations {
+                    let unix = a.args.iter().any(|x| x.starts_with("unix"));
+                    if a.name == "format" && unix {
+                        schema = json!({ "type": "integer" });
+                    } else if a.name == "base64" {
+                        schema = json!({ "type": "string", "contentEncoding": "base64" });
+                    } else if a.name == "string" {
+                        schema = json!({ "type": "string" });
+                    }
+                }
+                // flattened map accepts the undeclared keys
+                if f.annotations.iter().any(|a| a.name == "flatten") {
+                    let value = schema.get("additionalProperties").cloned();
+                    additional = Some(value.unwrap_or(json!(true)));
+                    continue;
+                }
+                let nullable = base_type(split_generic(&f.class).0) == "Option";
+                if !f.default && !nullable {
+                    required.push(json!(f.key));
+                }
+                properties.insert(f.key.clone(), schema);
+            }
+            let mut object = json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+            });
+            if self.has_option("deny_unknown_fields") && additional.is_none() {
+                additional = Some(json!(false));
+            }
+            if let Some(value) = additional {
+                object["additionalProperties"] = value;
+            }
+            defs.insert(s.name.clone(), object);
+        }
+
+        // the root is inlined, nested structs are definitions
+        let root = &structs[0].name;
+        let mut schema = Map::new();
+        schema.insert(
+            "$schema".to_owned(),
+            json!("https://json-schema.org/draft/2020-12/schema"),
+        );
+        schema.insert("title".to_owned(), json!(root));
+        if let Some(V::Object(object)) = defs.remove(root) {
+            schema.extend(object);
+        }
+        if !defs.is_empty() {
+            schema.insert("$defs".to_owned(), V::Object(defs));
+        }
+        return V::Object(schema).to_string();
+    }
+}
@@ -0,0 +1,143 @@
+use crate::json::*;
+
+//------------------- TypeScript ------------------------------
+//
+// #[json(typescript)] generates Name::typescript(), the interfaces of the serialized shape
+//
+// number types  => number
+// String, char  => string
+// bool          => boolean
+// Vec<T>, [T]   => T[]
+// Option<T>     => T | null
+// bytes         => string, base64
+// raw, Value    => unknown
+// @string       => string
+// HashMap<K, V> => Record<string, V>
+// optional      => key?: T
+// others        => the type name, e.g. a declared struct
+
+// TypeScript type of a rust type
+fn ts_type(class: &str) -> String {
+    const NUMBERS: [&str; 14] = [
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+        "f32", "f64",
+    ];
+    const STRINGS: [&str; 19] = [
+        "String",
+        "Decimal",
+        "BigDecimal",
+        "str",
+        "char",
+        "Cow",
+        "DateTime",
+        "NaiveDateTime",
+        "NaiveDate",
+        "NaiveTime",
+        "OffsetDateTime",
+        "Uuid",
+        "PathBuf",
+        "IpAddr",
+        "Ipv4Addr",
+        "Ipv6Addr",
+        "SocketAddr",
+        "SocketAddrV4",
+        "SocketAddrV6",
+    ];
+
+    let class = class.trim_start_matches('&').trim();
+    // slice
+    if class.starts_with('[') && class.ends_with(']') {
+        return format!("{}[]", ts_type(&class[1..class.len() - 1]));
+    }
+
+    let (base, args) = split_generic(class);
+    let name = base_type(base);
+    let arg = |i: usize| {
+        args.get(i)
+            .map(|x| ts_type(x))
+            .unwrap_or("unknown".to_owned())
+    };
+
+    if NUMBERS.contains(&name) {
+        return "number".to_owned();
+    } else if STRINGS.contains(&name) {
+        return "string".to_owned();
+    }
+    return match name {
+        "bool" => "boolean".to_owned(),
+        "Value" | "RawValue" => "unknown".to_owned(),
+        "()" => "null".to_owned(),
+        "Vec" | "VecDeque" | "LinkedList" | "HashSet" | "BTreeSet" => {
+            let item = arg(0);
+            if item.contains(' ') {
+                format!("({})[]", item)
+            } else {
+                format!("{}[]", item)
+            }
+        }
+        "Option" => format!("{} | null", arg(0)),
+        "HashMap" | "BTreeMap" | "IndexMap" | "OrderedMap" => format!("Record<string, {}>", arg(1)),
+        "Box" | "Rc" | "Arc" => arg(0),
+        _ => name.to_owned(),
+    };
+}
+
+// property name, quoted unless it is an identifier
+fn ts_key(key: &str) -> String {
+    let identifier = !key.is_empty()
+        && !key.starts_with(|c: char| c.is_ascii_digit())
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if identifier {
+        return key.to_owned();
+    }
+    return format!("{:?}", key);
+}
+
+impl Json {
+    pub fn gen_typescript(&self) -> String {
+        let mut interfaces = Vec::new();
+        for s in self.get_structs() {
+            let mut lines = Vec::new();
+            let mut extra = Vec::new();
+            for f in &s.fields {
+                let mut t = ts_type(&f.class);
+                for a in &f.annotations {
+                    let unix = a.args.iter().any(|x| x.starts_with("unix"));
+                    if a.name == "format" && unix {
+                        t = "number".to_owned();
+                    } else if a.name == "base64" || a.name == "string" {
+                        t = "string".to_owned();
+                    }
+                }
+                // flattened map is an index signature
+                if f.annotations.iter().any(|a| a.name == "flatten") {
+                    extra.push(t);
+                    continue;
+                }
+                let optional = if f.optional { "?" } else { "" };
+                lines.push(format!("  {}{}: {};", ts_key(&f.key), optional, t));
+            }
+            for t in extra {
+                let value = match t.strip_prefix("Record<string, ") {
+                    Some(v) => v[0..v.len() - 1].to_owned(),
+                    None => "unknown".to_owned(),
+                };
+                lines.push(format!("  [key: string]: {};", value));
+            }
+            let extends = match &s.extends {
+                Some(base) => format!(" extends {}", base_type(base)),
+                None => "".to_owned(),
+            };
+            let interface = format!(
+                "export interface {}{} {{\n{}\n}}\n",
+                s.name,
+                extends,
+                lines.join("\n")
+            );
+            interfaces.push(interface);
+        }
+        return interfaces.join("\n");
+    }
+}
@@ -0,0 +1,157 @@
+use crate::json::*;
+use syn::{Error, Result};
+
+//------------------- Validation ------------------------------
+//
+// annotations of field generate `impl native_json::Validate`, which is empty without rules
+//
+// @range(min, max)  => min <= value <= max
+// @min(n), @max(n)  => n <= value, value <= n
+// @min_len(n)       => n <= length of string or collection
+// @max_len(n)       => length of string or collection <= n
+// @regex("pattern") => string matches the pattern, requires the `regex` feature
+//
+// optional fields are validated unless they are default, `Option<T>` fields unless they are None, nested structs are
+// validated recursively, the patterns are checked at compile time
+//
+// Name extends Base validates the base first
+
+impl Json {
+    pub fn gen_validate(&self) -> Result<String> {
+        let structs = self.get_structs();
+        let names: Vec<_> = structs.iter().map(|x| x.name.clone()).collect();
+        let mut code = "".to_owned();
+        for s in &structs {
+            let mut blocks = Vec::new();
+            // the fields of base are flattened, at the same path
+            if s.extends.is_some() {
+                blocks.push(
+                    "native_json::Validate::validate_into(&self.base, path, errors);".to_owned(),
+                );
+            }
+            for f in &s.fields {
+                // the value of `Option<T>` is checked
+                let (base, args) = split_generic(&f.class);
+                let (class, unwrap) = match base_type(base) == "Option" && args.len() == 1 {
+                    true => (args[0], true),
+                    false => (f.class.as_str(), false),
+                };
+                let mut checks = Vec::new();
+                for a in &f.annotations {
+                    if let Some(check) = self.gen_check(f, class, a)? {
+                        checks.push(check);
+                    }
+                }
+                // nested
+                let (base, args) = split_generic(class);
+                if names.iter().any(|x| x == class) {
+                    checks.push(
+                        "native_json::Validate::validate_into(value, &path, errors);".to_owned(),
+                    );
+                } else if base_type(base) == "Vec"
+                    && args.len() == 1
+                    && names.iter().any(|x| x == args[0])
+                {
+                    checks.push("for (i, item) in value.iter().enumerate() {\n    native_json::Validate::validate_into(item, &format!(\"{}[{}]\", path, i), errors);\n}".to_owned());
+                }
+                if checks.is_empty() {
+                    continue;
+                }
+                let mut body = checks.join("\n");
+                if unwrap {
+                    body = format!("if let Some(value) = value {{\n{body}\n}}");
+                }
+                // optional field is not validated if it is absent
+                if f.optional {
+                    body = format!("if !native_json::is_default(value) {{\n{body}\n}}");
+                }
+                blocks.push(format!(
+                    "{}{{\n    let value = &self.{};\n    let path = native_json::validate::join(path, {:?});\n{}\n}}",
+                    f.attributes.join("\n"),
+                    f.field,
+                    f.key,
+                    body
+                ));
+            }
+            let (path, errors) = if blocks.is_empty() {
+                ("_path", "_errors")
+            } else {
+                ("path", "errors")
+            };
+            code += &format!(
+                "impl native_json::Validate for {} {{\n    fn validate_into(&self, {path}: &str, {errors}: &mut native_json::ValidationErrors) {{\n{}\n    }}\n}}\n",
+                s.name,
+                blocks.join("\n")
+            );
+        }
+        return Ok(code);
+    }
+
+    fn gen_check(&self, f: &Field, class: &str, a: &Annotation) -> Result<Option<String>> {
+        let error = |message: String| Error::new(proc_macro2::Span::call_site(), message);
+        let arg = |i: usize| -> Result<&String> {
+            return a.args.get(i).ok_or_else(|| {
+                error(format!(
+                    "@{} of `{}` expects {} arguments",
+                    a.name,
+                    f.key,
+                    i + 1
+                ))
+            });
+        };
+        let (condition, message) = match a.name.as_str() {
+            "range" => {
+                let (min, max) = (arg(0)?, arg(1)?);
+                (
+                    format!("*value < ({min}) as {class} || *value > ({max}) as {class}"),
+                    format!("must be in range {min}..={max}"),
+                )
+            }
+            "min" => {
+                let min = arg(0)?;
+                (
+                    format!("*value < ({min}) as {class}"),
+                    format!("must be at least {min}"),
+                )
+            }
+            "max" => {
+                let max = arg(0)?;
+                (
+                    format!("*value > ({max}) as {class}"),
+                    format!("must be at most {max}"),
+                )
+            }
+            "min_len" => {
+                let min = arg(0)?;
+                (
+                    format!("native_json::validate::Length::length(value) < {min}"),
+                    format!("length must be at least {min}"),
+                )
+            }
+            "max_len" => {
+                let max = arg(0)?;
+                (
+                    format!("native_json::validate::Length::length(value) > {max}"),
+                    format!("length must be at most {max}"),
+                )
+            }
+            "regex" => {
+                let pattern = arg(0)?;
+                let literal = syn::parse_str::<syn::LitStr>(pattern).map_err(|_| {
+                    error(format!("@regex of `{}` expects a string literal", f.key))
+                })?;
+                if let Err(e) = regex_syntax::Parser::new().parse(&literal.value()) {
+                    return Err(error(format!("invalid pattern of `{}`: {}", f.key, e)));
+                }
+                (
+                    format!("!native_json::validate::is_match(value, {pattern})"),
+                    format!("must match {}", pattern.trim_matches('"')),
+                )
+            }
+            _ => return Ok(None),
+        };
+        return Ok(Some(format!(
+            "if {condition} {{\n    errors.add(&path, {message:?}.to_owned());\n}}"
+        )));
+    }
+}
@@ -0,0 +1,153 @@
+use crate::json::*;
+use crate::rpc::{camel, declare};
+use std::str::FromStr;
+use syn::{
+    ext::IdentExt,
+    parse::{Parse, ParseStream},
+    *,
+};
+
+//------------------- WebSocket Message Syntax ------------------------------
+//
+// ws = ws Name @tag("key") { variant, ... }
+// variant = name | name { key: type, ... }
+//
+// The enum `Name` is tagged by the key, `type` by default, the value of the tag is the variant name as written,
+// the variant is `camel(name)`, e.g. `book_ticker` is `BookTicker` tagged as "book_ticker". The fields of a variant
+// are declared as the struct `NameVariant`, the variant without fields is a unit variant.
+
+struct Variant {
+    name: Ident,
+    payload: Option<Json>,
+}
+
+pub struct Ws {
+    name: Ident,
+    tag: String,
+    variants: Vec<Variant>,
+}
+
+// ws := `ws` name ..., a struct named `ws` is declared otherwise
+pub fn is_ws(input: &proc_macro::TokenStream) -> bool {
+    use proc_macro::TokenTree;
+    let mut tokens = input.clone().into_iter();
+    return match (tokens.next(), tokens.next()) {
+        (Some(TokenTree::Ident(keyword)), Some(TokenTree::Ident(name))) => {
+            keyword.to_string() == "ws" && name.to_string() != "extends"
+        }
+        _ => false,
+    };
+}
+
+impl Ws {
+    pub fn get_tokens(&self) -> Result<proc_macro2::TokenStream> {
+        let name = self.name.to_string();
+        let mut tokens = proc_macro2::TokenStream::new();
+        let mut variants = Vec::new();
+        let mut kinds = Vec::new();
+        let mut conversions = String::new();
+        for variant in &self.variants {
+            let tag = variant.name.unraw().to_string();
+            let class = camel(&tag);
+            let mut rename = String::new();
+            if class != tag {
+                rename = format!("#[serde(rename = {tag:?})]\n    ");
+            }
+            match &variant.payload {
+                Some(json) => {
+                    tokens.extend(json.get_tokens()?);
+                    let payload = format!("{name}{class}");
+                    variants.push(format!("{rename}{class}({payload})"));
+                    kinds.push(format!("{name}::{class}(..) => {tag:?}"));
+                    conversions += &format!(
+                        "impl From<{payload}> for {name} {{\n    fn from(message: {payload}) -> Self {{\n        {name}::{class}(message)\n    }}\n}}\n"
+                    );
+                }
+                None => {
+                    variants.push(format!("{rename}{class}"));
+                    kinds.push(format!("{name}::{class} => {tag:?}"));
+                }
+            }
+        }
+        let code = format!(
+            "#[derive(Serialize, Deserialize, Debug, Clone)]\n#[serde(tag = {tag:?})]\n\
+            pub enum {name} {{\n    {}\n}}\n\
+            impl {name} {{\n    \
+            /// Value of the tag `{}`\n    \
+            pub fn kind(&self) -> &'static str {{\n        match self {{\n            {}\n        }}\n    }}\n    \
+            /// Parse a message, an unknown tag is a type error\n    \
+            pub fn parse_message(text: &str) -> native_json::Result<Self> {{\n        native_json::parse(text)\n    }}\n    \
+            /// Concise JSON of the message with the tag\n    \
+            pub fn encode_message(&self) -> native_json::Result<String> {{\n        native_json::JSON::string(self)\n    }}\n}}\n\
+            {conversions}",
+            variants.join(",\n    "),
+            self.tag,
+            kinds.join(",\n            "),
+            tag = self.tag,
+        );
+        tokens.extend(proc_macro2::TokenStream::from_str(&code)?);
+        return Ok(tokens);
+    }
+}
+
+impl Parse for Ws {
+    fn parse(input: ParseStream) -> Result<Self> {
+        input.parse::<Ident>()?;
+        let name: Ident = input.parse()?;
+
+        // @tag("key")
+        let mut tag = "type".to_owned();
+        if input.peek(Token![@]) {
+            input.parse::<Token![@]>()?;
+            let annotation = Ident::parse_any(input)?;
+            if annotation != "tag" {
+                return Err(Error::new(annotation.span(), "expected @tag(\"key\")"));
+            }
+            let inner;
+            parenthesized!(inner in input);
+            tag = inner.parse::<LitStr>()?.value();
+        }
+
+        let content;
+        braced!(content in input);
+        let mut variants: Vec<Variant> = Vec::new();
+        while !content.is_empty() {
+            let variant = Ident::parse_any(&content)?;
+            let tag = variant.unraw().to_string();
+            if camel(&tag).is_empty() {
+                return Err(Error::new(variant.span(), "expected variant name"));
+            }
+            if variants.iter().any(|x| x.name.unraw() == tag) {
+                return Err(Error::new(variant.span(), "duplicated variant"));
+            }
+            let mut payload = None;
+            if content.peek(token::Brace) {
+                let class = format!("{}{}", name, camel(&tag));
+                payload = Some(declare(&content, class)?);
+            }
+            variants.push(Variant {
+                name: variant,
+                payload,
+            });
+            if !content.peek(Token![,]) {
+                break;
+            }
+            content.parse::<Token![,]>()?;
+        }
+
+        if !content.is_empty() {
+            return Err(content.error("expected `,` or `}`"));
+        }
+        if variants.is_empty() {
+            return Err(Error::new(name.span(), "expected message variants"));
+        }
+        if !input.is_empty() {
+            return Err(input.error("unexpected tokens after the messages"));
+        }
+        return Ok(Ws {
+            name,
+            tag,
+            variants,
+        });
+    }
+}